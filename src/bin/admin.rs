@@ -0,0 +1,221 @@
+use dotenv::dotenv;
+use eletypes_backend::config::app_config::AppConfig;
+use eletypes_backend::config::database::connect_to_mongodb;
+use eletypes_backend::config::logging::init_tracing;
+use eletypes_backend::config::password::configure_argon2;
+use eletypes_backend::constants::DB_NAME;
+use eletypes_backend::errors::app_error::AppError;
+use eletypes_backend::migrations::runner::run_migrations;
+use eletypes_backend::repositories::user_repository::MongoUserRepository;
+use eletypes_backend::services::admin_service::{
+    delete_user, recalculate_high_scores, recompute_completed_tests, reset_password, seed_users,
+    set_user_banned, set_user_email, wipe_high_scores,
+};
+use eletypes_backend::services::user_service::process_user_registration;
+use eletypes_backend::utils::helpers::get_collection;
+use mongodb::Database;
+use std::io::{BufRead, IsTerminal, Write};
+
+const USAGE: &str = "Usage: eletypes-admin <command> [arguments]
+
+Commands:
+  create-user <username>                 Register a user, skipping the captcha
+  delete-user <username>                 Delete a user with their sessions and results
+  ban <username>                         Block logins and sign the user out everywhere
+  unban <username>                       Lift a ban
+  reset-password <username>              Set a new password and sign the user out
+  set-email <username> <email>           Set a verified address for password reset links
+  recompute-completed-tests <username>   Recount completed_tests from stored results
+  wipe-high-scores <username>            Reset every personal best to zero
+  recalc-high-scores <username>          Rebuild personal bests from stored results
+  seed <count>                           Insert synthetic users
+  migrate                                Apply pending migrations
+
+Passwords are read from ELETYPES_ADMIN_PASSWORD when set, otherwise from
+stdin, so that they never show up in the process list or shell history.";
+
+const PASSWORD_ENV: &str = "ELETYPES_ADMIN_PASSWORD";
+
+// Prompts on a terminal; piped input is taken as is, one line
+fn read_password() -> Result<String, AppError> {
+    if let Ok(password) = std::env::var(PASSWORD_ENV) {
+        return Ok(password);
+    }
+
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        std::io::stderr().flush().ok();
+    }
+    let mut password = String::new();
+    stdin
+        .lock()
+        .read_line(&mut password)
+        .map_err(|e| AppError::Internal(format!("Failed to read the password: {}", e)))?;
+
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(AppError::Validation(
+            "Password cannot be empty.".to_string(),
+        ));
+    }
+    Ok(password)
+}
+
+enum Command {
+    CreateUser { username: String },
+    DeleteUser { username: String },
+    Ban { username: String },
+    Unban { username: String },
+    ResetPassword { username: String },
+    SetEmail { username: String, email: String },
+    RecomputeCompletedTests { username: String },
+    WipeHighScores { username: String },
+    RecalcHighScores { username: String },
+    Seed { count: usize },
+    Migrate,
+}
+
+fn parse_command(args: &[String]) -> Option<Command> {
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let username = |value: &str| value.trim().to_string();
+
+    let command = match args.as_slice() {
+        ["create-user", name] => Command::CreateUser {
+            username: username(name),
+        },
+        ["delete-user", name] => Command::DeleteUser {
+            username: username(name),
+        },
+        ["ban", name] => Command::Ban {
+            username: username(name),
+        },
+        ["unban", name] => Command::Unban {
+            username: username(name),
+        },
+        ["reset-password", name] => Command::ResetPassword {
+            username: username(name),
+        },
+        ["set-email", name, email] => Command::SetEmail {
+            username: username(name),
+            email: email.to_string(),
+        },
+        ["recompute-completed-tests", name] => Command::RecomputeCompletedTests {
+            username: username(name),
+        },
+        ["wipe-high-scores", name] => Command::WipeHighScores {
+            username: username(name),
+        },
+        ["recalc-high-scores", name] => Command::RecalcHighScores {
+            username: username(name),
+        },
+        ["seed", count] => Command::Seed {
+            count: count.parse().ok().filter(|count| *count > 0)?,
+        },
+        ["migrate"] => Command::Migrate,
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+async fn run(command: Command, config: &AppConfig, db: &Database) -> Result<(), AppError> {
+    match command {
+        Command::CreateUser { username } => {
+            if username.is_empty() {
+                return Err(AppError::Validation(
+                    "Username cannot be empty.".to_string(),
+                ));
+            }
+            let password = read_password()?;
+            let users = MongoUserRepository::new(get_collection(db.client()));
+            process_user_registration(&users, &username, &password, None, &config.modes).await?;
+            println!("Created user '{}'", username);
+        }
+        Command::DeleteUser { username } => {
+            delete_user(db, &username).await?;
+            println!("Deleted user '{}'", username);
+        }
+        Command::Ban { username } => {
+            set_user_banned(db, &username, true).await?;
+            println!("Banned user '{}'", username);
+        }
+        Command::Unban { username } => {
+            set_user_banned(db, &username, false).await?;
+            println!("Unbanned user '{}'", username);
+        }
+        Command::ResetPassword { username } => {
+            let password = read_password()?;
+            reset_password(db, &username, &password).await?;
+            println!("Reset the password of '{}'", username);
+        }
+        Command::SetEmail { username, email } => {
+            set_user_email(db, &username, &email).await?;
+            println!("Set the email of '{}'", username);
+        }
+        Command::RecomputeCompletedTests { username } => {
+            let completed_tests = recompute_completed_tests(db, &username).await?;
+            println!(
+                "Set completed_tests of '{}' to {}",
+                username, completed_tests
+            );
+        }
+        Command::WipeHighScores { username } => {
+            wipe_high_scores(db, &username, &config.modes).await?;
+            println!("Wiped the high scores of '{}'", username);
+        }
+        Command::RecalcHighScores { username } => {
+            let restored = recalculate_high_scores(db, &username, &config.modes).await?;
+            println!(
+                "Rebuilt the high scores of '{}' from {} mode(s) with results",
+                username, restored
+            );
+        }
+        Command::Seed { count } => {
+            let password = read_password()?;
+            let usernames = seed_users(db, count, &password, &config.modes).await?;
+            for username in usernames.iter() {
+                println!("{}", username);
+            }
+            println!("Seeded {} user(s)", usernames.len());
+        }
+        Command::Migrate => {
+            let applied = run_migrations(db, &config.modes).await?;
+            println!("Applied {} migration(s)", applied.len());
+        }
+    }
+
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() {
+    dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_command(&args) {
+        Some(command) => command,
+        None => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    init_tracing(&config.logging);
+    configure_argon2(&config.password);
+
+    let client = connect_to_mongodb(&config.database).await;
+    let db = client.database(DB_NAME);
+
+    if let Err(err) = run(command, &config, &db).await {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::config::auth::AuthConfig;
+use crate::config::captcha::CaptchaConfig;
+use crate::config::cors::CorsConfig;
+use crate::config::database::{DatabaseConfig, ServerConfig};
+use crate::config::logging::LoggingConfig;
+use crate::config::mail::MailConfig;
+use crate::config::modes::ModeRegistry;
+use crate::config::password::PasswordConfig;
+use crate::config::rate_limit::RateLimitConfig;
+use crate::config::realtime::RealtimeConfig;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::{env, fs};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// Everything the server needs to boot, read once at startup and shared as
+// app data. Values come from the defaults, then an optional TOML file, then
+// environment variables, each overriding the previous one.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub cors: CorsConfig,
+    pub captcha: CaptchaConfig,
+    pub modes: ModeRegistry,
+    pub password: PasswordConfig,
+    pub realtime: RealtimeConfig,
+    pub logging: LoggingConfig,
+    pub rate_limit: RateLimitConfig,
+    pub mail: MailConfig,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in self.problems.iter() {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn read_env(key: &str) -> Option<String> {
+    // An empty variable counts as unset, which is what `KEY=` in .env means
+    env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn read_env_parsed<T: FromStr>(key: &str, problems: &mut Vec<String>) -> Option<T> {
+    let value = read_env(key)?;
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            problems.push(format!("{} has an invalid value: {:?}", key, value));
+            None
+        }
+    }
+}
+
+fn read_env_list(key: &str) -> Option<Vec<String>> {
+    read_env(key).map(|value| {
+        value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+}
+
+impl AppConfig {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+
+        // CONFIG_FILE must exist when given; the default file is optional
+        let mut config = match read_env("CONFIG_FILE") {
+            Some(path) => AppConfig::from_file(Path::new(&path), &mut problems),
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                AppConfig::from_file(Path::new(DEFAULT_CONFIG_FILE), &mut problems)
+            }
+            None => AppConfig::default(),
+        };
+
+        config.apply_env(&mut problems);
+        config.validate(&mut problems);
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError { problems })
+        }
+    }
+
+    fn from_file(path: &Path, problems: &mut Vec<String>) -> Self {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                problems.push(format!("Cannot read {}: {}", path.display(), e));
+                return AppConfig::default();
+            }
+        };
+
+        match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                problems.push(format!("Cannot parse {}: {}", path.display(), e));
+                AppConfig::default()
+            }
+        }
+    }
+
+    fn apply_env(&mut self, problems: &mut Vec<String>) {
+        if let Some(host) = read_env("HOST") {
+            self.server.host = host;
+        }
+        if let Some(port) = read_env_parsed("PORT", problems) {
+            self.server.port = port;
+        }
+        if let Some(proxies) = read_env_list("TRUSTED_PROXIES") {
+            self.server.trusted_proxies = proxies
+                .iter()
+                .filter_map(|proxy| match proxy.parse() {
+                    Ok(ip) => Some(ip),
+                    Err(_) => {
+                        problems.push(format!(
+                            "TRUSTED_PROXIES has an invalid address: {:?}",
+                            proxy
+                        ));
+                        None
+                    }
+                })
+                .collect();
+        }
+
+        if let Some(uri) = read_env("MONGODB_URI") {
+            self.database.uri = uri;
+        }
+        if let Some(run_migrations) = read_env_parsed("RUN_MIGRATIONS", problems) {
+            self.database.run_migrations = run_migrations;
+        }
+        if let Some(ping_timeout_ms) = read_env_parsed("MONGODB_PING_TIMEOUT_MS", problems) {
+            self.database.ping_timeout_ms = ping_timeout_ms;
+        }
+
+        if let Some(jwt_secret) = read_env("JWT_SECRET") {
+            self.auth.jwt_secret = jwt_secret;
+        }
+        if let Some(ttl) = read_env_parsed("PASSWORD_RESET_TTL_MINUTES", problems) {
+            self.auth.password_reset_ttl_minutes = ttl;
+        }
+        if let Some(secs) = read_env_parsed("PASSWORD_RESET_RESEND_SECS", problems) {
+            self.auth.password_reset_resend_secs = secs;
+        }
+        if let Some(ttl) = read_env_parsed("EMAIL_VERIFICATION_TTL_HOURS", problems) {
+            self.auth.email_verification_ttl_hours = ttl;
+        }
+        if let Some(secs) = read_env_parsed("EMAIL_VERIFICATION_RESEND_SECS", problems) {
+            self.auth.email_verification_resend_secs = secs;
+        }
+
+        if let Some(origins) = read_env_list("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = origins;
+        }
+
+        if let Some(provider) = read_env_parsed("CAPTCHA_PROVIDER", problems) {
+            self.captcha.provider = provider;
+        }
+        // SECRET_KEY is the historical name of the reCAPTCHA secret
+        if let Some(secret) = read_env("CAPTCHA_SECRET").or_else(|| read_env("SECRET_KEY")) {
+            self.captcha.secret = secret;
+        }
+        if let Some(min_score) = read_env_parsed("RECAPTCHA_MIN_SCORE", problems) {
+            self.captcha.min_score = min_score;
+        }
+        if let Some(timeout_ms) = read_env_parsed("CAPTCHA_TIMEOUT_MS", problems) {
+            self.captcha.timeout_ms = timeout_ms;
+        }
+        if let Some(fixed_result) = read_env_parsed("CAPTCHA_FIXED_RESULT", problems) {
+            self.captcha.fixed_result = fixed_result;
+        }
+
+        if let Some(languages) = read_env_list("MODE_LANGUAGES") {
+            self.modes.languages = languages;
+        }
+        if let Some(difficulties) = read_env_list("MODE_DIFFICULTIES") {
+            self.modes.difficulties = difficulties;
+        }
+        if let Some(durations) = read_env_list("MODE_DURATIONS") {
+            self.modes.durations = durations;
+        }
+
+        if let Some(memory_kib) = read_env_parsed("ARGON2_MEMORY_KIB", problems) {
+            self.password.memory_kib = memory_kib;
+        }
+        if let Some(iterations) = read_env_parsed("ARGON2_ITERATIONS", problems) {
+            self.password.iterations = iterations;
+        }
+        if let Some(parallelism) = read_env_parsed("ARGON2_PARALLELISM", problems) {
+            self.password.parallelism = parallelism;
+        }
+
+        if let Some(top_n) = read_env_parsed("LEADERBOARD_PUSH_TOP_N", problems) {
+            self.realtime.leaderboard_push_top_n = top_n;
+        }
+
+        if let Some(format) = read_env_parsed("LOG_FORMAT", problems) {
+            self.logging.format = format;
+        }
+        if let Some(level) = read_env("LOG_LEVEL") {
+            self.logging.level = level;
+        }
+
+        if let Some(enabled) = read_env_parsed("RATE_LIMIT_ENABLED", problems) {
+            self.rate_limit.enabled = enabled;
+        }
+        let lockout = &mut self.rate_limit.lockout;
+        if let Some(enabled) = read_env_parsed("LOGIN_LOCKOUT_ENABLED", problems) {
+            lockout.enabled = enabled;
+        }
+        if let Some(max_failures) = read_env_parsed("LOGIN_LOCKOUT_MAX_FAILURES", problems) {
+            lockout.max_failures = max_failures;
+        }
+        if let Some(max_account_failures) =
+            read_env_parsed("LOGIN_LOCKOUT_MAX_ACCOUNT_FAILURES", problems)
+        {
+            lockout.max_account_failures = max_account_failures;
+        }
+        if let Some(base_lockout_secs) = read_env_parsed("LOGIN_LOCKOUT_BASE_SECS", problems) {
+            lockout.base_lockout_secs = base_lockout_secs;
+        }
+        if let Some(max_lockout_secs) = read_env_parsed("LOGIN_LOCKOUT_MAX_SECS", problems) {
+            lockout.max_lockout_secs = max_lockout_secs;
+        }
+
+        if let Some(provider) = read_env_parsed("MAIL_PROVIDER", problems) {
+            self.mail.provider = provider;
+        }
+        if let Some(from) = read_env("MAIL_FROM") {
+            self.mail.from = from;
+        }
+        if let Some(app_url) = read_env("APP_URL") {
+            self.mail.app_url = app_url;
+        }
+        if let Some(dir) = read_env("MAIL_DIR") {
+            self.mail.dir = dir;
+        }
+        if let Some(host) = read_env("SMTP_HOST") {
+            self.mail.smtp_host = host;
+        }
+        if let Some(port) = read_env_parsed("SMTP_PORT", problems) {
+            self.mail.smtp_port = port;
+        }
+        if let Some(username) = read_env("SMTP_USERNAME") {
+            self.mail.smtp_username = username;
+        }
+        if let Some(password) = read_env("SMTP_PASSWORD") {
+            self.mail.smtp_password = password;
+        }
+    }
+
+    // Problems with the loaded values, also re-checked by the readiness probe
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        self.validate(&mut problems);
+        problems
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        self.server.validate(problems);
+        self.database.validate(problems);
+        self.auth.validate(problems);
+        self.cors.validate(problems);
+        self.captcha.validate(problems);
+        self.modes.validate(problems);
+        self.password.validate(problems);
+        self.realtime.validate(problems);
+        self.logging.validate(problems);
+        self.rate_limit.validate(problems);
+        self.mail.validate(problems);
+    }
+}
@@ -0,0 +1,65 @@
+use serde::Deserialize;
+
+const DEFAULT_PASSWORD_RESET_TTL_MINUTES: i64 = 30;
+const DEFAULT_PASSWORD_RESET_RESEND_SECS: i64 = 60;
+const DEFAULT_EMAIL_VERIFICATION_TTL_HOURS: i64 = 24;
+const DEFAULT_EMAIL_VERIFICATION_RESEND_SECS: i64 = 60;
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // Signs access tokens and typing test sessions (HS256)
+    pub jwt_secret: String,
+    // How long a password reset link stays valid
+    pub password_reset_ttl_minutes: i64,
+    // Minimum wait between two reset emails to the same user
+    pub password_reset_resend_secs: i64,
+    // How long an email verification link stays valid
+    pub email_verification_ttl_hours: i64,
+    // Minimum wait between two verification emails to the same user
+    pub email_verification_resend_secs: i64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            jwt_secret: String::new(),
+            password_reset_ttl_minutes: DEFAULT_PASSWORD_RESET_TTL_MINUTES,
+            password_reset_resend_secs: DEFAULT_PASSWORD_RESET_RESEND_SECS,
+            email_verification_ttl_hours: DEFAULT_EMAIL_VERIFICATION_TTL_HOURS,
+            email_verification_resend_secs: DEFAULT_EMAIL_VERIFICATION_RESEND_SECS,
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.jwt_secret.is_empty() {
+            problems.push("auth.jwt_secret (JWT_SECRET) must be set".to_string());
+        }
+        if self.password_reset_ttl_minutes <= 0 {
+            problems.push(
+                "auth.password_reset_ttl_minutes (PASSWORD_RESET_TTL_MINUTES) must be greater than 0"
+                    .to_string(),
+            );
+        }
+        if self.password_reset_resend_secs < 0 {
+            problems.push(
+                "auth.password_reset_resend_secs (PASSWORD_RESET_RESEND_SECS) cannot be negative"
+                    .to_string(),
+            );
+        }
+        if self.email_verification_ttl_hours <= 0 {
+            problems.push(
+                "auth.email_verification_ttl_hours (EMAIL_VERIFICATION_TTL_HOURS) must be greater than 0"
+                    .to_string(),
+            );
+        }
+        if self.email_verification_resend_secs < 0 {
+            problems.push(
+                "auth.email_verification_resend_secs (EMAIL_VERIFICATION_RESEND_SECS) cannot be negative"
+                    .to_string(),
+            );
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::services::captcha_service::{
+    CaptchaVerifier, FixedCaptchaVerifier, HCaptchaVerifier, RecaptchaV2Verifier,
+    RecaptchaV3Verifier, TurnstileVerifier,
+};
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_RECAPTCHA_MIN_SCORE: f32 = 0.5;
+const DEFAULT_CAPTCHA_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaProvider {
+    RecaptchaV2,
+    RecaptchaV3,
+    Hcaptcha,
+    Turnstile,
+    // Accepts every token
+    Noop,
+    // Always answers with `fixed_result`
+    Fixed,
+}
+
+impl FromStr for CaptchaProvider {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "recaptcha_v2" => Ok(CaptchaProvider::RecaptchaV2),
+            "recaptcha_v3" => Ok(CaptchaProvider::RecaptchaV3),
+            "hcaptcha" => Ok(CaptchaProvider::Hcaptcha),
+            "turnstile" => Ok(CaptchaProvider::Turnstile),
+            "noop" => Ok(CaptchaProvider::Noop),
+            "fixed" => Ok(CaptchaProvider::Fixed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl CaptchaProvider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CaptchaProvider::RecaptchaV2 => "recaptcha_v2",
+            CaptchaProvider::RecaptchaV3 => "recaptcha_v3",
+            CaptchaProvider::Hcaptcha => "hcaptcha",
+            CaptchaProvider::Turnstile => "turnstile",
+            CaptchaProvider::Noop => "noop",
+            CaptchaProvider::Fixed => "fixed",
+        }
+    }
+
+    fn needs_secret(self) -> bool {
+        !matches!(self, CaptchaProvider::Noop | CaptchaProvider::Fixed)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaFixedResult {
+    Pass,
+    Fail,
+}
+
+impl FromStr for CaptchaFixedResult {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pass" => Ok(CaptchaFixedResult::Pass),
+            "fail" => Ok(CaptchaFixedResult::Fail),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptchaConfig {
+    pub provider: CaptchaProvider,
+    pub secret: String,
+    // reCAPTCHA v3 only; scores range from 0.0 (bot) to 1.0 (human)
+    pub min_score: f32,
+    pub fixed_result: CaptchaFixedResult,
+    // A siteverify call that takes longer fails the login or sign-up
+    pub timeout_ms: u64,
+}
+
+impl Default for CaptchaConfig {
+    fn default() -> Self {
+        CaptchaConfig {
+            provider: CaptchaProvider::RecaptchaV2,
+            secret: String::new(),
+            min_score: DEFAULT_RECAPTCHA_MIN_SCORE,
+            fixed_result: CaptchaFixedResult::Pass,
+            timeout_ms: DEFAULT_CAPTCHA_TIMEOUT_MS,
+        }
+    }
+}
+
+impl CaptchaConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.provider.needs_secret() && self.secret.is_empty() {
+            problems.push(format!(
+                "captcha.secret (CAPTCHA_SECRET) must be set for provider '{}'",
+                self.provider.as_str()
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.min_score) {
+            problems.push(
+                "captcha.min_score (RECAPTCHA_MIN_SCORE) must be a number between 0.0 and 1.0"
+                    .to_string(),
+            );
+        }
+        if self.timeout_ms == 0 {
+            problems
+                .push("captcha.timeout_ms (CAPTCHA_TIMEOUT_MS) must be greater than 0".to_string());
+        }
+    }
+}
+
+fn create_http_client(config: &CaptchaConfig) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_millis(config.timeout_ms))
+        .build()
+        .expect("Failed to build the captcha HTTP client")
+}
+
+pub fn load_captcha_verifier(config: &CaptchaConfig) -> Arc<dyn CaptchaVerifier> {
+    let secret = config.secret.clone();
+
+    match config.provider {
+        CaptchaProvider::RecaptchaV2 => {
+            Arc::new(RecaptchaV2Verifier::new(create_http_client(config), secret))
+        }
+        CaptchaProvider::RecaptchaV3 => Arc::new(RecaptchaV3Verifier::new(
+            create_http_client(config),
+            secret,
+            config.min_score,
+        )),
+        CaptchaProvider::Hcaptcha => {
+            Arc::new(HCaptchaVerifier::new(create_http_client(config), secret))
+        }
+        CaptchaProvider::Turnstile => {
+            Arc::new(TurnstileVerifier::new(create_http_client(config), secret))
+        }
+        // Fine for development and tests, but nothing stops bots in production
+        CaptchaProvider::Noop => {
+            tracing::warn!("Captcha provider is 'noop': every captcha token is accepted");
+            Arc::new(FixedCaptchaVerifier::new(true))
+        }
+        CaptchaProvider::Fixed => {
+            let pass = config.fixed_result == CaptchaFixedResult::Pass;
+            tracing::warn!(
+                pass,
+                "Captcha provider is 'fixed': tokens are not checked with any provider"
+            );
+            Arc::new(FixedCaptchaVerifier::new(pass))
+        }
+    }
+}
@@ -1,12 +1,57 @@
 use actix_cors::Cors;
 use actix_web::http;
+use serde::Deserialize;
 
-pub fn configure_cors() -> Cors {
-    Cors::default()
-        .allowed_origin("http://localhost:5173")
-        .allowed_origin("http://localhost:3000")
-        .allowed_origin("https://eletypes.com")
-        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
+const DEFAULT_ALLOWED_ORIGINS: [&str; 3] = [
+    "http://localhost:5173",
+    "http://localhost:3000",
+    "https://eletypes.com",
+];
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: DEFAULT_ALLOWED_ORIGINS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.allowed_origins.is_empty() {
+            problems.push(
+                "cors.allowed_origins (CORS_ALLOWED_ORIGINS) must contain at least one origin"
+                    .to_string(),
+            );
+        }
+        for origin in self.allowed_origins.iter() {
+            let has_scheme = origin.starts_with("http://") || origin.starts_with("https://");
+            if !has_scheme || origin.ends_with('/') {
+                problems.push(format!(
+                    "cors.allowed_origins (CORS_ALLOWED_ORIGINS) contains an invalid origin: {:?}",
+                    origin
+                ));
+            }
+        }
+    }
+}
+
+pub fn configure_cors(config: &CorsConfig) -> Cors {
+    let cors = config
+        .allowed_origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin));
+
+    cors.allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
         .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
         .allowed_headers(vec![actix_web::http::header::CONTENT_TYPE])
         .supports_credentials()
@@ -1,9 +1,82 @@
 use mongodb::Client;
+use serde::Deserialize;
+use std::net::IpAddr;
 
-pub async fn connect_to_mongodb() -> Client {
-    let uri = std::env::var("MONGODB_URI").unwrap_or_else(|_| "mongodb://localhost:27017".into());
+const DEFAULT_MONGODB_URI: &str = "mongodb://localhost:27017";
+const DEFAULT_HOST: &str = "0.0.0.0";
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_PING_TIMEOUT_MS: u64 = 2000;
 
-    match Client::with_uri_str(uri).await {
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub uri: String,
+    // Apply pending migrations before serving; otherwise run `migrate` by hand
+    pub run_migrations: bool,
+    // How long /readyz waits for MongoDB to answer a ping
+    pub ping_timeout_ms: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            uri: DEFAULT_MONGODB_URI.to_string(),
+            run_migrations: true,
+            ping_timeout_ms: DEFAULT_PING_TIMEOUT_MS,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if !self.uri.starts_with("mongodb://") && !self.uri.starts_with("mongodb+srv://") {
+            problems.push(
+                "database.uri (MONGODB_URI) must start with mongodb:// or mongodb+srv://"
+                    .to_string(),
+            );
+        }
+        if self.ping_timeout_ms == 0 {
+            problems.push(
+                "database.ping_timeout_ms (MONGODB_PING_TIMEOUT_MS) must be greater than 0"
+                    .to_string(),
+            );
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // Peers whose X-Forwarded-For is believed. Anyone else is identified by
+    // the address they connect from.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.host.trim().is_empty() {
+            problems.push("server.host (HOST) cannot be empty".to_string());
+        }
+        if self.port == 0 {
+            problems.push("server.port (PORT) must be between 1 and 65535".to_string());
+        }
+    }
+}
+
+pub async fn connect_to_mongodb(config: &DatabaseConfig) -> Client {
+    match Client::with_uri_str(&config.uri).await {
         Ok(client) => client,
         Err(e) => {
             panic!("Failed to connect to MongoDB: {:?}", e);
@@ -11,7 +84,6 @@ pub async fn connect_to_mongodb() -> Client {
     }
 }
 
-pub fn get_server_address() -> String {
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    format!("0.0.0.0:{}", port)
+pub fn get_server_address(config: &ServerConfig) -> String {
+    format!("{}:{}", config.host, config.port)
 }
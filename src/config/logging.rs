@@ -0,0 +1,74 @@
+use serde::Deserialize;
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    // Human readable, for local development
+    Pretty,
+    // One JSON object per line, for log shippers
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    // An EnvFilter directive such as "info" or "info,eletypes_backend=debug"
+    pub level: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            format: LogFormat::Pretty,
+            level: DEFAULT_LOG_LEVEL.to_string(),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if let Err(e) = EnvFilter::try_new(&self.level) {
+            problems.push(format!(
+                "logging.level (LOG_LEVEL) is not a valid filter: {}",
+                e
+            ));
+        }
+    }
+}
+
+pub fn init_tracing(config: &LoggingConfig) {
+    let filter = EnvFilter::try_new(&config.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    let result = match config.format {
+        LogFormat::Pretty => builder.try_init(),
+        LogFormat::Json => builder
+            .json()
+            .flatten_event(true)
+            // The whole span list, so events deep inside a request keep its id
+            .with_current_span(false)
+            .with_span_list(true)
+            .try_init(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Tracing was already initialized: {}", e);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::services::mailer_service::{FileMailer, LogMailer, Mailer, SmtpMailer};
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const DEFAULT_FROM: &str = "Eletypes <no-reply@eletypes.com>";
+const DEFAULT_APP_URL: &str = "http://localhost:5173";
+const DEFAULT_MAIL_DIR: &str = "mail";
+const DEFAULT_SMTP_PORT: u16 = 587;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailProvider {
+    Smtp,
+    // Writes every message to a file in `dir`
+    File,
+    // Logs every message, links included; local development only
+    Log,
+}
+
+impl FromStr for MailProvider {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "smtp" => Ok(MailProvider::Smtp),
+            "file" => Ok(MailProvider::File),
+            "log" => Ok(MailProvider::Log),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub provider: MailProvider,
+    pub from: String,
+    // Frontend base URL that links in emails point to
+    pub app_url: String,
+    pub dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig {
+            // Log would write reset links into the logs, so it has to be chosen
+            provider: MailProvider::File,
+            from: DEFAULT_FROM.to_string(),
+            app_url: DEFAULT_APP_URL.to_string(),
+            dir: DEFAULT_MAIL_DIR.to_string(),
+            smtp_host: String::new(),
+            smtp_port: DEFAULT_SMTP_PORT,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+        }
+    }
+}
+
+impl MailConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.from.parse::<lettre::message::Mailbox>().is_err() {
+            problems.push(format!(
+                "mail.from (MAIL_FROM) is not a valid address: {:?}",
+                self.from
+            ));
+        }
+        if !self.app_url.starts_with("http://") && !self.app_url.starts_with("https://") {
+            problems.push("mail.app_url (APP_URL) must start with http:// or https://".to_string());
+        }
+        match self.provider {
+            MailProvider::Smtp if self.smtp_host.is_empty() => problems
+                .push("mail.smtp_host (SMTP_HOST) must be set for provider 'smtp'".to_string()),
+            MailProvider::File if self.dir.is_empty() => {
+                problems.push("mail.dir (MAIL_DIR) must be set for provider 'file'".to_string())
+            }
+            _ => {}
+        }
+    }
+
+    pub fn create_link(&self, path: &str, token: &str) -> String {
+        format!(
+            "{}{}?token={}",
+            self.app_url.trim_end_matches('/'),
+            path,
+            token
+        )
+    }
+}
+
+pub fn load_mailer(config: &MailConfig) -> Arc<dyn Mailer> {
+    match config.provider {
+        MailProvider::Smtp => Arc::new(SmtpMailer::new(config)),
+        MailProvider::File => Arc::new(FileMailer::new(config.from.clone(), config.dir.clone())),
+        // Anyone who can read the logs can follow the links in them
+        MailProvider::Log => {
+            tracing::warn!(
+                "Mail provider is 'log': password reset and verification links are logged"
+            );
+            Arc::new(LogMailer)
+        }
+    }
+}
@@ -1,2 +1,11 @@
+pub mod app_config;
+pub mod auth;
+pub mod captcha;
 pub mod cors;
 pub mod database;
+pub mod logging;
+pub mod mail;
+pub mod modes;
+pub mod password;
+pub mod rate_limit;
+pub mod realtime;
@@ -0,0 +1,129 @@
+use crate::constants::MAX_LEADERBOARD_MODES;
+use crate::models::mode::GameMode;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LANGUAGES: [&str; 2] = ["english", "chinese"];
+const DEFAULT_DIFFICULTIES: [&str; 2] = ["hard", "normal"];
+const DEFAULT_DURATIONS: [&str; 4] = ["15", "30", "60", "90"];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModeRegistry {
+    pub languages: Vec<String>,
+    pub difficulties: Vec<String>,
+    pub durations: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvalidMode {
+    pub field: &'static str,
+    pub value: String,
+    pub allowed: Vec<String>,
+}
+
+impl Default for ModeRegistry {
+    fn default() -> Self {
+        ModeRegistry {
+            languages: DEFAULT_LANGUAGES.iter().map(|s| s.to_string()).collect(),
+            difficulties: DEFAULT_DIFFICULTIES.iter().map(|s| s.to_string()).collect(),
+            durations: DEFAULT_DURATIONS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl ModeRegistry {
+    pub fn resolve(
+        &self,
+        language: &str,
+        difficulty: &str,
+        duration: &str,
+    ) -> Result<GameMode, InvalidMode> {
+        self.validate_language(language)?;
+        self.validate_difficulty(difficulty)?;
+        self.validate_duration(duration)?;
+
+        Ok(GameMode::new(language, difficulty, duration))
+    }
+
+    pub fn validate_language(&self, language: &str) -> Result<(), InvalidMode> {
+        validate_key("language", language, &self.languages)
+    }
+
+    pub fn validate_difficulty(&self, difficulty: &str) -> Result<(), InvalidMode> {
+        validate_key("difficulty", difficulty, &self.difficulties)
+    }
+
+    pub fn validate_duration(&self, duration: &str) -> Result<(), InvalidMode> {
+        validate_key("duration", duration, &self.durations)
+    }
+
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        validate_keys(
+            "modes.languages (MODE_LANGUAGES)",
+            &self.languages,
+            problems,
+        );
+        validate_keys(
+            "modes.difficulties (MODE_DIFFICULTIES)",
+            &self.difficulties,
+            problems,
+        );
+        validate_keys(
+            "modes.durations (MODE_DURATIONS)",
+            &self.durations,
+            problems,
+        );
+
+        if let Some(invalid) = self
+            .durations
+            .iter()
+            .find(|duration| !matches!(duration.parse::<u32>(), Ok(seconds) if seconds > 0))
+        {
+            problems.push(format!(
+                "modes.durations (MODE_DURATIONS) contains an invalid duration: {:?}",
+                invalid
+            ));
+        }
+
+        let mode_count = self.languages.len() * self.difficulties.len() * self.durations.len();
+        if mode_count > MAX_LEADERBOARD_MODES {
+            problems.push(format!(
+                "modes allow {} language/difficulty/duration combinations, but at most {} \
+                 fit in the leaderboard indexes",
+                mode_count, MAX_LEADERBOARD_MODES
+            ));
+        }
+    }
+}
+
+fn validate_key(field: &'static str, value: &str, allowed: &[String]) -> Result<(), InvalidMode> {
+    if allowed.iter().any(|key| key == value) {
+        Ok(())
+    } else {
+        Err(InvalidMode {
+            field,
+            value: value.to_string(),
+            allowed: allowed.to_vec(),
+        })
+    }
+}
+
+// Keys end up in BSON field paths, so only allow plain identifiers
+fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn validate_keys(name: &str, values: &[String], problems: &mut Vec<String>) {
+    if values.is_empty() {
+        problems.push(format!("{} must contain at least one entry", name));
+    }
+    if let Some(invalid) = values.iter().find(|value| !is_safe_key(value)) {
+        problems.push(format!(
+            "{} contains an invalid mode key: {:?}",
+            name, invalid
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+// OWASP recommended baseline for Argon2id (19 MiB, 2 iterations, 1 lane)
+const DEFAULT_MEMORY_KIB: u32 = 19 * 1024;
+const DEFAULT_ITERATIONS: u32 = 2;
+const DEFAULT_PARALLELISM: u32 = 1;
+
+static ARGON2_PARAMS: OnceLock<Params> = OnceLock::new();
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        PasswordConfig {
+            memory_kib: DEFAULT_MEMORY_KIB,
+            iterations: DEFAULT_ITERATIONS,
+            parallelism: DEFAULT_PARALLELISM,
+        }
+    }
+}
+
+impl PasswordConfig {
+    fn to_params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+    }
+
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if let Err(e) = self.to_params() {
+            problems.push(format!(
+                "password (ARGON2_MEMORY_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM) has invalid Argon2 parameters: {}",
+                e
+            ));
+        }
+    }
+}
+
+// Hashing runs on blocking threads far away from app data, so the validated
+// parameters are installed once at startup. Until then the defaults apply.
+pub fn configure_argon2(config: &PasswordConfig) {
+    match config.to_params() {
+        Ok(params) => {
+            if ARGON2_PARAMS.set(params).is_err() {
+                tracing::warn!("Argon2 parameters were already configured");
+            }
+        }
+        Err(e) => {
+            panic!("Invalid Argon2 parameters: {:?}", e);
+        }
+    }
+}
+
+pub fn get_argon2_params() -> &'static Params {
+    ARGON2_PARAMS.get_or_init(|| {
+        PasswordConfig::default()
+            .to_params()
+            .expect("default Argon2 parameters are valid")
+    })
+}
+
+pub fn get_argon2() -> Argon2<'static> {
+    Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        get_argon2_params().clone(),
+    )
+}
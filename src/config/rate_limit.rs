@@ -0,0 +1,160 @@
+use actix_web::http::Method;
+use serde::Deserialize;
+use std::str::FromStr;
+
+const DEFAULT_MAX_FAILURES: u32 = 5;
+const DEFAULT_MAX_ACCOUNT_FAILURES: u32 = 20;
+const DEFAULT_BASE_LOCKOUT_SECS: u64 = 30;
+const DEFAULT_MAX_LOCKOUT_SECS: u64 = 15 * 60;
+const DEFAULT_FAILURE_WINDOW_SECS: u64 = 15 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    Ip,
+    // Signed-in username, falling back to the IP for anonymous requests
+    Username,
+}
+
+// A token bucket per key: up to `burst` requests at once, refilled at
+// `per_minute`. Every policy matching a request applies.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    // Route pattern as registered, e.g. "/leaderboard/rank/{username}"
+    pub route: String,
+    // Any method when left out
+    #[serde(default)]
+    pub method: Option<String>,
+    pub key: RateLimitKey,
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+impl RateLimitPolicy {
+    fn new(method: &str, route: &str, key: RateLimitKey, burst: u32, per_minute: u32) -> Self {
+        RateLimitPolicy {
+            route: route.to_string(),
+            method: Some(method.to_string()),
+            key,
+            burst,
+            per_minute,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LockoutConfig {
+    pub enabled: bool,
+    // Failed logins in a row from one address before the account is locked
+    // for that address
+    pub max_failures: u32,
+    // Failed logins in a row from any address before the account is locked
+    // everywhere, so rotating addresses does not allow unlimited guesses
+    pub max_account_failures: u32,
+    // The first lockout lasts this long and every further one twice as long
+    pub base_lockout_secs: u64,
+    pub max_lockout_secs: u64,
+    // Failures older than this are forgotten
+    pub failure_window_secs: u64,
+}
+
+impl Default for LockoutConfig {
+    fn default() -> Self {
+        LockoutConfig {
+            enabled: true,
+            max_failures: DEFAULT_MAX_FAILURES,
+            max_account_failures: DEFAULT_MAX_ACCOUNT_FAILURES,
+            base_lockout_secs: DEFAULT_BASE_LOCKOUT_SECS,
+            max_lockout_secs: DEFAULT_MAX_LOCKOUT_SECS,
+            failure_window_secs: DEFAULT_FAILURE_WINDOW_SECS,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub policies: Vec<RateLimitPolicy>,
+    pub lockout: LockoutConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            policies: vec![
+                RateLimitPolicy::new("POST", "/login", RateLimitKey::Ip, 10, 10),
+                RateLimitPolicy::new("POST", "/sign_up", RateLimitKey::Ip, 5, 5),
+                RateLimitPolicy::new("POST", "/tests/finish", RateLimitKey::Username, 20, 30),
+                RateLimitPolicy::new("GET", "/get_leaderboard_stats", RateLimitKey::Ip, 30, 120),
+                // Every call pings the database
+                RateLimitPolicy::new("GET", "/readyz", RateLimitKey::Ip, 10, 60),
+                RateLimitPolicy::new("POST", "/password_reset/request", RateLimitKey::Ip, 5, 5),
+                RateLimitPolicy::new("POST", "/password_reset/confirm", RateLimitKey::Ip, 10, 10),
+                RateLimitPolicy::new("PUT", "/me/email", RateLimitKey::Username, 5, 5),
+                RateLimitPolicy::new(
+                    "POST",
+                    "/email_verification/confirm",
+                    RateLimitKey::Ip,
+                    10,
+                    10,
+                ),
+            ],
+            lockout: LockoutConfig::default(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        for policy in self.policies.iter() {
+            if !policy.route.starts_with('/') {
+                problems.push(format!(
+                    "rate_limit.policies route must start with '/': {:?}",
+                    policy.route
+                ));
+            }
+            if let Some(method) = &policy.method {
+                if Method::from_str(method).is_err() {
+                    problems.push(format!(
+                        "rate_limit.policies has an invalid method for {}: {:?}",
+                        policy.route, method
+                    ));
+                }
+            }
+            if policy.burst == 0 || policy.per_minute == 0 {
+                problems.push(format!(
+                    "rate_limit.policies for {} needs a burst and per_minute greater than 0",
+                    policy.route
+                ));
+            }
+        }
+
+        let lockout = &self.lockout;
+        if lockout.max_failures == 0 {
+            problems.push(
+                "rate_limit.lockout.max_failures (LOGIN_LOCKOUT_MAX_FAILURES) must be greater than 0"
+                    .to_string(),
+            );
+        }
+        if lockout.max_account_failures < lockout.max_failures {
+            problems.push(
+                "rate_limit.lockout.max_account_failures (LOGIN_LOCKOUT_MAX_ACCOUNT_FAILURES) must be at least max_failures"
+                    .to_string(),
+            );
+        }
+        if lockout.base_lockout_secs == 0 || lockout.base_lockout_secs > lockout.max_lockout_secs {
+            problems.push(
+                "rate_limit.lockout.base_lockout_secs (LOGIN_LOCKOUT_BASE_SECS) must be between 1 and max_lockout_secs (LOGIN_LOCKOUT_MAX_SECS)"
+                    .to_string(),
+            );
+        }
+        if lockout.failure_window_secs == 0 {
+            problems
+                .push("rate_limit.lockout.failure_window_secs must be greater than 0".to_string());
+        }
+    }
+}
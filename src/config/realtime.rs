@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+const DEFAULT_LEADERBOARD_PUSH_TOP_N: u64 = 10;
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RealtimeConfig {
+    // Only personal bests that land within the top N of a board are pushed
+    pub leaderboard_push_top_n: u64,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        RealtimeConfig {
+            leaderboard_push_top_n: DEFAULT_LEADERBOARD_PUSH_TOP_N,
+        }
+    }
+}
+
+impl RealtimeConfig {
+    pub fn validate(&self, problems: &mut Vec<String>) {
+        if self.leaderboard_push_top_n == 0 {
+            problems.push(
+                "realtime.leaderboard_push_top_n (LEADERBOARD_PUSH_TOP_N) must be a positive integer"
+                    .to_string(),
+            );
+        }
+    }
+}
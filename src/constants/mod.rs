@@ -1,2 +1,24 @@
 pub const DB_NAME: &str = "eletypes_backend";
 pub const COLL_NAME: &str = "users";
+pub const TEST_SESSIONS_COLL_NAME: &str = "test_sessions";
+pub const TEST_RESULTS_COLL_NAME: &str = "test_results";
+pub const RACE_RESULTS_COLL_NAME: &str = "race_results";
+pub const SESSIONS_COLL_NAME: &str = "sessions";
+pub const PASSWORD_RESETS_COLL_NAME: &str = "password_resets";
+pub const EMAIL_VERIFICATIONS_COLL_NAME: &str = "email_verifications";
+// Unique index on verified addresses in users.email, named so duplicate key
+// errors can tell it apart from the username index
+pub const EMAIL_INDEX_NAME: &str = "email_unique";
+// Every mode gets its own leaderboard index on users. MongoDB allows 64
+// indexes per collection and _id, username_unique and email_unique take 3.
+pub const MAX_LEADERBOARD_MODES: usize = 61;
+pub const MIGRATIONS_COLL_NAME: &str = "_migrations";
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+// Refresh tokens expire after this long without being used
+pub const REFRESH_TOKEN_DAYS: i64 = 7;
+// Sockets are pinged on this interval and dropped when nothing, pongs
+// included, came back within the timeout
+pub const SOCKET_HEARTBEAT_SECS: u64 = 15;
+pub const SOCKET_CLIENT_TIMEOUT_SECS: u64 = 45;
+
+pub mod word_lists;
@@ -0,0 +1,208 @@
+pub const ENGLISH_NORMAL: &[&str] = &[
+    "the", "be", "of", "and", "a", "to", "in", "he", "have", "it", "that", "for", "they", "with",
+    "as", "not", "on", "she", "at", "by", "this", "we", "you", "do", "but", "from", "or", "which",
+    "one", "would", "all", "will", "there", "say", "who", "make", "when", "can", "more", "if",
+    "no", "man", "out", "other", "so", "what", "time", "up", "go", "about", "than", "into",
+    "could", "state", "only", "new", "year", "some", "take", "come", "these", "know", "see", "use",
+    "get", "like", "then", "first", "any", "work", "now", "may", "such", "give", "over", "think",
+    "most", "even", "find", "day", "also", "after", "way", "many", "must", "look", "before",
+    "great", "back", "through", "long", "where", "much", "should", "well", "people", "down", "own",
+    "just", "because", "good", "each", "those", "feel", "seem", "how", "high", "too", "place",
+    "little", "world", "very", "still", "nation", "hand", "old", "life", "tell", "write", "become",
+    "here", "show", "house", "both", "between", "need", "mean", "call", "develop", "under", "last",
+    "right", "move", "thing", "general", "school", "never", "same", "another", "begin", "while",
+    "number", "part", "turn", "real", "leave", "might", "want", "point", "form", "off", "child",
+    "few", "small", "since", "against", "ask", "late", "home", "interest", "large", "person",
+    "end", "open", "public", "follow", "during", "present", "without", "again", "hold", "govern",
+    "around", "possible", "head", "consider", "word", "program", "problem", "however", "lead",
+    "system", "set", "order", "eye", "plan", "run", "keep", "face", "fact", "group", "play",
+    "stand", "increase", "early", "course", "change", "help", "line",
+];
+
+pub const ENGLISH_HARD: &[&str] = &[
+    "acknowledgement",
+    "accommodate",
+    "anthropology",
+    "bureaucracy",
+    "catastrophe",
+    "circumstance",
+    "comprehensive",
+    "conscientious",
+    "contemporary",
+    "correspondence",
+    "deteriorate",
+    "disproportionate",
+    "entrepreneur",
+    "environmental",
+    "extraordinary",
+    "fluorescent",
+    "guarantee",
+    "hierarchy",
+    "hypothetical",
+    "idiosyncrasy",
+    "imperceptible",
+    "inconsequential",
+    "infrastructure",
+    "intellectual",
+    "interpretation",
+    "jurisdiction",
+    "kaleidoscope",
+    "labyrinth",
+    "lieutenant",
+    "magnificent",
+    "maneuver",
+    "mediterranean",
+    "miscellaneous",
+    "mischievous",
+    "necessarily",
+    "neighbourhood",
+    "nevertheless",
+    "occasionally",
+    "onomatopoeia",
+    "opportunity",
+    "parliament",
+    "perseverance",
+    "phenomenon",
+    "philosophical",
+    "predominantly",
+    "pronunciation",
+    "psychological",
+    "questionnaire",
+    "recommendation",
+    "reconnaissance",
+    "rhythm",
+    "sacrilegious",
+    "silhouette",
+    "simultaneously",
+    "sophisticated",
+    "spontaneous",
+    "strengths",
+    "subsequently",
+    "surveillance",
+    "technological",
+    "temperature",
+    "thoroughly",
+    "transcendental",
+    "unanimous",
+    "unnecessary",
+    "vacuum",
+    "vegetarian",
+    "vulnerability",
+    "whatsoever",
+    "wholehearted",
+    "xylophone",
+    "yesterday",
+    "zealous",
+    "accelerate",
+    "ambiguous",
+    "apparatus",
+    "benevolent",
+    "camouflage",
+    "characteristic",
+    "colleague",
+    "conscience",
+    "curriculum",
+    "dilemma",
+    "embarrass",
+    "exaggerate",
+    "fahrenheit",
+    "government",
+    "harassment",
+    "immediately",
+    "independent",
+    "knowledgeable",
+    "maintenance",
+    "millennium",
+    "occurrence",
+    "perpendicular",
+    "privilege",
+    "rehearsal",
+    "restaurant",
+    "schedule",
+    "threshold",
+];
+
+pub const CHINESE_NORMAL: &[&str] = &[
+    "我们", "你们", "他们", "什么", "时候", "没有", "知道", "可以", "自己", "这个", "那个", "一个",
+    "现在", "已经", "因为", "所以", "但是", "如果", "还是", "就是", "觉得", "今天", "明天", "昨天",
+    "朋友", "学习", "工作", "生活", "喜欢", "时间", "问题", "事情", "地方", "东西", "中国", "世界",
+    "国家", "社会", "发展", "经济", "文化", "历史", "电脑", "手机", "学校", "老师", "学生", "家人",
+    "孩子", "父母", "身体", "健康", "开始", "结束", "希望", "需要", "应该", "一起", "非常", "比较",
+    "特别", "简单", "容易", "重要", "认识", "了解", "告诉", "回来", "出去", "进来", "看见", "听说",
+    "记得", "忘记", "准备", "帮助", "努力", "成功", "快乐", "幸福", "美丽", "漂亮", "天气", "春天",
+    "夏天", "秋天", "冬天", "早上", "晚上", "中午", "城市", "公司", "医院", "商店", "饭店", "银行",
+    "电影", "音乐",
+];
+
+pub const CHINESE_HARD: &[&str] = &[
+    "一帆风顺",
+    "二龙腾飞",
+    "三羊开泰",
+    "四季平安",
+    "五福临门",
+    "六六大顺",
+    "七星高照",
+    "八方来财",
+    "九九同心",
+    "十全十美",
+    "百发百中",
+    "千方百计",
+    "万众一心",
+    "画蛇添足",
+    "守株待兔",
+    "亡羊补牢",
+    "对牛弹琴",
+    "井底之蛙",
+    "掩耳盗铃",
+    "刻舟求剑",
+    "自相矛盾",
+    "滥竽充数",
+    "杯弓蛇影",
+    "望梅止渴",
+    "叶公好龙",
+    "卧薪尝胆",
+    "破釜沉舟",
+    "纸上谈兵",
+    "完璧归赵",
+    "负荆请罪",
+    "四面楚歌",
+    "指鹿为马",
+    "图穷匕见",
+    "草木皆兵",
+    "风声鹤唳",
+    "一鼓作气",
+    "入木三分",
+    "胸有成竹",
+    "画龙点睛",
+    "班门弄斧",
+    "拔苗助长",
+    "狐假虎威",
+    "朝三暮四",
+    "塞翁失马",
+    "鹬蚌相争",
+    "愚公移山",
+    "精卫填海",
+    "夸父逐日",
+    "按图索骥",
+    "邯郸学步",
+    "惊弓之鸟",
+    "闻鸡起舞",
+    "悬梁刺股",
+    "凿壁偷光",
+    "程门立雪",
+    "铁杵磨针",
+    "孟母三迁",
+    "孔融让梨",
+    "司马光砸缸",
+    "曹冲称象",
+    "持之以恒",
+    "坚持不懈",
+    "实事求是",
+    "与时俱进",
+    "自强不息",
+    "厚德载物",
+    "海纳百川",
+    "有容乃大",
+    "温故知新",
+    "学而不厌",
+];
@@ -0,0 +1,89 @@
+use crate::config::app_config::AppConfig;
+use crate::errors::app_error::AppError;
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::repositories::email_verification_repository::EmailVerificationRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::email_verification_service::{
+    change_email, remove_email, resend_verification_email, verify_email,
+};
+use crate::services::mailer_service::Mailer;
+use crate::structs::api_response::{success_response, success_response_with_data};
+use crate::structs::email::{ChangeEmailRequest, EmailStatus, EmailVerificationConfirmation};
+use actix_web::{web, HttpResponse};
+use tracing::instrument;
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn update_email(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    verifications: web::Data<dyn EmailVerificationRepository>,
+    mailer: web::Data<dyn Mailer>,
+    user: AuthenticatedUser,
+    req: web::Json<ChangeEmailRequest>,
+) -> Result<HttpResponse, AppError> {
+    change_email(
+        users.get_ref(),
+        verifications.get_ref(),
+        mailer.get_ref(),
+        &config.mail,
+        &config.auth,
+        &user.username,
+        &req.email,
+    )
+    .await?;
+
+    let updated = users.find_by_username(&user.username).await?;
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Email address saved. Check your inbox for a verification link.",
+        EmailStatus {
+            email: updated.as_ref().and_then(|user| user.email.clone()),
+            email_verified: updated.is_some_and(|user| user.email_verified),
+        },
+    )))
+}
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn delete_email(
+    users: web::Data<dyn UserRepository>,
+    verifications: web::Data<dyn EmailVerificationRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    remove_email(users.get_ref(), verifications.get_ref(), &user.username).await?;
+
+    Ok(HttpResponse::Ok().json(success_response("Email address removed.")))
+}
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn resend_verification(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    verifications: web::Data<dyn EmailVerificationRepository>,
+    mailer: web::Data<dyn Mailer>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    resend_verification_email(
+        users.get_ref(),
+        verifications.get_ref(),
+        mailer.get_ref(),
+        &config.mail,
+        &config.auth,
+        &user.username,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(success_response("Verification link sent.")))
+}
+
+// Not authenticated, since the link is often opened in a mail client or on
+// another device
+#[instrument(skip_all)]
+pub async fn confirm_verification(
+    users: web::Data<dyn UserRepository>,
+    verifications: web::Data<dyn EmailVerificationRepository>,
+    req: web::Json<EmailVerificationConfirmation>,
+) -> Result<HttpResponse, AppError> {
+    let username = verify_email(users.get_ref(), verifications.get_ref(), req.token.trim()).await?;
+    tracing::info!(%username, "Email address verified");
+
+    Ok(HttpResponse::Ok().json(success_response("Email address verified.")))
+}
@@ -0,0 +1,33 @@
+use crate::config::app_config::AppConfig;
+use crate::services::health_service::{check_readiness, get_build_info};
+use crate::structs::api_response::{
+    error_response_with_data, success_response, success_response_with_data,
+};
+use actix_web::{web, HttpResponse};
+use mongodb::Client;
+use tracing::instrument;
+
+// Liveness only tells the orchestrator that the process still answers
+#[instrument(skip_all)]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(success_response("OK"))
+}
+
+#[instrument(skip_all)]
+pub async fn readyz(client: web::Data<Client>, config: web::Data<AppConfig>) -> HttpResponse {
+    let report = check_readiness(&client, &config).await;
+
+    if report.ready {
+        HttpResponse::Ok().json(success_response_with_data("Ready.", report))
+    } else {
+        HttpResponse::ServiceUnavailable().json(error_response_with_data("Not ready.", report))
+    }
+}
+
+#[instrument(skip_all)]
+pub async fn version() -> HttpResponse {
+    HttpResponse::Ok().json(success_response_with_data(
+        "Build info retrieved successfully.",
+        get_build_info(),
+    ))
+}
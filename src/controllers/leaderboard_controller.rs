@@ -1,55 +1,179 @@
-use crate::constants::{COLL_NAME, DB_NAME};
-use crate::services::leaderboard_service::{fetch_filtered_users, get_total_document_count};
+use crate::config::modes::ModeRegistry;
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::repositories::leaderboard_repository::LeaderboardRepository;
+use crate::repositories::test_result_repository::TestResultRepository;
+use crate::services::leaderboard_service::{
+    calculate_percentile, parse_leaderboard_paging, parse_leaderboard_window, LeaderboardCursor,
+};
+use crate::services::mode_service::resolve_mode;
+use crate::services::windowed_leaderboard_service::get_window_start;
+use crate::structs::api_response::success_response_with_data;
 use crate::structs::leaderboard::{
-    GetLeaderboardStatsQueries, LeaderboardEntry, LeaderboardResponse,
+    AroundUserLeaderboard, GetLeaderboardStatsQueries, GetUserRankQueries, LeaderboardEntry,
+    LeaderboardResponse, RankedLeaderboardEntry, UserRank,
 };
 
 use actix_web::{web, HttpResponse};
-use mongodb::bson;
-use mongodb::Client;
+use chrono::Utc;
+use tracing::instrument;
 
+#[instrument(skip_all)]
 pub async fn get_leaderboard_stats(
-    client: web::Data<Client>,
+    leaderboard: web::Data<dyn LeaderboardRepository>,
+    results: web::Data<dyn TestResultRepository>,
+    modes: web::Data<ModeRegistry>,
     query: web::Query<GetLeaderboardStatsQueries>,
-) -> HttpResponse {
-    let collection = client
-        .database(DB_NAME)
-        .collection::<bson::Document>(COLL_NAME);
-
-    let total_count = match get_total_document_count(&collection).await {
-        Ok(count) => count,
-        Err(response) => return response,
+) -> Result<HttpResponse, AppError> {
+    let mode = resolve_mode(
+        &modes,
+        &query.language,
+        &query.difficulty,
+        &query.timer_duration,
+    )?;
+
+    let window = parse_leaderboard_window(query.window.as_deref())?;
+
+    let paging = parse_leaderboard_paging(
+        query.page.as_deref(),
+        query.limit.as_deref(),
+        query.after.as_deref(),
+        window,
+    )?;
+
+    let (total_count, page) = match get_window_start(window, Utc::now()) {
+        // Windowed boards are built from the results submitted inside the window
+        Some(window_start) => {
+            let total_count = results.count_in_window(&mode, window_start).await?;
+            let page = results
+                .fetch_window_page(&mode, window_start, &paging)
+                .await?;
+            (total_count, page)
+        }
+        None => {
+            let total_count = leaderboard.count(&mode).await?;
+            let page = leaderboard.fetch_page(&mode, &paging).await?;
+            (total_count, page)
+        }
     };
 
-    let timer_duration = query.timer_duration.to_string();
-    let language = query.language.to_string();
-    let difficulty = query.difficulty.to_string();
-
-    let users = fetch_filtered_users(
-        &collection,
-        &timer_duration,
-        &query.page,
-        &query.limit,
-        &language,
-        &difficulty,
-    )
-    .await;
-
-    if users.is_err() {
-        return HttpResponse::InternalServerError().json(LeaderboardResponse {
-            status: "error".to_string(),
-            message: "Failed to fetch leaderboard data. Please try again later.".to_string(),
-            leaderboard: vec![],
-            total_count: 0,
-        });
-    }
-
-    let leaderboard: Vec<LeaderboardEntry> = users.unwrap().into_iter().collect();
-
-    HttpResponse::Ok().json(LeaderboardResponse {
+    Ok(HttpResponse::Ok().json(LeaderboardResponse {
         status: "success".to_string(),
         message: "Leaderboard stats retrieved successfully.".to_string(),
-        leaderboard,
+        leaderboard: page.entries,
         total_count,
+        next_cursor: page.next_cursor,
+    }))
+}
+
+const DEFAULT_AROUND_RANGE: u64 = 5;
+const MAX_AROUND_RANGE: u64 = 50;
+
+struct RankLookup {
+    mode: GameMode,
+    entry: LeaderboardEntry,
+    position: LeaderboardCursor,
+    user_rank: UserRank,
+}
+
+async fn lookup_user_rank(
+    leaderboard: &dyn LeaderboardRepository,
+    modes: &ModeRegistry,
+    query: &GetUserRankQueries,
+    username: &str,
+) -> Result<RankLookup, AppError> {
+    let mode = resolve_mode(
+        modes,
+        &query.language,
+        &query.difficulty,
+        &query.timer_duration,
+    )?;
+
+    let (entry, position) = match leaderboard.fetch_user_position(&mode, username).await? {
+        Some(found) => found,
+        None => {
+            return Err(AppError::NotFound(format!(
+                "User '{}' has no score for this mode.",
+                username
+            )))
+        }
+    };
+
+    let rank = leaderboard.get_rank(&mode, &position).await?;
+    let total_count = leaderboard.count(&mode).await? as u64;
+
+    Ok(RankLookup {
+        mode,
+        entry,
+        position,
+        user_rank: UserRank {
+            username: username.to_string(),
+            rank,
+            total_count,
+            percentile: calculate_percentile(rank, total_count),
+        },
     })
 }
+
+#[instrument(skip_all, fields(username = %username))]
+pub async fn get_user_rank(
+    leaderboard: web::Data<dyn LeaderboardRepository>,
+    modes: web::Data<ModeRegistry>,
+    username: web::Path<String>,
+    query: web::Query<GetUserRankQueries>,
+) -> Result<HttpResponse, AppError> {
+    let lookup = lookup_user_rank(leaderboard.get_ref(), &modes, &query, &username).await?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Leaderboard rank retrieved successfully.",
+        lookup.user_rank,
+    )))
+}
+
+#[instrument(skip_all, fields(username = %username))]
+pub async fn get_leaderboard_around_user(
+    leaderboard: web::Data<dyn LeaderboardRepository>,
+    modes: web::Data<ModeRegistry>,
+    username: web::Path<String>,
+    query: web::Query<GetUserRankQueries>,
+) -> Result<HttpResponse, AppError> {
+    let range = match query.range.as_deref() {
+        Some(range) => match range.parse::<u64>() {
+            Ok(range) if (1..=MAX_AROUND_RANGE).contains(&range) => range,
+            _ => {
+                return Err(AppError::Validation(format!(
+                    "range must be an integer between 1 and {}.",
+                    MAX_AROUND_RANGE
+                )))
+            }
+        },
+        None => DEFAULT_AROUND_RANGE,
+    };
+
+    let lookup = lookup_user_rank(leaderboard.get_ref(), &modes, &query, &username).await?;
+
+    let (above, below) = leaderboard
+        .fetch_entries_around(&lookup.mode, &lookup.position, range)
+        .await?;
+
+    let rank = lookup.user_rank.rank;
+    let first_rank = rank - above.len() as u64;
+    let entries = above
+        .into_iter()
+        .chain(std::iter::once(lookup.entry))
+        .chain(below)
+        .enumerate()
+        .map(|(index, entry)| RankedLeaderboardEntry {
+            rank: first_rank + index as u64,
+            entry,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Leaderboard entries retrieved successfully.",
+        AroundUserLeaderboard {
+            user_rank: lookup.user_rank,
+            entries,
+        },
+    )))
+}
@@ -0,0 +1,155 @@
+use crate::config::modes::ModeRegistry;
+use crate::constants::{SOCKET_CLIENT_TIMEOUT_SECS, SOCKET_HEARTBEAT_SECS};
+use crate::models::mode::GameMode;
+use crate::services::leaderboard_hub::LeaderboardHub;
+use crate::structs::leaderboard_socket::{
+    LeaderboardClientMessage, LeaderboardServerMessage, LeaderboardUpdate,
+};
+use actix_web::{rt, web, Error, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream, Session};
+use futures_util::StreamExt;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::instrument;
+
+#[instrument(skip_all)]
+pub async fn leaderboard_socket(
+    req: HttpRequest,
+    body: web::Payload,
+    hub: web::Data<LeaderboardHub>,
+    modes: web::Data<ModeRegistry>,
+) -> Result<HttpResponse, Error> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    rt::spawn(run_leaderboard_session(
+        session,
+        stream,
+        hub.subscribe(),
+        modes.into_inner(),
+    ));
+
+    Ok(response)
+}
+
+async fn send_message(
+    session: &mut Session,
+    message: &LeaderboardServerMessage,
+) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(message) {
+        Ok(text) => session.text(text).await,
+        Err(err) => {
+            tracing::error!(error = ?err, "Error serializing leaderboard message");
+            Ok(())
+        }
+    }
+}
+
+fn handle_client_message(
+    text: &str,
+    modes: &ModeRegistry,
+    subscriptions: &mut HashSet<GameMode>,
+) -> LeaderboardServerMessage {
+    let message = match serde_json::from_str::<LeaderboardClientMessage>(text) {
+        Ok(message) => message,
+        Err(_) => {
+            return LeaderboardServerMessage::Error {
+                message: "Invalid message.".to_string(),
+            }
+        }
+    };
+
+    let (language, difficulty, timer_duration, subscribe) = match &message {
+        LeaderboardClientMessage::Subscribe {
+            language,
+            difficulty,
+            timer_duration,
+        } => (language, difficulty, timer_duration, true),
+        LeaderboardClientMessage::Unsubscribe {
+            language,
+            difficulty,
+            timer_duration,
+        } => (language, difficulty, timer_duration, false),
+    };
+
+    let mode = match modes.resolve(language, difficulty, timer_duration) {
+        Ok(mode) => mode,
+        Err(invalid_mode) => {
+            return LeaderboardServerMessage::Error {
+                message: format!("Unknown {} '{}'.", invalid_mode.field, invalid_mode.value),
+            }
+        }
+    };
+
+    if subscribe {
+        subscriptions.insert(mode.clone());
+        LeaderboardServerMessage::Subscribed { mode }
+    } else {
+        subscriptions.remove(&mode);
+        LeaderboardServerMessage::Unsubscribed { mode }
+    }
+}
+
+async fn run_leaderboard_session(
+    mut session: Session,
+    mut stream: MessageStream,
+    mut updates: Receiver<LeaderboardUpdate>,
+    modes: Arc<ModeRegistry>,
+) {
+    let mut subscriptions: HashSet<GameMode> = HashSet::new();
+    let mut heartbeat = rt::time::interval(Duration::from_secs(SOCKET_HEARTBEAT_SECS));
+    let mut last_heard = Instant::now();
+
+    loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > Duration::from_secs(SOCKET_CLIENT_TIMEOUT_SECS) {
+                    tracing::debug!("Closing an unresponsive leaderboard socket");
+                    break;
+                }
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            },
+            message = stream.next() => {
+                if let Some(Ok(_)) = &message {
+                    last_heard = Instant::now();
+                }
+                match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let reply = handle_client_message(&text, &modes, &mut subscriptions);
+                        if send_message(&mut session, &reply).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => {
+                        let _ = session.close(reason).await;
+                        return;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break,
+                }
+            },
+            update = updates.recv() => match update {
+                Ok(update) if subscriptions.contains(&update.mode) => {
+                    let message = LeaderboardServerMessage::LeaderboardUpdate(update);
+                    if send_message(&mut session, &message).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                // Missed updates are not replayed; clients can refetch the board
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+
+    let _ = session.close(None).await;
+}
@@ -0,0 +1,11 @@
+use crate::errors::app_error::AppError;
+use crate::services::metrics_service::metrics;
+use actix_web::HttpResponse;
+use tracing::instrument;
+
+#[instrument(skip_all)]
+pub async fn get_metrics() -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics().render()?))
+}
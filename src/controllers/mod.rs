@@ -1,2 +1,12 @@
+pub mod email_controller;
+pub mod health_controller;
 pub mod leaderboard_controller;
+pub mod leaderboard_socket_controller;
+pub mod metrics_controller;
+pub mod password_controller;
+pub mod race_result_controller;
+pub mod race_socket_controller;
+pub mod session_controller;
+pub mod test_result_controller;
+pub mod typing_test_controller;
 pub mod user_controller;
@@ -0,0 +1,123 @@
+use crate::config::app_config::AppConfig;
+use crate::errors::app_error::AppError;
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::repositories::password_reset_repository::PasswordResetRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::mailer_service::Mailer;
+use crate::services::password_reset_service::{request_password_reset, reset_password_with_token};
+use crate::services::rate_limit_service::LoginLockout;
+use crate::services::user_service::{
+    authenticate_user, create_expired_cookie, get_client_ip, set_user_password,
+    validate_new_password,
+};
+use crate::structs::api_response::{success_response, success_response_with_data};
+use crate::structs::password::{
+    ChangePasswordRequest, PasswordResetConfirmation, PasswordResetRequest,
+};
+use crate::structs::session::RevokedSessions;
+use actix_web::{rt, web, HttpRequest, HttpResponse};
+use tracing::{instrument, Instrument};
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn change_password(
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    lockout: web::Data<LoginLockout>,
+    user: AuthenticatedUser,
+    http_req: HttpRequest,
+    req: web::Json<ChangePasswordRequest>,
+) -> Result<HttpResponse, AppError> {
+    let req = req.into_inner();
+    validate_new_password(&req.new_password, &req.confirmation_password)?;
+
+    // Guessing the current password counts towards the login lockout
+    let client_ip = get_client_ip(&http_req);
+    lockout.check(&user.username, client_ip)?;
+    if !authenticate_user(users.get_ref(), &user.username, &req.current_password).await? {
+        lockout.record_failure(&user.username, client_ip);
+        return Err(AppError::Forbidden(
+            "Current password is incorrect.".to_string(),
+        ));
+    }
+    lockout.record_success(&user.username, client_ip);
+
+    set_user_password(users.get_ref(), &user.username, &req.new_password).await?;
+
+    // Keep the session that made the change and sign out every other one
+    let revoked_count = sessions
+        .revoke_others(&user.username, &user.session_id, "password_changed")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Password changed.",
+        RevokedSessions { revoked_count },
+    )))
+}
+
+#[instrument(skip_all, fields(username = %req.username))]
+pub async fn request_reset(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    resets: web::Data<dyn PasswordResetRepository>,
+    mailer: web::Data<dyn Mailer>,
+    req: web::Json<PasswordResetRequest>,
+) -> Result<HttpResponse, AppError> {
+    let username = req.username.trim();
+    if username.is_empty() {
+        return Err(AppError::Validation(
+            "Username cannot be empty.".to_string(),
+        ));
+    }
+
+    // Runs in the background so that neither the response time nor its
+    // status tells whether the account exists or the mail went out
+    let username = username.to_string();
+    let task = async move {
+        if let Err(err) = request_password_reset(
+            users.get_ref(),
+            resets.get_ref(),
+            mailer.get_ref(),
+            &config.mail,
+            &config.auth,
+            &username,
+        )
+        .await
+        {
+            tracing::error!(error = %err, "Error handling a password reset request");
+        }
+    };
+    rt::spawn(task.instrument(tracing::Span::current()));
+
+    Ok(HttpResponse::Ok().json(success_response(
+        "If the account has a verified email address, a reset link has been sent to it.",
+    )))
+}
+
+#[instrument(skip_all)]
+pub async fn confirm_reset(
+    users: web::Data<dyn UserRepository>,
+    resets: web::Data<dyn PasswordResetRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    req: web::Json<PasswordResetConfirmation>,
+) -> Result<HttpResponse, AppError> {
+    let req = req.into_inner();
+    validate_new_password(&req.password, &req.confirmation_password)?;
+
+    reset_password_with_token(
+        users.get_ref(),
+        resets.get_ref(),
+        sessions.get_ref(),
+        req.token.trim(),
+        &req.password,
+    )
+    .await?;
+
+    // Any session in this browser was just revoked as well
+    Ok(HttpResponse::Ok()
+        .cookie(create_expired_cookie("user_jwt_token"))
+        .cookie(create_expired_cookie("user_refresh_token"))
+        .json(success_response(
+            "Password has been reset. Please log in again.",
+        )))
+}
@@ -0,0 +1,26 @@
+use crate::constants::RACE_RESULTS_COLL_NAME;
+use crate::errors::app_error::AppError;
+use crate::services::race_result_service::fetch_race_results;
+use crate::structs::api_response::success_response_with_data;
+use crate::structs::race::GetRaceResultsQueries;
+use crate::utils::helpers::get_collection_by_name;
+use actix_web::{web, HttpResponse};
+use mongodb::Client;
+use tracing::instrument;
+
+#[instrument(skip_all, fields(username = %username))]
+pub async fn get_user_races(
+    client: web::Data<Client>,
+    username: web::Path<String>,
+    query: web::Query<GetRaceResultsQueries>,
+) -> Result<HttpResponse, AppError> {
+    let collection = get_collection_by_name(&client, RACE_RESULTS_COLL_NAME);
+    let username = username.into_inner();
+
+    let page = fetch_race_results(&collection, &username, &query).await?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Race results retrieved successfully.",
+        page,
+    )))
+}
@@ -0,0 +1,197 @@
+use crate::config::modes::ModeRegistry;
+use crate::constants::{SOCKET_CLIENT_TIMEOUT_SECS, SOCKET_HEARTBEAT_SECS};
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::services::race_rooms::{RaceRooms, RaceSender};
+use crate::structs::race::{RaceClientMessage, RaceServerMessage};
+use actix_web::{rt, web, Error, HttpRequest, HttpResponse};
+use actix_ws::{Message, MessageStream, Session};
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::instrument;
+
+// Players are authenticated by the extractor before the upgrade
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn race_socket(
+    user: AuthenticatedUser,
+    req: HttpRequest,
+    body: web::Payload,
+    rooms: web::Data<RaceRooms>,
+    modes: web::Data<ModeRegistry>,
+) -> Result<HttpResponse, Error> {
+    let (response, session, stream) = actix_ws::handle(&req, body)?;
+
+    rt::spawn(run_race_session(
+        session,
+        stream,
+        user.username,
+        rooms.into_inner(),
+        modes.into_inner(),
+    ));
+
+    Ok(response)
+}
+
+async fn send_message(
+    session: &mut Session,
+    message: &RaceServerMessage,
+) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(message) {
+        Ok(text) => session.text(text).await,
+        Err(err) => {
+            tracing::error!(error = ?err, "Error serializing race message");
+            Ok(())
+        }
+    }
+}
+
+fn create_race_error(message: &str) -> RaceServerMessage {
+    RaceServerMessage::Error {
+        message: message.to_string(),
+    }
+}
+
+// Returns a direct reply for the player, if any; room events reach every
+// player (this one included) through their channel
+fn handle_client_message(
+    text: &str,
+    username: &str,
+    sender: &RaceSender,
+    room_code: &mut Option<String>,
+    rooms: &Arc<RaceRooms>,
+    modes: &ModeRegistry,
+) -> Option<RaceServerMessage> {
+    let message = match serde_json::from_str::<RaceClientMessage>(text) {
+        Ok(message) => message,
+        Err(_) => return Some(create_race_error("Invalid message.")),
+    };
+
+    let result = match message {
+        RaceClientMessage::Create {
+            language,
+            difficulty,
+            word_count,
+        } => {
+            if room_code.is_some() {
+                return Some(create_race_error("Leave your current room first."));
+            }
+            let valid_mode = modes
+                .validate_language(&language)
+                .and_then(|_| modes.validate_difficulty(&difficulty));
+            if let Err(invalid_mode) = valid_mode {
+                return Some(create_race_error(&format!(
+                    "Unknown {} '{}'.",
+                    invalid_mode.field, invalid_mode.value
+                )));
+            }
+
+            rooms
+                .create_room(username, sender.clone(), &language, &difficulty, word_count)
+                .map(|code| *room_code = Some(code))
+        }
+        RaceClientMessage::Join { code } => {
+            if room_code.is_some() {
+                return Some(create_race_error("Leave your current room first."));
+            }
+            let code = code.trim().to_uppercase();
+
+            rooms
+                .join_room(&code, username, sender.clone())
+                .map(|_| *room_code = Some(code))
+        }
+        RaceClientMessage::Start => match room_code {
+            Some(code) => rooms.start_race(code, username),
+            None => Err("You are not in a room.".to_string()),
+        },
+        RaceClientMessage::Progress { position } => match room_code {
+            Some(code) => rooms.update_progress(code, username, position),
+            None => Err("You are not in a room.".to_string()),
+        },
+        RaceClientMessage::Leave => match room_code.take() {
+            Some(code) => {
+                rooms.leave_room(&code, username);
+                return Some(RaceServerMessage::Left);
+            }
+            None => Err("You are not in a room.".to_string()),
+        },
+    };
+
+    result.err().map(|message| create_race_error(&message))
+}
+
+async fn run_race_session(
+    mut session: Session,
+    mut stream: MessageStream,
+    username: String,
+    rooms: Arc<RaceRooms>,
+    modes: Arc<ModeRegistry>,
+) {
+    let (sender, mut events) = mpsc::unbounded_channel();
+    let mut room_code: Option<String> = None;
+    let mut heartbeat = rt::time::interval(Duration::from_secs(SOCKET_HEARTBEAT_SECS));
+    let mut last_heard = Instant::now();
+
+    loop {
+        tokio::select! {
+            // A player whose connection silently died leaves the room like
+            // one who disconnected, instead of holding up the race
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > Duration::from_secs(SOCKET_CLIENT_TIMEOUT_SECS) {
+                    tracing::debug!(username, "Closing an unresponsive race socket");
+                    break;
+                }
+                if session.ping(b"").await.is_err() {
+                    break;
+                }
+            },
+            message = stream.next() => {
+                if let Some(Ok(_)) = &message {
+                    last_heard = Instant::now();
+                }
+                match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let reply = handle_client_message(
+                            &text,
+                            &username,
+                            &sender,
+                            &mut room_code,
+                            &rooms,
+                            &modes,
+                        );
+                        if let Some(reply) = reply {
+                            if send_message(&mut session, &reply).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => {
+                        let _ = session.close(reason).await;
+                        if let Some(code) = room_code {
+                            rooms.leave_room(&code, &username);
+                        }
+                        return;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break,
+                }
+            },
+            // The session holds a sender itself, so this never yields None
+            Some(event) = events.recv() => {
+                if send_message(&mut session, &event).await.is_err() {
+                    break;
+                }
+            },
+        }
+    }
+
+    if let Some(code) = room_code {
+        rooms.leave_room(&code, &username);
+    }
+    let _ = session.close(None).await;
+}
@@ -0,0 +1,82 @@
+use crate::errors::app_error::AppError;
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::models::session::UserSession;
+use crate::repositories::session_repository::SessionRepository;
+use crate::services::user_service::create_expired_cookie;
+use crate::structs::api_response::{success_response, success_response_with_data};
+use crate::structs::session::{RevokedSessions, SessionEntry};
+use crate::utils::helpers::to_chrono_datetime;
+use actix_web::{web, HttpResponse};
+use tracing::instrument;
+
+fn to_session_entry(session: UserSession, current_session_id: &str) -> SessionEntry {
+    let id = session.id.to_hex();
+    SessionEntry {
+        current: id == current_session_id,
+        id,
+        user_agent: session.user_agent,
+        ip_address: session.ip_address,
+        created_at: to_chrono_datetime(session.created_at),
+        last_seen_at: to_chrono_datetime(session.last_seen_at),
+    }
+}
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn list_sessions(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let entries: Vec<SessionEntry> = sessions
+        .list_active(&user.username)
+        .await?
+        .into_iter()
+        .map(|session| to_session_entry(session, &user.session_id))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Sessions retrieved successfully.",
+        entries,
+    )))
+}
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn revoke_session(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+    session_id: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = session_id.into_inner();
+
+    if !sessions
+        .revoke_for_user(&user.username, &session_id, "revoked_by_user")
+        .await?
+    {
+        return Err(AppError::NotFound("Session not found.".to_string()));
+    }
+
+    // Revoking the session in use is the same as logging out
+    if session_id == user.session_id {
+        return Ok(HttpResponse::Ok()
+            .cookie(create_expired_cookie("user_jwt_token"))
+            .cookie(create_expired_cookie("user_refresh_token"))
+            .json(success_response("Session revoked.")));
+    }
+
+    Ok(HttpResponse::Ok().json(success_response("Session revoked.")))
+}
+
+// "Sign out everywhere else": keeps only the session making the request
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn revoke_other_user_sessions(
+    sessions: web::Data<dyn SessionRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let revoked_count = sessions
+        .revoke_others(&user.username, &user.session_id, "revoked_by_user")
+        .await?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Other sessions revoked.",
+        RevokedSessions { revoked_count },
+    )))
+}
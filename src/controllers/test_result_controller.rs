@@ -0,0 +1,43 @@
+use crate::config::modes::{InvalidMode, ModeRegistry};
+use crate::errors::app_error::AppError;
+use crate::repositories::test_result_repository::TestResultRepository;
+use crate::services::test_result_service::fetch_test_results;
+use crate::structs::api_response::success_response_with_data;
+use crate::structs::test_result::GetTestResultsQueries;
+use actix_web::{web, HttpResponse};
+use tracing::instrument;
+
+fn validate_result_filters(
+    modes: &ModeRegistry,
+    query: &GetTestResultsQueries,
+) -> Result<(), InvalidMode> {
+    if let Some(language) = &query.language {
+        modes.validate_language(language)?;
+    }
+    if let Some(difficulty) = &query.difficulty {
+        modes.validate_difficulty(difficulty)?;
+    }
+    if let Some(duration) = &query.duration {
+        modes.validate_duration(duration)?;
+    }
+    Ok(())
+}
+
+#[instrument(skip_all, fields(username = %username))]
+pub async fn get_user_results(
+    results: web::Data<dyn TestResultRepository>,
+    modes: web::Data<ModeRegistry>,
+    username: web::Path<String>,
+    query: web::Query<GetTestResultsQueries>,
+) -> Result<HttpResponse, AppError> {
+    let username = username.into_inner();
+
+    validate_result_filters(&modes, &query)?;
+
+    let page = fetch_test_results(results.get_ref(), &username, &query).await?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "Test results retrieved successfully.",
+        page,
+    )))
+}
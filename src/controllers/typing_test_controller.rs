@@ -0,0 +1,109 @@
+use crate::config::app_config::AppConfig;
+use crate::errors::app_error::AppError;
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::models::user::Score;
+use crate::repositories::leaderboard_repository::LeaderboardRepository;
+use crate::repositories::test_result_repository::TestResultRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::leaderboard_hub::{notify_personal_best, LeaderboardHub};
+use crate::services::mode_service::resolve_mode;
+use crate::services::test_result_service::create_test_result;
+use crate::services::typing_test_service::{
+    create_test_session, decode_test_session, verify_test_result,
+};
+use crate::services::user_service::save_user_scores;
+use crate::structs::api_response::success_response_with_data;
+use crate::structs::typing_test::{FinishTestRequest, StartTestRequest};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use tracing::instrument;
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn start_test(
+    user: AuthenticatedUser,
+    config: web::Data<AppConfig>,
+    start_req: web::Json<StartTestRequest>,
+) -> Result<HttpResponse, AppError> {
+    let mode = resolve_mode(
+        &config.modes,
+        &start_req.language,
+        &start_req.difficulty,
+        &start_req.duration,
+    )?;
+
+    let session = create_test_session(&config.auth, &user.username, &mode)?;
+
+    Ok(HttpResponse::Ok().json(success_response_with_data("Test session started.", session)))
+}
+
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn finish_test(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    results: web::Data<dyn TestResultRepository>,
+    leaderboard: web::Data<dyn LeaderboardRepository>,
+    hub: web::Data<LeaderboardHub>,
+    user: AuthenticatedUser,
+    finish_req: web::Json<FinishTestRequest>,
+) -> Result<HttpResponse, AppError> {
+    let username = user.username;
+
+    let finish_request = finish_req.into_inner();
+    let claims = decode_test_session(&config.auth, &finish_request.session_token)?;
+
+    if claims.sub != username {
+        return Err(AppError::Forbidden(
+            "Test session belongs to another user.".to_string(),
+        ));
+    }
+
+    // The registry may have changed since the session was issued
+    let mode = resolve_mode(
+        &config.modes,
+        &claims.language,
+        &claims.difficulty,
+        &claims.duration.to_string(),
+    )?;
+
+    // Recompute the result from the keystrokes instead of trusting the client
+    let mut result = verify_test_result(&claims, &finish_request)?;
+
+    // Each session can only be submitted once
+    if !results.claim_session(&claims).await? {
+        return Err(AppError::Conflict(
+            "Test session has already been submitted.".to_string(),
+        ));
+    }
+
+    let score = Score {
+        wpm: result.wpm,
+        raw_wpm: result.raw_wpm,
+        accuracy: result.accuracy,
+        date: Utc::now(),
+    };
+
+    // Nothing has been written if the score update fails, so the claim is
+    // released and the client can retry the same session
+    result.personal_best = match save_user_scores(users.get_ref(), &username, &mode, &score).await {
+        Ok(personal_best) => personal_best,
+        Err(err) => {
+            if let Err(release_err) = results.release_session(&claims).await {
+                tracing::error!(error = %release_err, "Error releasing the test session");
+            }
+            return Err(err);
+        }
+    };
+
+    // The score already counts at this point; a missing history entry is
+    // logged rather than failing a result the user has earned
+    let test_result = create_test_result(&username, &mode, &score, Some(result.char_stats.clone()));
+    if let Err(err) = results.insert(&test_result).await {
+        tracing::error!(error = %err, "Error saving the verified test result");
+    }
+
+    if result.personal_best {
+        notify_personal_best(&hub, leaderboard.get_ref(), &mode, &username, &score).await;
+    }
+
+    Ok(HttpResponse::Ok().json(success_response_with_data("Test result verified.", result)))
+}
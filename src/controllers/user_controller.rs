@@ -1,101 +1,136 @@
+use crate::config::app_config::AppConfig;
+use crate::errors::app_error::AppError;
+use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::repositories::email_verification_repository::EmailVerificationRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::captcha_service::CaptchaVerifier;
+use crate::services::email_verification_service::send_verification_email;
+use crate::services::mailer_service::Mailer;
+use crate::services::metrics_service::{record_captcha_result, record_login_result};
+use crate::services::rate_limit_service::LoginLockout;
+use crate::services::session_service::read_refresh_session_id;
 use crate::services::user_service::{
-    authenticate_user, create_http_only_cookie, fetch_user_and_handle_response, generate_jwt,
-    process_user_registration, save_user_scores, update_user_high_scores, validate_credentials,
-    verify_jwt, verify_recaptcha, verify_recaptcha_and_check,
+    authenticate_user, create_expired_cookie, create_http_only_cookie, create_refresh_cookie,
+    fetch_user_and_handle_response, generate_jwt, get_client_ip, get_device_info, normalize_email,
+    process_user_registration, read_session_id, validate_credentials,
 };
-use crate::structs::api_response::{error_response, success_response, success_response_with_data};
-use crate::structs::claims::Claims;
-use crate::structs::leaderboard::ScoreUpdateRequest;
+use crate::structs::api_response::{success_response, success_response_with_data};
 use crate::structs::login::LoginRequest;
+use crate::structs::me::CurrentUser;
+use crate::structs::session::RefreshOutcome;
 use crate::structs::sign_up::SignUpRequest;
-use crate::utils::helpers::get_collection;
-use actix_web::cookie::time::Duration;
-use actix_web::{
-    cookie::{Cookie, SameSite},
-    web, Error, HttpRequest, HttpResponse,
-};
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
-use mongodb::{bson::doc, Client};
-use std::env;
-
-pub async fn logout(_req: HttpRequest) -> Result<HttpResponse, Error> {
-    let cookie = Cookie::build("user_jwt_token", "")
-        .http_only(true)
-        .secure(true)
-        .same_site(SameSite::None)
-        .path("/")
-        .max_age(Duration::new(0, 0))
-        .finish();
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use tracing::instrument;
+
+#[instrument(skip_all)]
+pub async fn logout(
+    config: web::Data<AppConfig>,
+    sessions: web::Data<dyn SessionRepository>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    // Either cookie identifies the session; the access token may have expired
+    let session_id = req
+        .cookie("user_jwt_token")
+        .and_then(|cookie| read_session_id(&config.auth, cookie.value()))
+        .or_else(|| {
+            req.cookie("user_refresh_token")
+                .and_then(|cookie| read_refresh_session_id(cookie.value()))
+        });
+
+    if let Some(session_id) = session_id {
+        sessions.revoke(&session_id, "logout").await?;
+    }
 
     Ok(HttpResponse::Ok()
-        .cookie(cookie)
+        .cookie(create_expired_cookie("user_jwt_token"))
+        .cookie(create_expired_cookie("user_refresh_token"))
         .json(success_response("Logout successfully.")))
 }
 
-pub async fn check_auth(req: HttpRequest) -> Result<HttpResponse, Error> {
-    // Attempt to extract the "user_jwt_token" cookie
-    let cookie = match req.cookie("user_jwt_token") {
-        Some(cookie) => cookie,
-        None => return Ok(HttpResponse::Unauthorized().finish()), // No cookie found
-    };
-
-    let token = cookie.value();
+// Rejected by the extractor when the token or its session is not valid
+#[instrument(skip_all)]
+pub async fn check_auth(_user: AuthenticatedUser) -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
 
-    // Fetch JWT secret key from environment variable
-    let secret_key = match env::var("JWT_SECRET") {
-        Ok(key) => key,
-        Err(_) => return Ok(HttpResponse::InternalServerError().finish()), // Missing secret key
-    };
+#[instrument(skip_all, fields(username = %user.username))]
+pub async fn get_current_user(
+    users: web::Data<dyn UserRepository>,
+    user: AuthenticatedUser,
+) -> Result<HttpResponse, AppError> {
+    let user = fetch_user_and_handle_response(users.get_ref(), &user.username).await?;
 
-    // Decode the JWT
-    let decoding_key = DecodingKey::from_secret(secret_key.as_ref());
-    let validation = Validation::new(Algorithm::HS256);
+    Ok(HttpResponse::Ok().json(success_response_with_data(
+        "User retrieved successfully.",
+        CurrentUser {
+            username: user.username,
+            created_at: user.created_at,
+            completed_tests: user.completed_tests.unwrap_or(0),
+            settings: user.settings,
+            email: user.email,
+            email_verified: user.email_verified,
+        },
+    )))
+}
 
-    match decode::<Claims>(token, &decoding_key, &validation) {
-        Ok(_) => Ok(HttpResponse::Ok().finish()), // Token is valid
-        Err(err) => {
-            eprintln!("JWT decode error: {:?}", err); // Log the error
-            Ok(HttpResponse::Unauthorized().finish()) // Token is invalid
+// A refresh that fails for good also drops the now useless cookies
+fn create_refresh_rejection(error: AppError) -> HttpResponse {
+    let mut response = error.error_response();
+    for name in ["user_jwt_token", "user_refresh_token"] {
+        if let Err(err) = response.add_cookie(&create_expired_cookie(name)) {
+            tracing::warn!(cookie = name, error = ?err, "Error clearing cookie");
         }
     }
+    response
 }
 
-pub async fn update_user_scores(
-    client: web::Data<Client>,
+#[instrument(skip_all)]
+pub async fn refresh_token(
+    config: web::Data<AppConfig>,
+    sessions: web::Data<dyn SessionRepository>,
     req: HttpRequest,
-    score_update_req: web::Json<ScoreUpdateRequest>,
-) -> HttpResponse {
-    // Extract token from cookies
-    let cookie = req.cookie("user_jwt_token");
-
-    // Check if the cookie exists and get its value
-    let token = match cookie {
+) -> Result<HttpResponse, AppError> {
+    let token = match req.cookie("user_refresh_token") {
         Some(cookie) => cookie.value().to_string(),
-        None => return HttpResponse::Unauthorized().json(error_response("Unauthorized")),
+        None => return Err(AppError::Unauthorized),
     };
 
-    // Verify the token and extract claims
-    let claims = match verify_jwt(&token) {
-        Ok(claims) => claims,
-        Err(_) => return HttpResponse::Unauthorized().json(error_response("Invalid token")),
+    let (username, session_id, refresh_token) = match sessions
+        .rotate_refresh_token(&token, &get_device_info(&req))
+        .await?
+    {
+        RefreshOutcome::Rotated {
+            username,
+            session_id,
+            refresh_token,
+        } => (username, session_id, refresh_token),
+        RefreshOutcome::Reused => {
+            return Ok(create_refresh_rejection(AppError::RefreshTokenReused))
+        }
+        RefreshOutcome::Invalid => {
+            return Ok(create_refresh_rejection(AppError::InvalidRefreshToken))
+        }
     };
-    let username = claims.sub;
 
-    // Proceed to update user scores
-    let collection = get_collection(&client);
-    let score_update = score_update_req.into_inner();
+    let jwt_token = generate_jwt(&config.auth, &username, &session_id)?;
 
-    match fetch_user_and_handle_response(&collection, &username).await {
-        Ok(mut user) => {
-            update_user_high_scores(&mut user, score_update);
-            save_user_scores(&collection, &username, &user).await
-        }
-        Err(response) => response,
-    }
+    Ok(HttpResponse::Ok()
+        .cookie(create_http_only_cookie(jwt_token))
+        .cookie(create_refresh_cookie(refresh_token))
+        .json(success_response_with_data("Session refreshed.", username)))
 }
 
-pub async fn sign_up(client: web::Data<Client>, req: web::Json<SignUpRequest>) -> HttpResponse {
-    let collection = get_collection(&client);
+#[instrument(skip_all, fields(username = %req.username))]
+pub async fn sign_up(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    verifications: web::Data<dyn EmailVerificationRepository>,
+    captcha: web::Data<dyn CaptchaVerifier>,
+    mailer: web::Data<dyn Mailer>,
+    http_req: HttpRequest,
+    req: web::Json<SignUpRequest>,
+) -> Result<HttpResponse, AppError> {
     let sign_up_request = req.into_inner();
 
     let username = sign_up_request.username.trim();
@@ -103,36 +138,63 @@ pub async fn sign_up(client: web::Data<Client>, req: web::Json<SignUpRequest>) -
     let password = sign_up_request.password.trim();
     let confirmation_password = sign_up_request.confirmation_password.trim();
 
-    if let Some(response) = validate_credentials(
+    validate_credentials(
         username,
         recaptcha_token,
         password,
         Some(confirmation_password),
-    ) {
-        return response;
-    }
-
-    let recaptcha_response = match verify_recaptcha(recaptcha_token).await {
-        Ok(response) => response,
-        Err(err) => {
-            eprintln!("Error verifying reCAPTCHA: {:?}", err);
-            return HttpResponse::InternalServerError()
-                .json(error_response("Error verifying reCAPTCHA."));
-        }
+    )?;
+    let email = match sign_up_request.email.as_deref().map(str::trim) {
+        Some(email) if !email.is_empty() => Some(normalize_email(email)?),
+        _ => None,
     };
 
-    if !recaptcha_response.success {
-        return HttpResponse::BadRequest().json(error_response(&format!(
-            "reCAPTCHA verification failed: {:?}",
-            recaptcha_response.error_codes
-        )));
+    let device = get_device_info(&http_req);
+    let verified = captcha
+        .verify(recaptcha_token, "sign_up", device.ip_address.as_deref())
+        .await;
+    record_captcha_result("sign_up", &verified);
+    verified?;
+
+    process_user_registration(
+        users.get_ref(),
+        username,
+        password,
+        email.clone(),
+        &config.modes,
+    )
+    .await?;
+
+    // The account exists either way; a failed email can be resent later
+    if let Some(email) = email {
+        if let Err(err) = send_verification_email(
+            users.get_ref(),
+            verifications.get_ref(),
+            mailer.get_ref(),
+            &config.mail,
+            &config.auth,
+            username,
+            &email,
+        )
+        .await
+        {
+            tracing::warn!(error = %err, "Could not send the verification email");
+        }
     }
 
-    process_user_registration(&collection, username, password).await
+    Ok(HttpResponse::Ok().json(success_response("User successfully registered.")))
 }
 
-pub async fn login(client: web::Data<Client>, req: web::Json<LoginRequest>) -> HttpResponse {
-    let collection = get_collection(&client);
+#[instrument(skip_all, fields(username = %req.username))]
+pub async fn login(
+    config: web::Data<AppConfig>,
+    users: web::Data<dyn UserRepository>,
+    sessions: web::Data<dyn SessionRepository>,
+    captcha: web::Data<dyn CaptchaVerifier>,
+    lockout: web::Data<LoginLockout>,
+    http_req: HttpRequest,
+    req: web::Json<LoginRequest>,
+) -> Result<HttpResponse, AppError> {
     let login_request = req.into_inner();
 
     let username = login_request.username.trim();
@@ -140,62 +202,58 @@ pub async fn login(client: web::Data<Client>, req: web::Json<LoginRequest>) -> H
     let password = login_request.password.trim();
 
     // Validate credentials and return early if there is an error
-    if let Some(response) = validate_credentials(username, recaptcha_token, password, None) {
-        return response;
-    }
+    validate_credentials(username, recaptcha_token, password, None)?;
 
-    // Verify reCAPTCHA token and return early if there is an error
-    if let Err(response) = verify_recaptcha_and_check(recaptcha_token).await {
-        return response;
-    }
+    // A locked account is rejected before spending a captcha verification
+    let client_ip = get_client_ip(&http_req);
+    lockout.check(username, client_ip)?;
 
-    // Authenticate user
-    let is_authenticated = match authenticate_user(&collection, username, password).await {
-        Ok(is_authenticated) => is_authenticated,
-        Err(_) => {
-            return HttpResponse::InternalServerError()
-                .json(error_response("Error authenticating user."))
-        }
-    };
+    // Verify the captcha token and return early if there is an error
+    let device = get_device_info(&http_req);
+    let verified = captcha
+        .verify(recaptcha_token, "login", device.ip_address.as_deref())
+        .await;
+    record_captcha_result("login", &verified);
+    verified?;
 
-    // Handle authentication result
-    if !is_authenticated {
-        return HttpResponse::Unauthorized().json(error_response("Invalid username or password."));
+    // Authenticate user
+    let authenticated = authenticate_user(users.get_ref(), username, password).await;
+    record_login_result(&authenticated);
+    if !authenticated? {
+        lockout.record_failure(username, client_ip);
+        return Err(AppError::InvalidCredentials);
     }
+    lockout.record_success(username, client_ip);
+
+    // Every login starts its own server-side session
+    let (session_id, refresh_token) = sessions.create(username, &device).await?;
 
     // Generate JWT token
-    let jwt_token = match generate_jwt(username) {
-        Ok(token) => token,
-        Err(_) => {
-            return HttpResponse::InternalServerError()
-                .json(error_response("Error generating JWT token."))
-        }
-    };
+    let jwt_token = generate_jwt(&config.auth, username, &session_id)?;
 
-    // Create HTTP-only cookie
+    // Create HTTP-only cookies
     let cookie = create_http_only_cookie(jwt_token);
+    let refresh_cookie = create_refresh_cookie(refresh_token);
 
-    HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
         .cookie(cookie)
-        .json(success_response_with_data("Login successfully.", username))
+        .cookie(refresh_cookie)
+        .json(success_response_with_data("Login successfully.", username)))
 }
 
+#[instrument(skip_all, fields(username = %username))]
 pub async fn get_user_detail(
-    client: web::Data<Client>,
+    users: web::Data<dyn UserRepository>,
     username: web::Path<String>,
-) -> HttpResponse {
-    let collection = get_collection(&client);
+) -> Result<HttpResponse, AppError> {
     let username = username.into_inner();
 
-    let filter = doc! { "username": &username };
-
-    match collection.find_one(filter).await {
-        Ok(Some(user)) => HttpResponse::Ok().json(user),
-        Ok(None) => HttpResponse::NotFound().json(error_response(&format!(
+    // Never expose the stored password hash
+    match users.find_profile(&username).await? {
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
+        None => Err(AppError::NotFound(format!(
             "No user found with username '{}'",
             username
         ))),
-        Err(err) => HttpResponse::InternalServerError()
-            .json(error_response(&format!("Error retrieving user: {}", err))),
     }
 }
@@ -0,0 +1,244 @@
+use crate::config::modes::InvalidMode;
+use crate::structs::api_response::{error_response, ApiErrorResponse};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use jsonwebtoken::errors::ErrorKind as JwtErrorKind;
+use std::fmt;
+
+// Every error a handler can return. Each variant maps to one HTTP status and
+// one stable `code` that clients can match on; the message is for humans and
+// may change. Infrastructure errors are logged and replaced by a generic
+// message so driver details never reach clients.
+#[derive(Debug)]
+pub enum AppError {
+    Validation(String),
+    InvalidMode(InvalidMode),
+    InvalidTestResult(String),
+    CaptchaFailed(String),
+    Unauthorized,
+    InvalidCredentials,
+    InvalidToken,
+    TokenExpired,
+    SessionRevoked,
+    InvalidRefreshToken,
+    RefreshTokenReused,
+    InvalidResetToken,
+    InvalidVerificationToken,
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    UsernameTaken,
+    EmailTaken,
+    // Seconds until the client may try again
+    RateLimited(u64),
+    AccountLocked(u64),
+    Database(mongodb::error::Error),
+    Serialization(String),
+    Jwt(jsonwebtoken::errors::Error),
+    Upstream(reqwest::Error),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Validation(_) => "validation_error",
+            AppError::InvalidMode(_) => "invalid_mode",
+            AppError::InvalidTestResult(_) => "invalid_test_result",
+            AppError::CaptchaFailed(_) => "captcha_failed",
+            AppError::Unauthorized => "unauthorized",
+            AppError::InvalidCredentials => "invalid_credentials",
+            AppError::InvalidToken => "invalid_token",
+            AppError::TokenExpired => "token_expired",
+            AppError::SessionRevoked => "session_revoked",
+            AppError::InvalidRefreshToken => "invalid_refresh_token",
+            AppError::RefreshTokenReused => "refresh_token_reused",
+            AppError::InvalidResetToken => "invalid_reset_token",
+            AppError::InvalidVerificationToken => "invalid_verification_token",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::UsernameTaken => "username_taken",
+            AppError::EmailTaken => "email_taken",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::AccountLocked(_) => "account_locked",
+            AppError::Database(_) => "database_error",
+            AppError::Serialization(_) => "serialization_error",
+            AppError::Jwt(_) => "token_error",
+            AppError::Upstream(_) => "upstream_error",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Validation(message)
+            | AppError::InvalidTestResult(message)
+            | AppError::CaptchaFailed(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message) => message.clone(),
+            AppError::InvalidMode(invalid_mode) => {
+                format!("Unknown {} '{}'.", invalid_mode.field, invalid_mode.value)
+            }
+            AppError::Unauthorized => "Unauthorized".to_string(),
+            AppError::InvalidCredentials => "Invalid username or password.".to_string(),
+            AppError::InvalidToken => "Invalid token".to_string(),
+            AppError::TokenExpired => "Token has expired.".to_string(),
+            AppError::SessionRevoked => "Session has expired or been revoked.".to_string(),
+            AppError::InvalidRefreshToken => "Invalid or expired refresh token.".to_string(),
+            AppError::RefreshTokenReused => {
+                "Refresh token was already used. The session has been revoked.".to_string()
+            }
+            AppError::InvalidResetToken => {
+                "This password reset link is invalid or has expired.".to_string()
+            }
+            AppError::InvalidVerificationToken => {
+                "This verification link is invalid or has expired.".to_string()
+            }
+            AppError::UsernameTaken => "Username already taken.".to_string(),
+            AppError::EmailTaken => "Email address is already in use.".to_string(),
+            AppError::RateLimited(retry_after) => format!(
+                "Too many requests. Please try again in {} seconds.",
+                retry_after
+            ),
+            AppError::AccountLocked(retry_after) => format!(
+                "Too many failed login attempts. Please try again in {} seconds.",
+                retry_after
+            ),
+            AppError::Database(_) | AppError::Serialization(_) | AppError::Internal(_) => {
+                "An internal error occurred. Please try again later.".to_string()
+            }
+            AppError::Jwt(_) => "Error processing token.".to_string(),
+            AppError::Upstream(_) => {
+                "An upstream service failed. Please try again later.".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database(err) => write!(f, "database error: {}", err),
+            AppError::Serialization(err) => write!(f, "serialization error: {}", err),
+            AppError::Jwt(err) => write!(f, "token error: {}", err),
+            AppError::Upstream(err) => write!(f, "upstream error: {}", err),
+            AppError::Internal(err) => write!(f, "internal error: {}", err),
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Validation(_)
+            | AppError::InvalidMode(_)
+            | AppError::InvalidTestResult(_)
+            | AppError::CaptchaFailed(_)
+            | AppError::InvalidResetToken
+            | AppError::InvalidVerificationToken => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized
+            | AppError::InvalidCredentials
+            | AppError::InvalidToken
+            | AppError::TokenExpired
+            | AppError::SessionRevoked
+            | AppError::InvalidRefreshToken
+            | AppError::RefreshTokenReused => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) | AppError::UsernameTaken | AppError::EmailTaken => {
+                StatusCode::CONFLICT
+            }
+            AppError::RateLimited(_) | AppError::AccountLocked(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::Database(_)
+            | AppError::Serialization(_)
+            | AppError::Jwt(_)
+            | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() || status == StatusCode::BAD_GATEWAY {
+            tracing::error!(error = %self, "Request failed");
+        }
+
+        let data = match self {
+            AppError::InvalidMode(invalid_mode) => serde_json::to_value(invalid_mode).ok(),
+            _ => None,
+        };
+
+        let mut response = HttpResponse::build(status);
+        if let AppError::RateLimited(retry_after) | AppError::AccountLocked(retry_after) = self {
+            response.insert_header((RETRY_AFTER, retry_after.to_string()));
+        }
+
+        response.json(ApiErrorResponse {
+            response: error_response(&self.message()),
+            code: self.code().to_string(),
+            data,
+        })
+    }
+}
+
+impl From<InvalidMode> for AppError {
+    fn from(invalid_mode: InvalidMode) -> Self {
+        AppError::InvalidMode(invalid_mode)
+    }
+}
+
+impl From<mongodb::error::Error> for AppError {
+    fn from(err: mongodb::error::Error) -> Self {
+        AppError::Database(err)
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for AppError {
+    fn from(err: mongodb::bson::ser::Error) -> Self {
+        AppError::Serialization(err.to_string())
+    }
+}
+
+impl From<mongodb::bson::de::Error> for AppError {
+    fn from(err: mongodb::bson::de::Error) -> Self {
+        AppError::Serialization(err.to_string())
+    }
+}
+
+// Problems with a presented token are the client's; anything else (such as
+// a bad signing key) is ours
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            JwtErrorKind::ExpiredSignature => AppError::TokenExpired,
+            JwtErrorKind::InvalidToken
+            | JwtErrorKind::InvalidSignature
+            | JwtErrorKind::InvalidAudience
+            | JwtErrorKind::InvalidIssuer
+            | JwtErrorKind::InvalidSubject
+            | JwtErrorKind::ImmatureSignature
+            | JwtErrorKind::InvalidAlgorithm
+            | JwtErrorKind::MissingRequiredClaim(_)
+            | JwtErrorKind::Base64(_)
+            | JwtErrorKind::Json(_)
+            | JwtErrorKind::Utf8(_) => AppError::InvalidToken,
+            _ => AppError::Jwt(err),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        AppError::Upstream(err)
+    }
+}
+
+impl From<actix_web::error::BlockingError> for AppError {
+    fn from(err: actix_web::error::BlockingError) -> Self {
+        AppError::Internal(err.to_string())
+    }
+}
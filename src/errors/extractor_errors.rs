@@ -0,0 +1,42 @@
+use crate::errors::app_error::AppError;
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::{web, HttpRequest};
+
+// Rejections from actix's own extractors (a malformed body, a missing field,
+// a bad query string) otherwise come back as plain text. Routing them
+// through AppError gives them the same JSON body and code as every other
+// client error.
+
+fn json_error(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        JsonPayloadError::ContentType => "Content-Type must be application/json.".to_string(),
+        JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+            "Request body is too large.".to_string()
+        }
+        JsonPayloadError::Deserialize(err) => format!("Invalid request body: {}", err),
+        _ => "Invalid request body.".to_string(),
+    };
+    AppError::Validation(message).into()
+}
+
+fn query_error(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        QueryPayloadError::Deserialize(err) => format!("Invalid query string: {}", err),
+        _ => "Invalid query string.".to_string(),
+    };
+    AppError::Validation(message).into()
+}
+
+fn path_error(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        PathError::Deserialize(err) => format!("Invalid path: {}", err),
+        _ => "Invalid path.".to_string(),
+    };
+    AppError::Validation(message).into()
+}
+
+pub fn configure_extractor_errors(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(web::JsonConfig::default().error_handler(json_error))
+        .app_data(web::QueryConfig::default().error_handler(query_error))
+        .app_data(web::PathConfig::default().error_handler(path_error));
+}
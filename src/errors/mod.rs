@@ -0,0 +1,2 @@
+pub mod app_error;
+pub mod extractor_errors;
@@ -0,0 +1,59 @@
+use crate::config::app_config::AppConfig;
+use crate::errors::app_error::AppError;
+use crate::repositories::session_repository::SessionRepository;
+use crate::services::user_service::verify_jwt;
+use actix_web::dev::Payload;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{web, FromRequest, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+
+// The caller of a protected handler, resolved from the access token in the
+// "user_jwt_token" cookie or an "Authorization: Bearer" header
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub username: String,
+    pub session_id: String,
+}
+
+fn extract_token(req: &HttpRequest) -> Option<String> {
+    // The cookie wins so browser clients behave exactly as before
+    if let Some(cookie) = req.cookie("user_jwt_token") {
+        return Some(cookie.value().to_string());
+    }
+
+    let header = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = header.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") || token.trim().is_empty() {
+        return None;
+    }
+    Some(token.trim().to_string())
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = extract_token(req);
+        let sessions = req.app_data::<web::Data<dyn SessionRepository>>().cloned();
+        let config = req.app_data::<web::Data<AppConfig>>().cloned();
+
+        Box::pin(async move {
+            let token = token.ok_or(AppError::Unauthorized)?;
+            let sessions = sessions.ok_or_else(|| {
+                AppError::Internal("SessionRepository is not registered as app data".to_string())
+            })?;
+            let config = config.ok_or_else(|| {
+                AppError::Internal("AppConfig is not registered as app data".to_string())
+            })?;
+
+            // Verify the token against its session and extract claims
+            let claims = verify_jwt(&config.auth, sessions.get_ref(), &token).await?;
+
+            Ok(AuthenticatedUser {
+                username: claims.sub,
+                session_id: claims.sid,
+            })
+        })
+    }
+}
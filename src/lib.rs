@@ -1,7 +1,12 @@
 pub mod config;
 pub mod constants;
 pub mod controllers;
+pub mod errors;
+pub mod extractors;
+pub mod middleware;
+pub mod migrations;
 pub mod models;
+pub mod repositories;
 pub mod routes;
 pub mod services;
 pub mod structs;
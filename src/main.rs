@@ -1,26 +1,178 @@
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
 use dotenv::dotenv;
+use eletypes_backend::config::app_config::AppConfig;
+use eletypes_backend::config::captcha::load_captcha_verifier;
 use eletypes_backend::config::cors::configure_cors;
 use eletypes_backend::config::database::{connect_to_mongodb, get_server_address};
+use eletypes_backend::config::logging::init_tracing;
+use eletypes_backend::config::mail::load_mailer;
+use eletypes_backend::config::password::configure_argon2;
+use eletypes_backend::constants::{
+    DB_NAME, EMAIL_VERIFICATIONS_COLL_NAME, PASSWORD_RESETS_COLL_NAME, RACE_RESULTS_COLL_NAME,
+    SESSIONS_COLL_NAME, TEST_RESULTS_COLL_NAME, TEST_SESSIONS_COLL_NAME,
+};
+use eletypes_backend::errors::extractor_errors::configure_extractor_errors;
+use eletypes_backend::middleware::metrics::record_http_metrics;
+use eletypes_backend::middleware::rate_limit::enforce_rate_limits;
+use eletypes_backend::middleware::request_id::assign_request_id;
+use eletypes_backend::migrations::runner::run_migrations;
+use eletypes_backend::repositories::email_verification_repository::{
+    EmailVerificationRepository, MongoEmailVerificationRepository,
+};
+use eletypes_backend::repositories::leaderboard_repository::{
+    LeaderboardRepository, MongoLeaderboardRepository,
+};
+use eletypes_backend::repositories::password_reset_repository::{
+    MongoPasswordResetRepository, PasswordResetRepository,
+};
+use eletypes_backend::repositories::session_repository::{
+    MongoSessionRepository, SessionRepository,
+};
+use eletypes_backend::repositories::test_result_repository::{
+    MongoTestResultRepository, TestResultRepository,
+};
+use eletypes_backend::repositories::user_repository::{MongoUserRepository, UserRepository};
 use eletypes_backend::routes::{
-    leaderboard_routes::configure_leaderboard_routes, user_routes::configure_user_routes,
+    email_routes::configure_email_routes, health_routes::configure_health_routes,
+    leaderboard_routes::configure_leaderboard_routes, metrics_routes::configure_metrics_routes,
+    password_routes::configure_password_routes, race_routes::configure_race_routes,
+    session_routes::configure_session_routes, test_result_routes::configure_test_result_routes,
+    typing_test_routes::configure_typing_test_routes, user_routes::configure_user_routes,
 };
+use eletypes_backend::services::leaderboard_hub::LeaderboardHub;
+use eletypes_backend::services::race_rooms::RaceRooms;
+use eletypes_backend::services::rate_limit_service::{LoginLockout, RateLimiter};
+use eletypes_backend::utils::helpers::{get_collection, get_collection_by_name};
+use std::sync::Arc;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
-    let address = get_server_address();
-    let mongodb_client = connect_to_mongodb().await;
+    // `eletypes-backend migrate` applies pending migrations and exits
+    let migrate_only = match std::env::args().nth(1).as_deref() {
+        None => false,
+        Some("migrate") => true,
+        Some(command) => {
+            eprintln!(
+                "Unknown command '{}'. Usage: eletypes-backend [migrate]",
+                command
+            );
+            std::process::exit(2);
+        }
+    };
+
+    // Fail fast on bad configuration instead of on the first request
+    let config = match AppConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    init_tracing(&config.logging);
+    configure_argon2(&config.password);
+
+    let address = get_server_address(&config.server);
+    let mongodb_client = connect_to_mongodb(&config.database).await;
+
+    if migrate_only || config.database.run_migrations {
+        let database = mongodb_client.database(DB_NAME);
+        match run_migrations(&database, &config.modes).await {
+            Ok(applied) => tracing::info!(count = applied.len(), "Applied migrations"),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to run migrations");
+                std::process::exit(1);
+            }
+        }
+        if migrate_only {
+            return Ok(());
+        }
+    }
+
+    let mode_registry = web::Data::new(config.modes.clone());
+    let captcha_verifier = web::Data::from(load_captcha_verifier(&config.captcha));
+    let mailer = web::Data::from(load_mailer(&config.mail));
+    let users: Arc<dyn UserRepository> =
+        Arc::new(MongoUserRepository::new(get_collection(&mongodb_client)));
+    let leaderboard: Arc<dyn LeaderboardRepository> = Arc::new(MongoLeaderboardRepository::new(
+        get_collection(&mongodb_client),
+    ));
+    let sessions: Arc<dyn SessionRepository> = Arc::new(MongoSessionRepository::new(
+        get_collection_by_name(&mongodb_client, SESSIONS_COLL_NAME),
+    ));
+    let results: Arc<dyn TestResultRepository> = Arc::new(MongoTestResultRepository::new(
+        get_collection_by_name(&mongodb_client, TEST_RESULTS_COLL_NAME),
+        get_collection_by_name(&mongodb_client, TEST_SESSIONS_COLL_NAME),
+    ));
+    let verifications: Arc<dyn EmailVerificationRepository> =
+        Arc::new(MongoEmailVerificationRepository::new(
+            get_collection_by_name(&mongodb_client, EMAIL_VERIFICATIONS_COLL_NAME),
+        ));
+    let resets: Arc<dyn PasswordResetRepository> = Arc::new(MongoPasswordResetRepository::new(
+        get_collection_by_name(&mongodb_client, PASSWORD_RESETS_COLL_NAME),
+    ));
+    let user_repository = web::Data::from(users);
+    let leaderboard_repository = web::Data::from(leaderboard);
+    let session_repository = web::Data::from(sessions);
+    let test_result_repository = web::Data::from(results);
+    let email_verification_repository = web::Data::from(verifications);
+    let password_reset_repository = web::Data::from(resets);
+    // Created once so that every worker shares the same hub
+    let leaderboard_hub =
+        web::Data::new(LeaderboardHub::new(config.realtime.leaderboard_push_top_n));
+    let race_rooms = web::Data::new(RaceRooms::new(get_collection_by_name(
+        &mongodb_client,
+        RACE_RESULTS_COLL_NAME,
+    )));
+
+    // Shared so that the limits hold across workers
+    let rate_limit_policies = if config.rate_limit.enabled {
+        config.rate_limit.policies.clone()
+    } else {
+        Vec::new()
+    };
+    let rate_limiter = web::Data::new(RateLimiter::new(rate_limit_policies));
+    let login_lockout = web::Data::new(LoginLockout::new(config.rate_limit.lockout.clone()));
+
+    let cors_config = config.cors.clone();
+    let app_config = web::Data::new(config);
 
-    println!("Server is running on {}", address);
+    tracing::info!(%address, "Server is running");
 
     HttpServer::new(move || {
         App::new()
-            .wrap(configure_cors())
+            .wrap(from_fn(enforce_rate_limits))
+            .wrap(configure_cors(&cors_config))
+            .wrap(from_fn(record_http_metrics))
+            .wrap(from_fn(assign_request_id))
             .app_data(web::Data::new(mongodb_client.clone()))
+            .app_data(app_config.clone())
+            .app_data(mode_registry.clone())
+            .app_data(captcha_verifier.clone())
+            .app_data(mailer.clone())
+            .app_data(user_repository.clone())
+            .app_data(leaderboard_repository.clone())
+            .app_data(session_repository.clone())
+            .app_data(test_result_repository.clone())
+            .app_data(email_verification_repository.clone())
+            .app_data(password_reset_repository.clone())
+            .app_data(leaderboard_hub.clone())
+            .app_data(race_rooms.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(login_lockout.clone())
+            .configure(configure_extractor_errors)
+            .configure(configure_health_routes)
+            .configure(configure_email_routes)
             .configure(configure_leaderboard_routes)
+            .configure(configure_metrics_routes)
+            .configure(configure_password_routes)
             .configure(configure_user_routes)
+            .configure(configure_typing_test_routes)
+            .configure(configure_test_result_routes)
+            .configure(configure_race_routes)
+            .configure(configure_session_routes)
     })
     .bind(address)?
     .run()
@@ -0,0 +1,54 @@
+use crate::config::app_config::AppConfig;
+use crate::config::rate_limit::RateLimitKey;
+use crate::services::rate_limit_service::RateLimiter;
+use crate::services::user_service::{get_client_ip, read_username};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpRequest};
+
+fn get_client_key(req: &HttpRequest) -> String {
+    match get_client_ip(req) {
+        Some(ip) => format!("ip:{}", ip),
+        None => "ip:".to_string(),
+    }
+}
+
+// The signature is checked but not the session, which is enough to pick a
+// bucket; the handler still authenticates the request properly
+fn get_user_key(req: &HttpRequest) -> Option<String> {
+    let config = req.app_data::<web::Data<AppConfig>>()?;
+    let token = req.cookie("user_jwt_token")?;
+    read_username(&config.auth, token.value()).map(|username| format!("user:{}", username))
+}
+
+// Applies the configured per-route token buckets and answers 429 with
+// Retry-After once a bucket runs dry
+pub async fn enforce_rate_limits(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limiter = match req.app_data::<web::Data<RateLimiter>>() {
+        Some(limiter) => limiter.clone(),
+        None => return next.call(req).await.map(|res| res.map_into_boxed_body()),
+    };
+
+    if let Some(route) = req.match_pattern() {
+        let method = req.method().as_str().to_string();
+        for (index, policy) in limiter.matching_policies(&method, &route) {
+            let key = match policy.key {
+                RateLimitKey::Ip => get_client_key(req.request()),
+                RateLimitKey::Username => {
+                    get_user_key(req.request()).unwrap_or_else(|| get_client_key(req.request()))
+                }
+            };
+
+            if let Err(err) = limiter.acquire(index, &key) {
+                tracing::warn!(route = %route, key = %key, "Rate limit exceeded");
+                return Ok(req.error_response(err));
+            }
+        }
+    }
+
+    next.call(req).await.map(|res| res.map_into_boxed_body())
+}
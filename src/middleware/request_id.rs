@@ -0,0 +1,83 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpMessage};
+use rand::RngCore;
+use std::time::Instant;
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// Stored in the request extensions for handlers that want to reference it
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+// Upstream proxies may already have assigned an id; anything unusual in it
+// could end up in log lines, so only plain tokens are reused
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn generate_request_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Assigns every request an id, runs it inside a span carrying that id and
+// echoes it back in the X-Request-Id response header. The query string is
+// left out of the logs since it can carry tokens.
+pub async fn assign_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(|value| value.to_string())
+        .unwrap_or_else(generate_request_id);
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+    );
+
+    async move {
+        let started = Instant::now();
+        let result = next.call(req).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(mut response) => {
+                tracing::info!(
+                    status = response.status().as_u16(),
+                    latency_ms,
+                    "request completed"
+                );
+                if let Ok(value) = HeaderValue::from_str(&request_id) {
+                    response
+                        .headers_mut()
+                        .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                }
+                Ok(response)
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, latency_ms, "request failed");
+                Err(err)
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
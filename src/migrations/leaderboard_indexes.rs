@@ -0,0 +1,76 @@
+use crate::config::modes::ModeRegistry;
+use crate::constants::COLL_NAME;
+use crate::models::mode::GameMode;
+use mongodb::bson::{doc, Document};
+use mongodb::options::IndexOptions;
+use mongodb::{Database, IndexModel};
+use std::collections::HashSet;
+
+const LEADERBOARD_INDEX_PREFIX: &str = "leaderboard_";
+
+fn get_leaderboard_index_name(mode: &GameMode) -> String {
+    format!(
+        "{}{}_{}_{}",
+        LEADERBOARD_INDEX_PREFIX,
+        mode.language(),
+        mode.difficulty(),
+        mode.duration()
+    )
+}
+
+fn create_leaderboard_index(mode: &GameMode) -> IndexModel {
+    let score_path = mode.score_path();
+
+    // Mirrors the sort in create_sort_stage so rank and page queries can walk
+    // the index instead of sorting every user
+    let keys = doc! {
+        format!("{}.wpm", score_path): -1,
+        format!("{}.accuracy", score_path): -1,
+        format!("{}.date", score_path): 1,
+        "_id": 1,
+    };
+    let options = IndexOptions::builder()
+        .name(get_leaderboard_index_name(mode))
+        .build();
+
+    IndexModel::builder().keys(keys).options(options).build()
+}
+
+fn get_all_modes(modes: &ModeRegistry) -> Vec<GameMode> {
+    let mut all_modes = Vec::new();
+    for language in modes.languages.iter() {
+        for difficulty in modes.difficulties.iter() {
+            for duration in modes.durations.iter() {
+                all_modes.push(GameMode::new(language, difficulty, duration));
+            }
+        }
+    }
+    all_modes
+}
+
+// The set of modes comes from configuration and can change between deploys,
+// so these indexes are ensured on every run rather than through a versioned
+// migration. Creating an index that already exists is a no-op; indexes of
+// modes no longer configured are dropped so they do not count towards
+// MongoDB's per-collection index limit.
+pub async fn ensure_leaderboard_indexes(
+    db: &Database,
+    modes: &ModeRegistry,
+) -> Result<usize, mongodb::error::Error> {
+    let all_modes = get_all_modes(modes);
+    let wanted: HashSet<String> = all_modes.iter().map(get_leaderboard_index_name).collect();
+    let collection = db.collection::<Document>(COLL_NAME);
+
+    for name in collection.list_index_names().await? {
+        if name.starts_with(LEADERBOARD_INDEX_PREFIX) && !wanted.contains(&name) {
+            tracing::info!(index = %name, "Dropping the index of a removed mode");
+            collection.drop_index(name).await?;
+        }
+    }
+
+    let indexes: Vec<IndexModel> = all_modes.iter().map(create_leaderboard_index).collect();
+    let count = indexes.len();
+    collection.create_indexes(indexes).await?;
+
+    Ok(count)
+}
@@ -0,0 +1,3 @@
+pub mod leaderboard_indexes;
+pub mod runner;
+pub mod versions;
@@ -0,0 +1,95 @@
+use crate::config::modes::ModeRegistry;
+use crate::constants::MIGRATIONS_COLL_NAME;
+use crate::migrations::leaderboard_indexes::ensure_leaderboard_indexes;
+use crate::migrations::versions::get_migrations;
+use crate::utils::helpers::is_duplicate_key_error;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::Database;
+use std::collections::HashSet;
+
+async fn fetch_applied_versions(db: &Database) -> Result<HashSet<i64>, mongodb::error::Error> {
+    let mut cursor = db
+        .collection::<Document>(MIGRATIONS_COLL_NAME)
+        .find(doc! {})
+        .await?;
+    let mut versions = HashSet::new();
+
+    while let Some(record) = cursor.try_next().await? {
+        match record
+            .get_i64("_id")
+            .or_else(|_| record.get_i32("_id").map(i64::from))
+        {
+            Ok(version) => {
+                versions.insert(version);
+            }
+            Err(_) => tracing::warn!(record = ?record, "Ignoring malformed migration record"),
+        }
+    }
+
+    Ok(versions)
+}
+
+async fn record_migration(
+    db: &Database,
+    version: u32,
+    name: &str,
+) -> Result<(), mongodb::error::Error> {
+    let record = doc! {
+        "_id": version as i64,
+        "name": name,
+        "applied_at": BsonDateTime::now(),
+    };
+
+    match db
+        .collection::<Document>(MIGRATIONS_COLL_NAME)
+        .insert_one(record)
+        .await
+    {
+        Ok(_) => Ok(()),
+        // Another instance applied the same migration at the same time
+        Err(err) if is_duplicate_key_error(&err) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+// Applies every pending migration in version order and brings the leaderboard
+// indexes in line with the mode registry. Returns the names of the migrations
+// applied by this run.
+pub async fn run_migrations(
+    db: &Database,
+    modes: &ModeRegistry,
+) -> Result<Vec<&'static str>, mongodb::error::Error> {
+    let applied_versions = fetch_applied_versions(db).await?;
+    let mut migrations = get_migrations();
+    migrations.sort_by_key(|migration| migration.version());
+
+    let latest_version = migrations.last().map_or(0, |m| m.version() as i64);
+    if let Some(unknown) = applied_versions.iter().find(|v| **v > latest_version) {
+        tracing::warn!(
+            version = unknown,
+            "Database has a migration applied that this build does not know about"
+        );
+    }
+
+    let mut applied = Vec::new();
+    for migration in migrations.iter() {
+        if applied_versions.contains(&(migration.version() as i64)) {
+            continue;
+        }
+
+        tracing::info!(
+            version = migration.version(),
+            name = migration.name(),
+            "Applying migration"
+        );
+        migration.up(db).await?;
+        record_migration(db, migration.version(), migration.name()).await?;
+        applied.push(migration.name());
+    }
+
+    let index_count = ensure_leaderboard_indexes(db, modes).await?;
+    tracing::info!(index_count, "Ensured leaderboard indexes");
+
+    Ok(applied)
+}
@@ -0,0 +1,342 @@
+use crate::constants::{
+    COLL_NAME, EMAIL_INDEX_NAME, EMAIL_VERIFICATIONS_COLL_NAME, PASSWORD_RESETS_COLL_NAME,
+    RACE_RESULTS_COLL_NAME, SESSIONS_COLL_NAME, TEST_RESULTS_COLL_NAME, TEST_SESSIONS_COLL_NAME,
+};
+use crate::models::user::HighScores;
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, to_bson, Bson, Document};
+use mongodb::error::ErrorKind;
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, Database, IndexModel};
+use std::time::Duration;
+
+// A schema change applied once per database. Each migration must be safe to
+// run again, since two instances booting together may both apply it before
+// either records it.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    // Applied in ascending order; never reuse or renumber a released version
+    fn version(&self) -> u32;
+
+    fn name(&self) -> &'static str;
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error>;
+}
+
+fn create_index(keys: Document, name: &str, unique: bool) -> IndexModel {
+    let options = IndexOptions::builder()
+        .name(name.to_string())
+        .unique(unique)
+        .build();
+
+    IndexModel::builder().keys(keys).options(options).build()
+}
+
+// Sign-ups check for an existing user before inserting, which two concurrent
+// requests can both pass. The index makes the second insert fail instead.
+struct UniqueUsernameIndex;
+
+#[async_trait]
+impl Migration for UniqueUsernameIndex {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "users_unique_username"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        let users = db.collection::<Document>(COLL_NAME);
+
+        // Building the index would fail on duplicates anyway; naming them
+        // lets an operator merge or rename the accounts and boot again
+        let duplicates = find_duplicate_usernames(&users).await?;
+        if !duplicates.is_empty() {
+            tracing::error!(
+                count = duplicates.len(),
+                usernames = ?duplicates,
+                "Usernames shared by several users; merge or rename the extra accounts before starting again"
+            );
+            return Err(mongodb::error::Error::custom(format!(
+                "{} usernames are shared by several users: {}",
+                duplicates.len(),
+                duplicates.join(", ")
+            )));
+        }
+
+        users
+            .create_index(create_index(
+                doc! { "username": 1 },
+                "username_unique",
+                true,
+            ))
+            .await?;
+        Ok(())
+    }
+}
+
+async fn find_duplicate_usernames(
+    users: &Collection<Document>,
+) -> Result<Vec<String>, mongodb::error::Error> {
+    let pipeline = vec![
+        doc! { "$group": { "_id": "$username", "count": { "$sum": 1 } } },
+        doc! { "$match": { "count": { "$gt": 1 } } },
+        doc! { "$sort": { "_id": 1 } },
+    ];
+
+    let mut cursor = users.aggregate(pipeline).await?;
+    let mut usernames = Vec::new();
+    while let Some(group) = cursor.try_next().await? {
+        usernames.push(match group.get("_id") {
+            Some(Bson::String(username)) => username.clone(),
+            other => format!("{:?}", other),
+        });
+    }
+
+    Ok(usernames)
+}
+
+// Indexes behind the per-user history queries and the windowed leaderboards
+struct HistoryIndexes;
+
+#[async_trait]
+impl Migration for HistoryIndexes {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "history_indexes"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        db.collection::<Document>(TEST_RESULTS_COLL_NAME)
+            .create_indexes(vec![
+                create_index(
+                    doc! { "username": 1, "timestamp": -1, "_id": -1 },
+                    "username_timestamp",
+                    false,
+                ),
+                create_index(
+                    doc! { "language": 1, "difficulty": 1, "duration": 1, "timestamp": 1 },
+                    "mode_timestamp",
+                    false,
+                ),
+            ])
+            .await?;
+
+        db.collection::<Document>(RACE_RESULTS_COLL_NAME)
+            .create_index(create_index(
+                doc! { "placements.username": 1, "started_at": -1, "_id": -1 },
+                "placements_username_started_at",
+                false,
+            ))
+            .await?;
+
+        db.collection::<Document>(SESSIONS_COLL_NAME)
+            .create_index(create_index(
+                doc! { "username": 1, "last_seen_at": -1 },
+                "username_last_seen_at",
+                false,
+            ))
+            .await?;
+
+        Ok(())
+    }
+}
+
+// A typing test session may only be submitted once; the upsert in
+// claim_test_session relies on this to stay race-free
+struct UniqueTestSessionIndex;
+
+#[async_trait]
+impl Migration for UniqueTestSessionIndex {
+    fn version(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "test_sessions_unique_test_id"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        db.collection::<Document>(TEST_SESSIONS_COLL_NAME)
+            .create_index(create_index(doc! { "test_id": 1 }, "test_id_unique", true))
+            .await?;
+        Ok(())
+    }
+}
+
+// Lets MongoDB delete reset tokens once they expire and keeps the per-user
+// cleanup on a new request cheap
+struct PasswordResetIndexes;
+
+#[async_trait]
+impl Migration for PasswordResetIndexes {
+    fn version(&self) -> u32 {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "password_reset_indexes"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        let expiry_options = IndexOptions::builder()
+            .name("expires_at_ttl".to_string())
+            .expire_after(Duration::from_secs(0))
+            .build();
+
+        db.collection::<Document>(PASSWORD_RESETS_COLL_NAME)
+            .create_indexes(vec![
+                create_index(doc! { "username": 1 }, "username", false),
+                IndexModel::builder()
+                    .keys(doc! { "expires_at": 1 })
+                    .options(expiry_options)
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
+
+// Lookups for verification links. The unique email index this used to
+// create is replaced by VerifiedEmailIndex.
+struct EmailIndexes;
+
+#[async_trait]
+impl Migration for EmailIndexes {
+    fn version(&self) -> u32 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "email_indexes"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        let expiry_options = IndexOptions::builder()
+            .name("expires_at_ttl".to_string())
+            .expire_after(Duration::from_secs(0))
+            .build();
+
+        db.collection::<Document>(EMAIL_VERIFICATIONS_COLL_NAME)
+            .create_indexes(vec![
+                create_index(
+                    doc! { "username": 1, "created_at": -1 },
+                    "username_created_at",
+                    false,
+                ),
+                IndexModel::builder()
+                    .keys(doc! { "expires_at": 1 })
+                    .options(expiry_options)
+                    .build(),
+            ])
+            .await?;
+        Ok(())
+    }
+}
+
+// Score dates used to be stored with as many fractional digits as needed,
+// which does not sort chronologically as a string. Reading and writing every
+// user's high scores back through the model stores them at a fixed width.
+struct FixedWidthScoreDates;
+
+#[async_trait]
+impl Migration for FixedWidthScoreDates {
+    fn version(&self) -> u32 {
+        6
+    }
+
+    fn name(&self) -> &'static str {
+        "fixed_width_score_dates"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        let users = db.collection::<Document>(COLL_NAME);
+        let mut cursor = users
+            .find(doc! { "high_scores": { "$type": "object" } })
+            .projection(doc! { "high_scores": 1 })
+            .await?;
+
+        while let Some(user) = cursor.try_next().await? {
+            let high_scores: HighScores = match user.get_document("high_scores") {
+                Ok(high_scores) => from_document(high_scores.clone())?,
+                Err(_) => continue,
+            };
+            users
+                .update_one(
+                    doc! { "_id": user.get("_id") },
+                    doc! { "$set": { "high_scores": to_bson(&high_scores)? } },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// Code of the error dropping an index that does not exist
+const INDEX_NOT_FOUND_CODE: i32 = 27;
+
+// Two accounts cannot both verify the same address. Unverified addresses stay
+// out of the index, so typing in someone else's address neither blocks its
+// owner nor tells whether it is registered; the conflict surfaces when the
+// second link is followed.
+struct VerifiedEmailIndex;
+
+#[async_trait]
+impl Migration for VerifiedEmailIndex {
+    fn version(&self) -> u32 {
+        7
+    }
+
+    fn name(&self) -> &'static str {
+        "verified_email_index"
+    }
+
+    async fn up(&self, db: &Database) -> Result<(), mongodb::error::Error> {
+        let users = db.collection::<Document>(COLL_NAME);
+
+        // Drop the index that also covered unverified addresses, if present
+        if let Err(err) = users.drop_index(EMAIL_INDEX_NAME).await {
+            match err.kind.as_ref() {
+                ErrorKind::Command(command_error) if command_error.code == INDEX_NOT_FOUND_CODE => {
+                }
+                _ => return Err(err),
+            }
+        }
+
+        let email_options = IndexOptions::builder()
+            .name(EMAIL_INDEX_NAME.to_string())
+            .unique(true)
+            .partial_filter_expression(doc! {
+                "email": { "$type": "string" },
+                "email_verified": true,
+            })
+            .build();
+
+        users
+            .create_index(
+                IndexModel::builder()
+                    .keys(doc! { "email": 1 })
+                    .options(email_options)
+                    .build(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+pub fn get_migrations() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(UniqueUsernameIndex),
+        Box::new(HistoryIndexes),
+        Box::new(UniqueTestSessionIndex),
+        Box::new(PasswordResetIndexes),
+        Box::new(EmailIndexes),
+        Box::new(FixedWidthScoreDates),
+        Box::new(VerifiedEmailIndex),
+    ]
+}
@@ -1 +1,7 @@
+pub mod email_verification;
+pub mod mode;
+pub mod password_reset;
+pub mod race_result;
+pub mod session;
+pub mod test_result;
 pub mod user;
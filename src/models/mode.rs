@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+// A language/difficulty/duration combination that has been checked against the
+// mode registry, so its keys are safe to use inside BSON field paths.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct GameMode {
+    language: String,
+    difficulty: String,
+    duration: String,
+}
+
+impl GameMode {
+    pub(crate) fn new(language: &str, difficulty: &str, duration: &str) -> Self {
+        GameMode {
+            language: language.to_string(),
+            difficulty: difficulty.to_string(),
+            duration: duration.to_string(),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn difficulty(&self) -> &str {
+        &self.difficulty
+    }
+
+    pub fn duration(&self) -> &str {
+        &self.duration
+    }
+
+    pub fn score_path(&self) -> String {
+        format!(
+            "high_scores.languages.{}.difficulties.{}.scores.{}",
+            self.language, self.difficulty, self.duration
+        )
+    }
+}
@@ -0,0 +1,16 @@
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PasswordReset {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub username: String,
+    // Only the SHA-256 hash of the emailed secret is stored
+    pub token_hash: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub used_at: Option<DateTime>,
+}
@@ -0,0 +1,26 @@
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RacePlacement {
+    pub place: u32,
+    pub username: String,
+    pub wpm: u32,
+    // Characters typed correctly when the race ended
+    pub progress: u32,
+    pub finished: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RaceResultRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub room_code: String,
+    pub language: String,
+    pub difficulty: String,
+    pub text_length: u32,
+    pub started_at: DateTime,
+    pub finished_at: DateTime,
+    pub placements: Vec<RacePlacement>,
+}
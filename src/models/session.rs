@@ -0,0 +1,53 @@
+use crate::utils::redact::Redacted;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct UserSession {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub username: String,
+    // Only SHA-256 hashes of refresh tokens are stored
+    pub refresh_token_hash: String,
+    // Hashes of tokens that were already rotated out, kept to detect reuse
+    #[serde(default)]
+    pub previous_token_hashes: Vec<String>,
+    // Device metadata captured at login and refreshed on token rotation
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub ip_address: Option<String>,
+    pub created_at: DateTime,
+    pub last_seen_at: DateTime,
+    pub expires_at: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_reason: Option<String>,
+}
+
+impl fmt::Debug for UserSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserSession")
+            .field("id", &self.id)
+            .field("username", &self.username)
+            .field("refresh_token_hash", &Redacted)
+            .field("previous_token_hashes", &Redacted)
+            .field("user_agent", &self.user_agent)
+            .field("ip_address", &self.ip_address)
+            .field("created_at", &self.created_at)
+            .field("last_seen_at", &self.last_seen_at)
+            .field("expires_at", &self.expires_at)
+            .field("revoked_at", &self.revoked_at)
+            .field("revoked_reason", &self.revoked_reason)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DeviceInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
@@ -0,0 +1,28 @@
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CharStats {
+    pub correct: u32,
+    pub incorrect: u32,
+    pub extra: u32,
+    pub missed: u32,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TestResultRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub username: String,
+    pub language: String,
+    pub difficulty: String,
+    pub duration: String,
+    pub wpm: u32,
+    pub raw_wpm: u32,
+    pub accuracy: f32,
+    // Stored as a BSON date so results can be filtered by date range
+    pub timestamp: DateTime,
+    #[serde(default)]
+    pub char_stats: Option<CharStats>,
+}
@@ -1,15 +1,30 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use crate::config::modes::ModeRegistry;
+use crate::utils::redact::Redacted;
+use chrono::{DateTime, SecondsFormat, Utc};
+use mongodb::bson::DateTime as BsonDateTime;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
+use std::fmt;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Score {
     pub wpm: u32,
     pub raw_wpm: u32,
     pub accuracy: f32,
+    #[serde(serialize_with = "serialize_score_date")]
     pub date: DateTime<Utc>,
 }
 
+// Leaderboard ties are broken by comparing stored dates as strings, so they
+// are always written with millisecond precision, the same fixed width the
+// windowed leaderboards format their dates with
+fn serialize_score_date<S: Serializer>(
+    date: &DateTime<Utc>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&date.to_rfc3339_opts(SecondsFormat::Millis, true))
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct DifficultyScores {
     pub scores: HashMap<String, Score>,
@@ -26,7 +41,20 @@ pub struct HighScores {
     pub languages: HashMap<String, LanguageScores>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+// Client preferences; unset fields fall back to the client's defaults
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UserSettings {
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub difficulty: Option<String>,
+    #[serde(default)]
+    pub duration: Option<String>,
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct User {
     pub username: String,
     pub password: String,
@@ -36,6 +64,43 @@ pub struct User {
     pub high_scores: Option<HighScores>,
     #[serde(default = "default_created_at")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub settings: UserSettings,
+    // Banned users cannot log in and are left off the leaderboards
+    #[serde(default)]
+    pub banned: bool,
+    // Where password reset links are sent; never shown on public profiles.
+    // Stored lowercased; only one user can have a given address verified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    // Whether the user followed a verification link sent to `email`
+    #[serde(default)]
+    pub email_verified: bool,
+    // When the last verification email went out. Kept on the user rather
+    // than read from the verification links, which come and go with the
+    // address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_verification_sent_at: Option<BsonDateTime>,
+}
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("username", &self.username)
+            .field("password", &Redacted)
+            .field("completed_tests", &self.completed_tests)
+            .field("high_scores", &self.high_scores)
+            .field("created_at", &self.created_at)
+            .field("settings", &self.settings)
+            .field("banned", &self.banned)
+            .field("email", &self.email)
+            .field("email_verified", &self.email_verified)
+            .field(
+                "email_verification_sent_at",
+                &self.email_verification_sent_at,
+            )
+            .finish()
+    }
 }
 
 // Provide default for completed_tests
@@ -53,7 +118,7 @@ fn default_languages() -> HashMap<String, LanguageScores> {
     HashMap::new() // Return an empty map as default
 }
 
-pub fn default_user() -> User {
+pub fn default_user(modes: &ModeRegistry) -> User {
     let default_score = Score {
         wpm: 0,
         raw_wpm: 0,
@@ -62,12 +127,12 @@ pub fn default_user() -> User {
     };
 
     let mut difficulty_scores = HashMap::new();
-    for duration in ["15", "30", "60", "90"].iter() {
+    for duration in modes.durations.iter() {
         difficulty_scores.insert(duration.to_string(), default_score.clone());
     }
 
     let mut difficulties = HashMap::new();
-    for difficulty in ["hard", "normal"].iter() {
+    for difficulty in modes.difficulties.iter() {
         difficulties.insert(
             difficulty.to_string(),
             DifficultyScores {
@@ -77,7 +142,7 @@ pub fn default_user() -> User {
     }
 
     let mut languages = HashMap::new();
-    for language in ["english", "chinese"].iter() {
+    for language in modes.languages.iter() {
         languages.insert(
             language.to_string(),
             LanguageScores {
@@ -92,5 +157,10 @@ pub fn default_user() -> User {
         completed_tests: Some(0),                    // Default to Some(0)
         high_scores: Some(HighScores { languages }), // Ensure high_scores is Some with a valid structure
         created_at: Some(Utc::now()),                // Automatically set to current time
+        settings: UserSettings::default(),
+        banned: false,
+        email: None,
+        email_verified: false,
+        email_verification_sent_at: None,
     }
 }
@@ -0,0 +1,113 @@
+use crate::errors::app_error::AppError;
+use crate::models::email_verification::EmailVerification;
+use crate::services::metrics_service::time_db_operation;
+use crate::utils::token::{format_token, generate_token_secret, hash_token_secret, parse_token};
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, from_document, to_document, DateTime as BsonDateTime, Document};
+use mongodb::Collection;
+
+// A fresh verification along with the token to email
+pub(crate) fn new_email_verification(
+    username: &str,
+    email: &str,
+    ttl_hours: i64,
+) -> (EmailVerification, String) {
+    let secret = generate_token_secret();
+    let now = chrono::Utc::now();
+    let verification = EmailVerification {
+        id: ObjectId::new(),
+        username: username.to_string(),
+        email: email.to_string(),
+        token_hash: hash_token_secret(&secret),
+        created_at: BsonDateTime::from_millis(now.timestamp_millis()),
+        expires_at: BsonDateTime::from_millis(
+            (now + chrono::Duration::hours(ttl_hours)).timestamp_millis(),
+        ),
+        used_at: None,
+    };
+    let token = format_token(&verification.id, &secret);
+    (verification, token)
+}
+
+// One-time links that confirm a user owns an email address
+#[async_trait]
+pub trait EmailVerificationRepository: Send + Sync {
+    // Stores a new verification and returns the token to email. Older unused
+    // tokens of the user are dropped so only the latest link works.
+    async fn create(&self, username: &str, email: &str, ttl_hours: i64)
+        -> Result<String, AppError>;
+
+    // Marks the verification as used if the token is valid, unused and not
+    // expired, in a single step so a link can only be redeemed once
+    async fn consume(&self, token: &str) -> Result<Option<EmailVerification>, AppError>;
+
+    async fn delete_pending(&self, username: &str) -> Result<(), AppError>;
+}
+
+pub struct MongoEmailVerificationRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoEmailVerificationRepository {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoEmailVerificationRepository { collection }
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for MongoEmailVerificationRepository {
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        ttl_hours: i64,
+    ) -> Result<String, AppError> {
+        self.delete_pending(username).await?;
+        let (verification, token) = new_email_verification(username, email, ttl_hours);
+
+        time_db_operation(
+            "insert_email_verification",
+            self.collection.insert_one(to_document(&verification)?),
+        )
+        .await?;
+
+        Ok(token)
+    }
+
+    async fn consume(&self, token: &str) -> Result<Option<EmailVerification>, AppError> {
+        let (verification_id, secret) = match parse_token(token) {
+            Some(parsed) => parsed,
+            None => return Ok(None),
+        };
+
+        let filter = doc! {
+            "_id": verification_id,
+            "token_hash": hash_token_secret(secret),
+            "used_at": null,
+            "expires_at": { "$gt": BsonDateTime::now() },
+        };
+        let update = doc! { "$set": { "used_at": BsonDateTime::now() } };
+
+        let verification = time_db_operation(
+            "consume_email_verification",
+            self.collection.find_one_and_update(filter, update),
+        )
+        .await?;
+
+        match verification {
+            Some(doc) => Ok(Some(from_document(doc)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_pending(&self, username: &str) -> Result<(), AppError> {
+        time_db_operation(
+            "delete_email_verifications",
+            self.collection
+                .delete_many(doc! { "username": username, "used_at": null }),
+        )
+        .await?;
+        Ok(())
+    }
+}
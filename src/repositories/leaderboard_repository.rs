@@ -0,0 +1,383 @@
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::user::HighScores;
+use crate::services::metrics_service::time_db_operation;
+use crate::structs::leaderboard::{
+    LeaderboardCursor, LeaderboardEntry, LeaderboardPage, LeaderboardPaging,
+};
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_bson, Bson, Document};
+use mongodb::Collection;
+use std::collections::HashMap;
+
+// Read side of the all-time leaderboard, ranked by wpm, then accuracy, then
+// the earliest date, then id
+#[async_trait]
+pub trait LeaderboardRepository: Send + Sync {
+    // Number of users with a score for the mode
+    async fn count(&self, mode: &GameMode) -> Result<i64, AppError>;
+
+    async fn fetch_page(
+        &self,
+        mode: &GameMode,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError>;
+
+    async fn fetch_user_position(
+        &self,
+        mode: &GameMode,
+        username: &str,
+    ) -> Result<Option<(LeaderboardEntry, LeaderboardCursor)>, AppError>;
+
+    // 1-based rank of the entry at the given position
+    async fn get_rank(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+    ) -> Result<u64, AppError>;
+
+    // Up to `range` entries directly ahead of and behind a position, in rank order
+    async fn fetch_entries_around(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+        range: u64,
+    ) -> Result<(Vec<LeaderboardEntry>, Vec<LeaderboardEntry>), AppError>;
+}
+
+pub struct MongoLeaderboardRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoLeaderboardRepository {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoLeaderboardRepository { collection }
+    }
+}
+
+// Field paths a leaderboard pipeline sorts and paginates on
+pub(crate) struct SortKeys {
+    pub wpm: String,
+    pub accuracy: String,
+    pub date: String,
+}
+
+impl SortKeys {
+    fn for_mode(mode: &GameMode) -> Self {
+        let score_path = mode.score_path();
+
+        SortKeys {
+            wpm: format!("{}.wpm", score_path),
+            accuracy: format!("{}.accuracy", score_path),
+            date: format!("{}.date", score_path),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Ordering {
+    // Entries ranked ahead of a position
+    Before,
+    // Entries ranked behind a position
+    After,
+}
+
+fn create_mode_filter(mode: &GameMode) -> Document {
+    doc! {
+        mode.score_path(): { "$exists": true },
+        "banned": { "$ne": true },
+    }
+}
+
+fn extract_leaderboard_entry(doc: &Document) -> Result<LeaderboardEntry, mongodb::error::Error> {
+    let _id = doc
+        .get_object_id("_id")
+        .map(|id| id.to_string())
+        .unwrap_or_else(|_| "".to_string());
+    let username = doc
+        .get_str("username")
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| "".to_string());
+    let completed_tests = match doc.get("completed_tests") {
+        Some(Bson::Int32(n)) => *n as u32,
+        Some(Bson::Int64(n)) => *n as u32,
+        _ => 0,
+    };
+
+    let high_scores = extract_high_scores(doc)?;
+
+    Ok(LeaderboardEntry {
+        _id,
+        username,
+        completed_tests,
+        high_scores,
+    })
+}
+
+fn extract_high_scores(doc: &Document) -> Result<HighScores, mongodb::error::Error> {
+    match doc.get("high_scores") {
+        Some(Bson::Document(doc)) => {
+            let high_scores: HighScores = from_bson(Bson::Document(doc.clone()))?;
+            Ok(high_scores)
+        }
+        _ => Ok(HighScores {
+            languages: HashMap::new(),
+        }),
+    }
+}
+
+fn get_mode_score_document<'a>(doc: &'a Document, mode: &GameMode) -> Option<&'a Document> {
+    doc.get_document("high_scores")
+        .and_then(|doc| doc.get_document("languages"))
+        .and_then(|doc| doc.get_document(mode.language()))
+        .and_then(|doc| doc.get_document("difficulties"))
+        .and_then(|doc| doc.get_document(mode.difficulty()))
+        .and_then(|doc| doc.get_document("scores"))
+        .and_then(|doc| doc.get_document(mode.duration()))
+        .ok()
+}
+
+// Reads the raw stored values so comparisons match exactly what MongoDB sorts on
+fn extract_leaderboard_position(doc: &Document, mode: &GameMode) -> Option<LeaderboardCursor> {
+    let score = get_mode_score_document(doc, mode)?;
+
+    let wpm = match score.get("wpm")? {
+        Bson::Int32(wpm) => *wpm as i64,
+        Bson::Int64(wpm) => *wpm,
+        _ => return None,
+    };
+    let accuracy = match score.get("accuracy")? {
+        Bson::Double(accuracy) => *accuracy,
+        Bson::Int32(accuracy) => *accuracy as f64,
+        Bson::Int64(accuracy) => *accuracy as f64,
+        _ => return None,
+    };
+
+    Some(LeaderboardCursor {
+        wpm,
+        accuracy,
+        date: score.get_str("date").ok()?.to_string(),
+        id: Bson::ObjectId(doc.get_object_id("_id").ok()?),
+    })
+}
+
+async fn collect_leaderboard_entries(
+    collection: &Collection<Document>,
+    pipeline: Vec<Document>,
+    mode: &GameMode,
+) -> Result<Vec<(LeaderboardEntry, Option<LeaderboardCursor>)>, mongodb::error::Error> {
+    time_db_operation("aggregate_leaderboard", async {
+        let mut cursor = collection.aggregate(pipeline).await?;
+        let mut users = Vec::new();
+
+        while let Some(doc) = cursor.try_next().await? {
+            match extract_leaderboard_entry(&doc) {
+                Ok(entry) => users.push((entry, extract_leaderboard_position(&doc, mode))),
+                Err(e) => tracing::warn!(error = ?e, "Error processing document"),
+            }
+        }
+
+        Ok(users)
+    })
+    .await
+}
+
+fn without_positions(
+    users: Vec<(LeaderboardEntry, Option<LeaderboardCursor>)>,
+) -> Vec<LeaderboardEntry> {
+    users.into_iter().map(|(entry, _)| entry).collect()
+}
+
+pub(crate) fn create_position_filter(
+    keys: &SortKeys,
+    position: &LeaderboardCursor,
+    ordering: Ordering,
+) -> Document {
+    // Higher wpm/accuracy rank first, then the earlier date, then the lower _id
+    let (ahead, behind) = match ordering {
+        Ordering::Before => ("$gt", "$lt"),
+        Ordering::After => ("$lt", "$gt"),
+    };
+
+    doc! { "$or": [
+        { &keys.wpm: { ahead: position.wpm } },
+        { &keys.wpm: position.wpm, &keys.accuracy: { ahead: position.accuracy } },
+        {
+            &keys.wpm: position.wpm,
+            &keys.accuracy: position.accuracy,
+            &keys.date: { behind: &position.date },
+        },
+        {
+            &keys.wpm: position.wpm,
+            &keys.accuracy: position.accuracy,
+            &keys.date: &position.date,
+            "_id": { behind: position.id.clone() },
+        },
+    ]}
+}
+
+fn create_projection_stage(mode: &GameMode) -> Document {
+    doc! { "$project": {
+        "_id": 1,
+        "username": 1,
+        "completed_tests": 1,
+        mode.score_path(): 1
+    }}
+}
+
+pub(crate) fn create_sort_stage(keys: &SortKeys, reversed: bool) -> Document {
+    let direction = if reversed { -1 } else { 1 };
+
+    // Ties are broken by accuracy, then the earlier date, then _id so that
+    // the ordering is total and pages never overlap or skip entries
+    doc! { "$sort": {
+        &keys.wpm: -direction,
+        &keys.accuracy: -direction,
+        &keys.date: direction,
+        "_id": direction
+    }}
+}
+
+fn create_aggregation_pipeline(
+    mode: &GameMode,
+    paging: &LeaderboardPaging,
+    limit: u64,
+) -> Vec<Document> {
+    let keys = SortKeys::for_mode(mode);
+    let mut match_filter = create_mode_filter(mode);
+    if let Some(cursor) = &paging.after {
+        match_filter.extend(create_position_filter(&keys, cursor, Ordering::After));
+    }
+
+    vec![
+        doc! { "$match": match_filter },
+        create_projection_stage(mode),
+        create_sort_stage(&keys, false),
+        doc! { "$skip": paging.skip as i64 },
+        doc! { "$limit": limit as i64 },
+    ]
+}
+
+fn create_window_pipeline(
+    mode: &GameMode,
+    position: &LeaderboardCursor,
+    ordering: Ordering,
+    limit: u64,
+) -> Vec<Document> {
+    let keys = SortKeys::for_mode(mode);
+    let mut match_filter = create_mode_filter(mode);
+    match_filter.extend(create_position_filter(&keys, position, ordering));
+
+    vec![
+        doc! { "$match": match_filter },
+        create_projection_stage(mode),
+        create_sort_stage(&keys, matches!(ordering, Ordering::Before)),
+        doc! { "$limit": limit as i64 },
+    ]
+}
+
+// fn log_leaderboard_stats(users: &[LeaderboardEntry]) {
+//     println!(
+//         "Leaderboard Stats: {}",
+//         serde_json::to_string_pretty(&json!(users)).unwrap()
+//     );
+// }
+
+#[async_trait]
+impl LeaderboardRepository for MongoLeaderboardRepository {
+    async fn count(&self, mode: &GameMode) -> Result<i64, AppError> {
+        let count = time_db_operation(
+            "count_leaderboard",
+            self.collection.count_documents(create_mode_filter(mode)),
+        )
+        .await?;
+        Ok(count as i64)
+    }
+
+    async fn fetch_page(
+        &self,
+        mode: &GameMode,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError> {
+        // Fetch one extra entry to find out whether another page exists
+        let pipeline = create_aggregation_pipeline(mode, paging, paging.limit + 1);
+        let mut users = collect_leaderboard_entries(&self.collection, pipeline, mode).await?;
+
+        // log_leaderboard_stats(&users);
+
+        let has_more = users.len() as u64 > paging.limit;
+        users.truncate(paging.limit as usize);
+
+        let next_cursor = if has_more {
+            users
+                .last()
+                .and_then(|(_, position)| position.as_ref())
+                .map(|position| position.encode())
+        } else {
+            None
+        };
+
+        Ok(LeaderboardPage {
+            entries: without_positions(users),
+            next_cursor,
+        })
+    }
+
+    async fn fetch_user_position(
+        &self,
+        mode: &GameMode,
+        username: &str,
+    ) -> Result<Option<(LeaderboardEntry, LeaderboardCursor)>, AppError> {
+        let mut filter = create_mode_filter(mode);
+        filter.insert("username", username);
+
+        let pipeline = vec![
+            doc! { "$match": filter },
+            create_projection_stage(mode),
+            doc! { "$limit": 1 },
+        ];
+        let users = collect_leaderboard_entries(&self.collection, pipeline, mode).await?;
+
+        Ok(users
+            .into_iter()
+            .next()
+            .and_then(|(entry, position)| position.map(|position| (entry, position))))
+    }
+
+    async fn get_rank(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+    ) -> Result<u64, AppError> {
+        let mut filter = create_mode_filter(mode);
+        filter.extend(create_position_filter(
+            &SortKeys::for_mode(mode),
+            position,
+            Ordering::Before,
+        ));
+
+        Ok(time_db_operation("count_rank", self.collection.count_documents(filter)).await? + 1)
+    }
+
+    async fn fetch_entries_around(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+        range: u64,
+    ) -> Result<(Vec<LeaderboardEntry>, Vec<LeaderboardEntry>), AppError> {
+        let above_pipeline = create_window_pipeline(mode, position, Ordering::Before, range);
+        let mut above = without_positions(
+            collect_leaderboard_entries(&self.collection, above_pipeline, mode).await?,
+        );
+        // The entries ahead were fetched closest-first, so flip them back into rank order
+        above.reverse();
+
+        let below_pipeline = create_window_pipeline(mode, position, Ordering::After, range);
+        let below = without_positions(
+            collect_leaderboard_entries(&self.collection, below_pipeline, mode).await?,
+        );
+
+        Ok((above, below))
+    }
+}
@@ -0,0 +1,822 @@
+use crate::errors::app_error::AppError;
+use crate::models::email_verification::EmailVerification;
+use crate::models::mode::GameMode;
+use crate::models::password_reset::PasswordReset;
+use crate::models::session::{DeviceInfo, UserSession};
+use crate::models::test_result::TestResultRecord;
+use crate::models::user::{DifficultyScores, HighScores, LanguageScores, Score, User};
+use crate::repositories::email_verification_repository::{
+    new_email_verification, EmailVerificationRepository,
+};
+use crate::repositories::leaderboard_repository::LeaderboardRepository;
+use crate::repositories::password_reset_repository::{new_password_reset, PasswordResetRepository};
+use crate::repositories::session_repository::{
+    get_refresh_expiration, new_session, truncate_device_field, SessionRepository,
+    MAX_PREVIOUS_TOKEN_HASHES,
+};
+use crate::repositories::test_result_repository::{
+    create_single_mode_high_scores, TestResultRepository,
+};
+use crate::repositories::user_repository::UserRepository;
+use crate::structs::claims::TestSessionClaims;
+use crate::structs::leaderboard::{
+    LeaderboardCursor, LeaderboardEntry, LeaderboardPage, LeaderboardPaging,
+};
+use crate::structs::session::RefreshOutcome;
+use crate::structs::test_result::GetTestResultsQueries;
+use crate::utils::helpers::{to_bson_datetime, to_chrono_datetime};
+use crate::utils::token::{format_token, generate_token_secret, hash_token_secret, parse_token};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use mongodb::bson::{
+    oid::ObjectId, to_bson, to_document, Bson, DateTime as BsonDateTime, Document,
+};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+struct StoredUser {
+    id: ObjectId,
+    user: User,
+}
+
+// Keeps users, sessions, test results, email verifications and password
+// resets in process memory and answers leaderboard queries from the same data, with the same
+// ordering as the MongoDB pipelines. Meant for tests and local experiments;
+// nothing survives a restart.
+#[derive(Default)]
+pub struct InMemoryRepository {
+    users: Mutex<Vec<StoredUser>>,
+    sessions: Mutex<Vec<UserSession>>,
+    claimed_tests: Mutex<HashSet<String>>,
+    test_results: Mutex<Vec<TestResultRecord>>,
+    verifications: Mutex<Vec<EmailVerification>>,
+    password_resets: Mutex<Vec<PasswordReset>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic while holding the lock cannot leave the data half-updated
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        InMemoryRepository::default()
+    }
+
+    fn lock_users(&self) -> MutexGuard<'_, Vec<StoredUser>> {
+        lock(&self.users)
+    }
+
+    // Revokes the active sessions matching the predicate, like the MongoDB
+    // update_many on the active session filter
+    fn revoke_sessions(&self, reason: &str, matches: impl Fn(&UserSession) -> bool) -> u64 {
+        let now = BsonDateTime::now();
+        let mut revoked = 0;
+        for session in lock(&self.sessions).iter_mut() {
+            if is_active_session(session, now) && matches(session) {
+                session.revoked_at = Some(now);
+                session.revoked_reason = Some(reason.to_string());
+                revoked += 1;
+            }
+        }
+        revoked
+    }
+
+    // Each user's best result for the mode since `window_start`, in
+    // leaderboard order
+    fn windowed_entries(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+    ) -> Vec<(LeaderboardEntry, LeaderboardCursor)> {
+        let window_start = to_bson_datetime(window_start);
+        let results = lock(&self.test_results);
+        let mut in_window: Vec<&TestResultRecord> = results
+            .iter()
+            .filter(|record| {
+                record.language == mode.language()
+                    && record.difficulty == mode.difficulty()
+                    && record.duration == mode.duration()
+                    && record.timestamp >= window_start
+            })
+            .collect();
+
+        // Best result first, so the first one seen per user is the one kept
+        in_window.sort_by(|a, b| {
+            b.wpm
+                .cmp(&a.wpm)
+                .then_with(|| b.accuracy.total_cmp(&a.accuracy))
+                .then_with(|| a.timestamp.cmp(&b.timestamp))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        let mut best: Vec<(&TestResultRecord, u32)> = Vec::new();
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for record in in_window {
+            match seen.get(record.username.as_str()) {
+                Some(&index) => best[index].1 += 1,
+                None => {
+                    seen.insert(&record.username, best.len());
+                    best.push((record, 1));
+                }
+            }
+        }
+
+        let users = self.lock_users();
+        let mut entries: Vec<_> = best
+            .into_iter()
+            .filter_map(|(record, completed_tests)| {
+                let stored = users
+                    .iter()
+                    .find(|stored| stored.user.username == record.username);
+                // Banned users are left off like on the all-time board
+                if stored.is_some_and(|stored| stored.user.banned) {
+                    return None;
+                }
+                let user_id = stored
+                    .map(|stored| stored.id.to_string())
+                    .unwrap_or_default();
+                let date = to_chrono_datetime(record.timestamp);
+
+                let position = LeaderboardCursor {
+                    wpm: record.wpm as i64,
+                    accuracy: record.accuracy as f64,
+                    date: date.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    id: Bson::String(record.username.clone()),
+                };
+                let score = Score {
+                    wpm: record.wpm,
+                    raw_wpm: record.raw_wpm,
+                    accuracy: record.accuracy,
+                    date,
+                };
+                let entry = LeaderboardEntry {
+                    _id: user_id,
+                    username: record.username.clone(),
+                    completed_tests,
+                    high_scores: create_single_mode_high_scores(mode, score),
+                };
+                Some((entry, position))
+            })
+            .collect();
+
+        entries.sort_by(|(_, a), (_, b)| compare_positions(a, b));
+        entries
+    }
+
+    // Every user with a score for the mode, in leaderboard order
+    fn ranked_entries(&self, mode: &GameMode) -> Vec<(LeaderboardEntry, LeaderboardCursor)> {
+        let users = self.lock_users();
+        let mut entries: Vec<_> = users
+            .iter()
+            .filter(|stored| !stored.user.banned)
+            .filter_map(|stored| {
+                let score = get_mode_score(&stored.user, mode)?;
+                let position = score_position(stored.id, score)?;
+                Some((create_entry(stored, mode, score), position))
+            })
+            .collect();
+
+        entries.sort_by(|(_, a), (_, b)| compare_positions(a, b));
+        entries
+    }
+}
+
+fn is_active_session(session: &UserSession, now: BsonDateTime) -> bool {
+    session.revoked_at.is_none() && session.expires_at > now
+}
+
+fn matches_results_query(
+    record: &TestResultRecord,
+    username: &str,
+    queries: &GetTestResultsQueries,
+) -> bool {
+    let matches = |filter: &Option<String>, value: &str| match filter {
+        Some(filter) => filter == value,
+        None => true,
+    };
+
+    record.username == username
+        && matches(&queries.language, &record.language)
+        && matches(&queries.difficulty, &record.difficulty)
+        && matches(&queries.duration, &record.duration)
+        && queries
+            .from
+            .is_none_or(|from| record.timestamp >= to_bson_datetime(from))
+        && queries
+            .to
+            .is_none_or(|to| record.timestamp <= to_bson_datetime(to))
+}
+
+// Cuts a page out of entries already in leaderboard order
+fn paginate(
+    entries: Vec<(LeaderboardEntry, LeaderboardCursor)>,
+    paging: &LeaderboardPaging,
+) -> LeaderboardPage {
+    let mut entries: Vec<_> = entries
+        .into_iter()
+        .filter(|(_, position)| match &paging.after {
+            Some(after) => compare_positions(position, after) == Ordering::Greater,
+            None => true,
+        })
+        .skip(paging.skip as usize)
+        .take(paging.limit as usize + 1)
+        .collect();
+
+    let has_more = entries.len() as u64 > paging.limit;
+    entries.truncate(paging.limit as usize);
+
+    let next_cursor = if has_more {
+        entries.last().map(|(_, position)| position.encode())
+    } else {
+        None
+    };
+
+    LeaderboardPage {
+        entries: entries.into_iter().map(|(entry, _)| entry).collect(),
+        next_cursor,
+    }
+}
+
+fn get_mode_score<'a>(user: &'a User, mode: &GameMode) -> Option<&'a Score> {
+    user.high_scores
+        .as_ref()?
+        .languages
+        .get(mode.language())?
+        .difficulties
+        .get(mode.difficulty())?
+        .scores
+        .get(mode.duration())
+}
+
+fn get_mode_score_mut<'a>(user: &'a mut User, mode: &GameMode) -> &'a mut HashMap<String, Score> {
+    let high_scores = user.high_scores.get_or_insert_with(|| HighScores {
+        languages: HashMap::new(),
+    });
+
+    &mut high_scores
+        .languages
+        .entry(mode.language().to_string())
+        .or_insert_with(|| LanguageScores {
+            difficulties: HashMap::new(),
+        })
+        .difficulties
+        .entry(mode.difficulty().to_string())
+        .or_insert_with(|| DifficultyScores {
+            scores: HashMap::new(),
+        })
+        .scores
+}
+
+fn score_position(id: ObjectId, score: &Score) -> Option<LeaderboardCursor> {
+    // Dates are compared in their stored string form, as MongoDB does
+    let date = match to_bson(&score.date).ok()? {
+        Bson::String(date) => date,
+        _ => return None,
+    };
+
+    Some(LeaderboardCursor {
+        wpm: score.wpm as i64,
+        accuracy: score.accuracy as f64,
+        date,
+        id: Bson::ObjectId(id),
+    })
+}
+
+// Same conditions as the MongoDB filter for a usable reset token
+fn is_valid_reset(reset: &PasswordReset, token: &str, now: BsonDateTime) -> bool {
+    match parse_token(token) {
+        Some((reset_id, secret)) => {
+            reset.id == reset_id
+                && reset.token_hash == hash_token_secret(secret)
+                && reset.used_at.is_none()
+                && reset.expires_at > now
+        }
+        None => false,
+    }
+}
+
+fn compare_ids(a: &Bson, b: &Bson) -> Ordering {
+    match (a, b) {
+        (Bson::ObjectId(a), Bson::ObjectId(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+// Less means `a` ranks ahead of `b`
+fn compare_positions(a: &LeaderboardCursor, b: &LeaderboardCursor) -> Ordering {
+    b.wpm
+        .cmp(&a.wpm)
+        .then_with(|| b.accuracy.total_cmp(&a.accuracy))
+        .then_with(|| a.date.cmp(&b.date))
+        .then_with(|| compare_ids(&a.id, &b.id))
+}
+
+// Like the MongoDB projection, only the score for the requested mode is kept
+fn create_entry(stored: &StoredUser, mode: &GameMode, score: &Score) -> LeaderboardEntry {
+    let mut scores = HashMap::new();
+    scores.insert(mode.duration().to_string(), score.clone());
+
+    let mut difficulties = HashMap::new();
+    difficulties.insert(mode.difficulty().to_string(), DifficultyScores { scores });
+
+    let mut languages = HashMap::new();
+    languages.insert(mode.language().to_string(), LanguageScores { difficulties });
+
+    LeaderboardEntry {
+        _id: stored.id.to_string(),
+        username: stored.user.username.clone(),
+        completed_tests: stored.user.completed_tests.unwrap_or(0),
+        high_scores: HighScores { languages },
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>, AppError> {
+        let users = self.lock_users();
+        Ok(users
+            .iter()
+            .find(|stored| stored.user.username == username)
+            .map(|stored| stored.user.clone()))
+    }
+
+    async fn find_profile(&self, username: &str) -> Result<Option<Document>, AppError> {
+        let users = self.lock_users();
+        let stored = match users.iter().find(|stored| stored.user.username == username) {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+
+        let mut profile = to_document(&stored.user)?;
+        profile.remove("password");
+        profile.remove("email");
+        profile.remove("email_verification_sent_at");
+        profile.insert("_id", stored.id);
+        Ok(Some(profile))
+    }
+
+    async fn exists(&self, username: &str) -> Result<bool, AppError> {
+        let users = self.lock_users();
+        Ok(users.iter().any(|stored| stored.user.username == username))
+    }
+
+    async fn insert(&self, user: User) -> Result<(), AppError> {
+        let mut users = self.lock_users();
+        if users
+            .iter()
+            .any(|stored| stored.user.username == user.username)
+        {
+            return Err(AppError::UsernameTaken);
+        }
+
+        users.push(StoredUser {
+            id: ObjectId::new(),
+            user,
+        });
+        Ok(())
+    }
+
+    async fn update_password(&self, username: &str, password_hash: &str) -> Result<(), AppError> {
+        let mut users = self.lock_users();
+        if let Some(stored) = users
+            .iter_mut()
+            .find(|stored| stored.user.username == username)
+        {
+            stored.user.password = password_hash.to_string();
+        }
+        Ok(())
+    }
+
+    async fn upgrade_password_hash(
+        &self,
+        username: &str,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<bool, AppError> {
+        let mut users = self.lock_users();
+        match users
+            .iter_mut()
+            .find(|stored| stored.user.username == username && stored.user.password == old_hash)
+        {
+            Some(stored) => {
+                stored.user.password = new_hash.to_string();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn update_email(&self, username: &str, email: Option<&str>) -> Result<bool, AppError> {
+        let mut users = self.lock_users();
+        match users
+            .iter_mut()
+            .find(|stored| stored.user.username == username)
+        {
+            Some(stored) => {
+                stored.user.email = email.map(|email| email.to_string());
+                stored.user.email_verified = false;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn mark_verification_email_sent(&self, username: &str) -> Result<(), AppError> {
+        let mut users = self.lock_users();
+        if let Some(stored) = users
+            .iter_mut()
+            .find(|stored| stored.user.username == username)
+        {
+            stored.user.email_verification_sent_at = Some(BsonDateTime::now());
+        }
+        Ok(())
+    }
+
+    async fn mark_email_verified(&self, username: &str, email: &str) -> Result<bool, AppError> {
+        let mut users = self.lock_users();
+        if users.iter().any(|stored| {
+            stored.user.username != username
+                && stored.user.email_verified
+                && stored.user.email.as_deref() == Some(email)
+        }) {
+            return Err(AppError::EmailTaken);
+        }
+        match users.iter_mut().find(|stored| {
+            stored.user.username == username && stored.user.email.as_deref() == Some(email)
+        }) {
+            Some(stored) => {
+                stored.user.email_verified = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn record_score(
+        &self,
+        username: &str,
+        mode: &GameMode,
+        score: &Score,
+    ) -> Result<Option<bool>, AppError> {
+        let mut users = self.lock_users();
+        let user = match users
+            .iter_mut()
+            .find(|stored| stored.user.username == username)
+        {
+            Some(stored) => &mut stored.user,
+            None => return Ok(None),
+        };
+
+        user.completed_tests = Some(user.completed_tests.unwrap_or(0) + 1);
+
+        let scores = get_mode_score_mut(user, mode);
+        let personal_best = match scores.get(mode.duration()) {
+            Some(existing) => score.wpm > existing.wpm,
+            None => true,
+        };
+        if personal_best {
+            scores.insert(mode.duration().to_string(), score.clone());
+        }
+
+        Ok(Some(personal_best))
+    }
+}
+
+#[async_trait]
+impl LeaderboardRepository for InMemoryRepository {
+    async fn count(&self, mode: &GameMode) -> Result<i64, AppError> {
+        Ok(self.ranked_entries(mode).len() as i64)
+    }
+
+    async fn fetch_page(
+        &self,
+        mode: &GameMode,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError> {
+        Ok(paginate(self.ranked_entries(mode), paging))
+    }
+
+    async fn fetch_user_position(
+        &self,
+        mode: &GameMode,
+        username: &str,
+    ) -> Result<Option<(LeaderboardEntry, LeaderboardCursor)>, AppError> {
+        Ok(self
+            .ranked_entries(mode)
+            .into_iter()
+            .find(|(entry, _)| entry.username == username))
+    }
+
+    async fn get_rank(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+    ) -> Result<u64, AppError> {
+        let ahead = self
+            .ranked_entries(mode)
+            .iter()
+            .filter(|(_, other)| compare_positions(other, position) == Ordering::Less)
+            .count();
+
+        Ok(ahead as u64 + 1)
+    }
+
+    async fn fetch_entries_around(
+        &self,
+        mode: &GameMode,
+        position: &LeaderboardCursor,
+        range: u64,
+    ) -> Result<(Vec<LeaderboardEntry>, Vec<LeaderboardEntry>), AppError> {
+        let mut above = Vec::new();
+        let mut below = Vec::new();
+
+        for (entry, other) in self.ranked_entries(mode) {
+            match compare_positions(&other, position) {
+                Ordering::Less => above.push(entry),
+                Ordering::Greater if (below.len() as u64) < range => below.push(entry),
+                _ => {}
+            }
+        }
+
+        // Only the entries closest to the position are kept
+        let start = above.len().saturating_sub(range as usize);
+        above.drain(..start);
+
+        Ok((above, below))
+    }
+}
+
+#[async_trait]
+impl SessionRepository for InMemoryRepository {
+    async fn create(
+        &self,
+        username: &str,
+        device: &DeviceInfo,
+    ) -> Result<(String, String), AppError> {
+        let (session, refresh_token) = new_session(username, device);
+        let session_id = session.id.to_hex();
+        lock(&self.sessions).push(session);
+        Ok((session_id, refresh_token))
+    }
+
+    async fn is_active(&self, session_id: &str) -> Result<bool, AppError> {
+        let now = BsonDateTime::now();
+        Ok(lock(&self.sessions)
+            .iter()
+            .any(|session| session.id.to_hex() == session_id && is_active_session(session, now)))
+    }
+
+    async fn revoke(&self, session_id: &str, reason: &str) -> Result<(), AppError> {
+        self.revoke_sessions(reason, |session| session.id.to_hex() == session_id);
+        Ok(())
+    }
+
+    async fn list_active(&self, username: &str) -> Result<Vec<UserSession>, AppError> {
+        let now = BsonDateTime::now();
+        let mut sessions: Vec<UserSession> = lock(&self.sessions)
+            .iter()
+            .filter(|session| session.username == username && is_active_session(session, now))
+            .cloned()
+            .collect();
+
+        sessions.sort_by(|a, b| {
+            b.last_seen_at
+                .cmp(&a.last_seen_at)
+                .then_with(|| b.id.cmp(&a.id))
+        });
+        Ok(sessions)
+    }
+
+    async fn revoke_for_user(
+        &self,
+        username: &str,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<bool, AppError> {
+        let revoked = self.revoke_sessions(reason, |session| {
+            session.username == username && session.id.to_hex() == session_id
+        });
+        Ok(revoked > 0)
+    }
+
+    async fn revoke_others(
+        &self,
+        username: &str,
+        current_session_id: &str,
+        reason: &str,
+    ) -> Result<u64, AppError> {
+        Ok(self.revoke_sessions(reason, |session| {
+            session.username == username && session.id.to_hex() != current_session_id
+        }))
+    }
+
+    async fn revoke_all(&self, username: &str, reason: &str) -> Result<u64, AppError> {
+        Ok(self.revoke_sessions(reason, |session| session.username == username))
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        refresh_token: &str,
+        device: &DeviceInfo,
+    ) -> Result<RefreshOutcome, AppError> {
+        let (session_id, secret) = match parse_token(refresh_token) {
+            Some(parts) => parts,
+            None => return Ok(RefreshOutcome::Invalid),
+        };
+        let token_hash = hash_token_secret(secret);
+        let now = BsonDateTime::now();
+
+        let mut sessions = lock(&self.sessions);
+        let session = match sessions.iter_mut().find(|session| session.id == session_id) {
+            Some(session) => session,
+            None => return Ok(RefreshOutcome::Invalid),
+        };
+
+        if is_active_session(session, now) && session.refresh_token_hash == token_hash {
+            let new_secret = generate_token_secret();
+            session.previous_token_hashes.push(token_hash);
+            let excess = session
+                .previous_token_hashes
+                .len()
+                .saturating_sub(MAX_PREVIOUS_TOKEN_HASHES as usize);
+            session.previous_token_hashes.drain(..excess);
+            session.refresh_token_hash = hash_token_secret(&new_secret);
+            session.expires_at = get_refresh_expiration();
+            session.user_agent = truncate_device_field(device.user_agent.clone());
+            session.ip_address = truncate_device_field(device.ip_address.clone());
+            session.last_seen_at = now;
+
+            return Ok(RefreshOutcome::Rotated {
+                username: session.username.clone(),
+                session_id: session_id.to_hex(),
+                refresh_token: format_token(&session_id, &new_secret),
+            });
+        }
+
+        if session.previous_token_hashes.contains(&token_hash) {
+            if session.revoked_at.is_none() {
+                session.revoked_at = Some(now);
+                session.revoked_reason = Some("refresh_token_reuse".to_string());
+            }
+            return Ok(RefreshOutcome::Reused);
+        }
+
+        Ok(RefreshOutcome::Invalid)
+    }
+}
+
+#[async_trait]
+impl TestResultRepository for InMemoryRepository {
+    async fn claim_session(&self, claims: &TestSessionClaims) -> Result<bool, AppError> {
+        Ok(lock(&self.claimed_tests).insert(claims.test_id.clone()))
+    }
+
+    async fn release_session(&self, claims: &TestSessionClaims) -> Result<(), AppError> {
+        lock(&self.claimed_tests).remove(&claims.test_id);
+        Ok(())
+    }
+
+    async fn insert(&self, result: &TestResultRecord) -> Result<(), AppError> {
+        let mut result = result.clone();
+        result.id.get_or_insert_with(ObjectId::new);
+        lock(&self.test_results).push(result);
+        Ok(())
+    }
+
+    async fn fetch_page(
+        &self,
+        username: &str,
+        queries: &GetTestResultsQueries,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<TestResultRecord>, u64), AppError> {
+        let results = lock(&self.test_results);
+        let mut matching: Vec<&TestResultRecord> = results
+            .iter()
+            .filter(|record| matches_results_query(record, username, queries))
+            .collect();
+
+        // Most recent results first, as the MongoDB sort
+        matching.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.id.cmp(&a.id)));
+
+        let total_count = matching.len() as u64;
+        let page = matching
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        Ok((page, total_count))
+    }
+
+    async fn count_in_window(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+    ) -> Result<i64, AppError> {
+        Ok(self.windowed_entries(mode, window_start).len() as i64)
+    }
+
+    async fn fetch_window_page(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError> {
+        Ok(paginate(self.windowed_entries(mode, window_start), paging))
+    }
+}
+
+#[async_trait]
+impl EmailVerificationRepository for InMemoryRepository {
+    async fn create(
+        &self,
+        username: &str,
+        email: &str,
+        ttl_hours: i64,
+    ) -> Result<String, AppError> {
+        let (verification, token) = new_email_verification(username, email, ttl_hours);
+
+        let mut verifications = lock(&self.verifications);
+        verifications.retain(|pending| pending.username != username || pending.used_at.is_some());
+        verifications.push(verification);
+        Ok(token)
+    }
+
+    async fn consume(&self, token: &str) -> Result<Option<EmailVerification>, AppError> {
+        let (verification_id, secret) = match parse_token(token) {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+        let token_hash = hash_token_secret(secret);
+        let now = BsonDateTime::now();
+
+        let mut verifications = lock(&self.verifications);
+        let verification = verifications.iter_mut().find(|verification| {
+            verification.id == verification_id
+                && verification.token_hash == token_hash
+                && verification.used_at.is_none()
+                && verification.expires_at > now
+        });
+
+        // Like find_one_and_update, the verification is returned as it was
+        Ok(verification.map(|verification| {
+            let unused = verification.clone();
+            verification.used_at = Some(now);
+            unused
+        }))
+    }
+
+    async fn delete_pending(&self, username: &str) -> Result<(), AppError> {
+        lock(&self.verifications)
+            .retain(|pending| pending.username != username || pending.used_at.is_some());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PasswordResetRepository for InMemoryRepository {
+    async fn create(&self, username: &str, ttl_minutes: i64) -> Result<String, AppError> {
+        let (reset, token) = new_password_reset(username, ttl_minutes);
+
+        let mut resets = lock(&self.password_resets);
+        resets.retain(|pending| pending.username != username || pending.used_at.is_some());
+        resets.push(reset);
+        Ok(token)
+    }
+
+    async fn last_created_at(&self, username: &str) -> Result<Option<BsonDateTime>, AppError> {
+        Ok(lock(&self.password_resets)
+            .iter()
+            .filter(|reset| reset.username == username)
+            .map(|reset| reset.created_at)
+            .max())
+    }
+
+    async fn find_valid(&self, token: &str) -> Result<Option<String>, AppError> {
+        let now = BsonDateTime::now();
+        Ok(lock(&self.password_resets)
+            .iter()
+            .find(|reset| is_valid_reset(reset, token, now))
+            .map(|reset| reset.username.clone()))
+    }
+
+    async fn consume(&self, token: &str) -> Result<bool, AppError> {
+        let now = BsonDateTime::now();
+        let mut resets = lock(&self.password_resets);
+        match resets
+            .iter_mut()
+            .find(|reset| is_valid_reset(reset, token, now))
+        {
+            Some(reset) => {
+                reset.used_at = Some(now);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod email_verification_repository;
+pub mod leaderboard_repository;
+pub mod memory_repository;
+pub mod password_reset_repository;
+pub mod session_repository;
+pub mod test_result_repository;
+pub mod user_repository;
@@ -0,0 +1,133 @@
+use crate::errors::app_error::AppError;
+use crate::models::password_reset::PasswordReset;
+use crate::services::metrics_service::time_db_operation;
+use crate::utils::token::{format_token, generate_token_secret, hash_token_secret, parse_token};
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, from_document, to_document, DateTime as BsonDateTime, Document};
+use mongodb::Collection;
+
+// A fresh reset along with the token to email
+pub(crate) fn new_password_reset(username: &str, ttl_minutes: i64) -> (PasswordReset, String) {
+    let secret = generate_token_secret();
+    let now = chrono::Utc::now();
+    let reset = PasswordReset {
+        id: ObjectId::new(),
+        username: username.to_string(),
+        token_hash: hash_token_secret(&secret),
+        created_at: BsonDateTime::from_millis(now.timestamp_millis()),
+        expires_at: BsonDateTime::from_millis(
+            (now + chrono::Duration::minutes(ttl_minutes)).timestamp_millis(),
+        ),
+        used_at: None,
+    };
+    let token = format_token(&reset.id, &secret);
+    (reset, token)
+}
+
+// One-time links that let a user choose a new password
+#[async_trait]
+pub trait PasswordResetRepository: Send + Sync {
+    // Stores a new reset and returns the token to email. Older unused tokens
+    // of the user are dropped so only the latest link works.
+    async fn create(&self, username: &str, ttl_minutes: i64) -> Result<String, AppError>;
+
+    // When the most recent reset for the user was created
+    async fn last_created_at(&self, username: &str) -> Result<Option<BsonDateTime>, AppError>;
+
+    // Username the token was issued for, while it is unused and not expired
+    async fn find_valid(&self, token: &str) -> Result<Option<String>, AppError>;
+
+    // Marks the reset as used; returns false if it no longer was valid
+    async fn consume(&self, token: &str) -> Result<bool, AppError>;
+}
+
+pub struct MongoPasswordResetRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoPasswordResetRepository {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoPasswordResetRepository { collection }
+    }
+}
+
+fn create_valid_reset_filter(token: &str) -> Option<Document> {
+    let (reset_id, secret) = parse_token(token)?;
+
+    Some(doc! {
+        "_id": reset_id,
+        "token_hash": hash_token_secret(secret),
+        "used_at": null,
+        "expires_at": { "$gt": BsonDateTime::now() },
+    })
+}
+
+#[async_trait]
+impl PasswordResetRepository for MongoPasswordResetRepository {
+    async fn create(&self, username: &str, ttl_minutes: i64) -> Result<String, AppError> {
+        time_db_operation(
+            "delete_password_resets",
+            self.collection
+                .delete_many(doc! { "username": username, "used_at": null }),
+        )
+        .await?;
+
+        let (reset, token) = new_password_reset(username, ttl_minutes);
+        time_db_operation(
+            "insert_password_reset",
+            self.collection.insert_one(to_document(&reset)?),
+        )
+        .await?;
+
+        Ok(token)
+    }
+
+    async fn last_created_at(&self, username: &str) -> Result<Option<BsonDateTime>, AppError> {
+        let last = time_db_operation(
+            "find_last_password_reset",
+            self.collection
+                .find_one(doc! { "username": username })
+                .sort(doc! { "created_at": -1 }),
+        )
+        .await?;
+
+        match last {
+            Some(doc) => Ok(Some(from_document::<PasswordReset>(doc)?.created_at)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_valid(&self, token: &str) -> Result<Option<String>, AppError> {
+        let filter = match create_valid_reset_filter(token) {
+            Some(filter) => filter,
+            None => return Ok(None),
+        };
+
+        let reset =
+            time_db_operation("find_password_reset", self.collection.find_one(filter)).await?;
+
+        match reset {
+            Some(doc) => Ok(Some(from_document::<PasswordReset>(doc)?.username)),
+            None => Ok(None),
+        }
+    }
+
+    // Checking and marking happen in one update, so a token is only ever
+    // used up once
+    async fn consume(&self, token: &str) -> Result<bool, AppError> {
+        let filter = match create_valid_reset_filter(token) {
+            Some(filter) => filter,
+            None => return Ok(false),
+        };
+        let update = doc! { "$set": { "used_at": BsonDateTime::now() } };
+
+        let result = time_db_operation(
+            "consume_password_reset",
+            self.collection.update_one(filter, update),
+        )
+        .await?;
+
+        Ok(result.modified_count > 0)
+    }
+}
@@ -0,0 +1,328 @@
+use crate::constants::REFRESH_TOKEN_DAYS;
+use crate::errors::app_error::AppError;
+use crate::models::session::{DeviceInfo, UserSession};
+use crate::services::metrics_service::time_db_operation;
+use crate::structs::session::RefreshOutcome;
+use crate::utils::token::{format_token, generate_token_secret, hash_token_secret, parse_token};
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, from_document, to_document, DateTime as BsonDateTime, Document};
+use mongodb::Collection;
+
+pub(crate) const MAX_PREVIOUS_TOKEN_HASHES: i32 = 50;
+const MAX_DEVICE_FIELD_LENGTH: usize = 512;
+
+pub(crate) fn get_refresh_expiration() -> BsonDateTime {
+    let expires_at = chrono::Utc::now() + chrono::Duration::days(REFRESH_TOKEN_DAYS);
+    BsonDateTime::from_millis(expires_at.timestamp_millis())
+}
+
+// Keep client-supplied metadata to a sane size
+pub(crate) fn truncate_device_field(value: Option<String>) -> Option<String> {
+    value.map(|value| value.chars().take(MAX_DEVICE_FIELD_LENGTH).collect())
+}
+
+// A fresh session along with its first refresh token
+pub(crate) fn new_session(username: &str, device: &DeviceInfo) -> (UserSession, String) {
+    let secret = generate_token_secret();
+    let now = BsonDateTime::now();
+    let session = UserSession {
+        id: ObjectId::new(),
+        username: username.to_string(),
+        refresh_token_hash: hash_token_secret(&secret),
+        previous_token_hashes: Vec::new(),
+        user_agent: truncate_device_field(device.user_agent.clone()),
+        ip_address: truncate_device_field(device.ip_address.clone()),
+        created_at: now,
+        last_seen_at: now,
+        expires_at: get_refresh_expiration(),
+        revoked_at: None,
+        revoked_reason: None,
+    };
+    let refresh_token = format_token(&session.id, &secret);
+    (session, refresh_token)
+}
+
+// Server-side login sessions and their refresh tokens. Session ids are the
+// hex strings carried in access tokens; ids that do not parse match nothing.
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    // Returns the new session id and its first refresh token
+    async fn create(
+        &self,
+        username: &str,
+        device: &DeviceInfo,
+    ) -> Result<(String, String), AppError>;
+
+    async fn is_active(&self, session_id: &str) -> Result<bool, AppError>;
+
+    async fn revoke(&self, session_id: &str, reason: &str) -> Result<(), AppError>;
+
+    // Active sessions of a user, most recently used first
+    async fn list_active(&self, username: &str) -> Result<Vec<UserSession>, AppError>;
+
+    // Returns false when the user has no such active session
+    async fn revoke_for_user(
+        &self,
+        username: &str,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<bool, AppError>;
+
+    // Revokes every active session of the user except the given one
+    async fn revoke_others(
+        &self,
+        username: &str,
+        current_session_id: &str,
+        reason: &str,
+    ) -> Result<u64, AppError>;
+
+    // Revokes every active session of the user, e.g. after a password reset or ban
+    async fn revoke_all(&self, username: &str, reason: &str) -> Result<u64, AppError>;
+
+    // Exchanges a refresh token for a new one; presenting a rotated-out token
+    // again revokes the session
+    async fn rotate_refresh_token(
+        &self,
+        refresh_token: &str,
+        device: &DeviceInfo,
+    ) -> Result<RefreshOutcome, AppError>;
+}
+
+pub struct MongoSessionRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoSessionRepository {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoSessionRepository { collection }
+    }
+
+    async fn revoke_by_id(
+        &self,
+        session_id: ObjectId,
+        reason: &str,
+    ) -> Result<(), mongodb::error::Error> {
+        time_db_operation(
+            "revoke_session",
+            self.collection.update_one(
+                doc! { "_id": session_id, "revoked_at": null },
+                doc! { "$set": { "revoked_at": BsonDateTime::now(), "revoked_reason": reason } },
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn create_active_session_filter() -> Document {
+    doc! {
+        "revoked_at": null,
+        "expires_at": { "$gt": BsonDateTime::now() },
+    }
+}
+
+fn create_device_update(device: &DeviceInfo) -> Document {
+    doc! {
+        "user_agent": truncate_device_field(device.user_agent.clone()),
+        "ip_address": truncate_device_field(device.ip_address.clone()),
+        "last_seen_at": BsonDateTime::now(),
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MongoSessionRepository {
+    async fn create(
+        &self,
+        username: &str,
+        device: &DeviceInfo,
+    ) -> Result<(String, String), AppError> {
+        let (session, refresh_token) = new_session(username, device);
+
+        time_db_operation(
+            "insert_session",
+            self.collection.insert_one(to_document(&session)?),
+        )
+        .await?;
+
+        Ok((session.id.to_hex(), refresh_token))
+    }
+
+    async fn is_active(&self, session_id: &str) -> Result<bool, AppError> {
+        let session_id = match ObjectId::parse_str(session_id) {
+            Ok(session_id) => session_id,
+            Err(_) => return Ok(false),
+        };
+
+        let mut filter = create_active_session_filter();
+        filter.insert("_id", session_id);
+
+        let count =
+            time_db_operation("check_session", self.collection.count_documents(filter)).await?;
+
+        Ok(count > 0)
+    }
+
+    async fn revoke(&self, session_id: &str, reason: &str) -> Result<(), AppError> {
+        let session_id = match ObjectId::parse_str(session_id) {
+            Ok(session_id) => session_id,
+            Err(_) => return Ok(()),
+        };
+
+        Ok(self.revoke_by_id(session_id, reason).await?)
+    }
+
+    async fn list_active(&self, username: &str) -> Result<Vec<UserSession>, AppError> {
+        let mut filter = create_active_session_filter();
+        filter.insert("username", username);
+
+        let sessions = time_db_operation("list_sessions", async {
+            let mut cursor = self
+                .collection
+                .find(filter)
+                .sort(doc! { "last_seen_at": -1, "_id": -1 })
+                .await?;
+
+            let mut sessions = Vec::new();
+            while let Some(doc) = cursor.try_next().await? {
+                match from_document::<UserSession>(doc) {
+                    Ok(session) => sessions.push(session),
+                    Err(e) => tracing::warn!(error = ?e, "Error processing document"),
+                }
+            }
+            Ok::<_, mongodb::error::Error>(sessions)
+        })
+        .await?;
+
+        Ok(sessions)
+    }
+
+    async fn revoke_for_user(
+        &self,
+        username: &str,
+        session_id: &str,
+        reason: &str,
+    ) -> Result<bool, AppError> {
+        let session_id = match ObjectId::parse_str(session_id) {
+            Ok(session_id) => session_id,
+            Err(_) => return Ok(false),
+        };
+
+        let mut filter = create_active_session_filter();
+        filter.insert("_id", session_id);
+        filter.insert("username", username);
+
+        let result = time_db_operation(
+            "revoke_user_session",
+            self.collection.update_one(
+                filter,
+                doc! { "$set": { "revoked_at": BsonDateTime::now(), "revoked_reason": reason } },
+            ),
+        )
+        .await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn revoke_others(
+        &self,
+        username: &str,
+        current_session_id: &str,
+        reason: &str,
+    ) -> Result<u64, AppError> {
+        let mut filter = create_active_session_filter();
+        filter.insert("username", username);
+        if let Ok(current_session_id) = ObjectId::parse_str(current_session_id) {
+            filter.insert("_id", doc! { "$ne": current_session_id });
+        }
+
+        let result = time_db_operation(
+            "revoke_other_sessions",
+            self.collection.update_many(
+                filter,
+                doc! { "$set": { "revoked_at": BsonDateTime::now(), "revoked_reason": reason } },
+            ),
+        )
+        .await?;
+
+        Ok(result.modified_count)
+    }
+
+    async fn revoke_all(&self, username: &str, reason: &str) -> Result<u64, AppError> {
+        let mut filter = create_active_session_filter();
+        filter.insert("username", username);
+
+        let result = time_db_operation(
+            "revoke_all_sessions",
+            self.collection.update_many(
+                filter,
+                doc! { "$set": { "revoked_at": BsonDateTime::now(), "revoked_reason": reason } },
+            ),
+        )
+        .await?;
+
+        Ok(result.modified_count)
+    }
+
+    // The swap is a single conditional update on the current hash, so two
+    // requests racing with the same token cannot both succeed: the loser sees
+    // a rotated-out token and revokes the session as a reuse.
+    async fn rotate_refresh_token(
+        &self,
+        refresh_token: &str,
+        device: &DeviceInfo,
+    ) -> Result<RefreshOutcome, AppError> {
+        let (session_id, secret) = match parse_token(refresh_token) {
+            Some(parts) => parts,
+            None => return Ok(RefreshOutcome::Invalid),
+        };
+        let token_hash = hash_token_secret(secret);
+        let new_secret = generate_token_secret();
+
+        let mut filter = create_active_session_filter();
+        filter.insert("_id", session_id);
+        filter.insert("refresh_token_hash", &token_hash);
+
+        let mut set_fields = create_device_update(device);
+        set_fields.insert("refresh_token_hash", hash_token_secret(&new_secret));
+        set_fields.insert("expires_at", get_refresh_expiration());
+        let update = doc! {
+            "$set": set_fields,
+            "$push": {
+                "previous_token_hashes": {
+                    "$each": [&token_hash],
+                    "$slice": -MAX_PREVIOUS_TOKEN_HASHES,
+                }
+            },
+        };
+
+        let rotated = time_db_operation(
+            "rotate_refresh_token",
+            self.collection.find_one_and_update(filter, update),
+        )
+        .await?;
+        if let Some(session) = rotated {
+            let username = session.get_str("username").unwrap_or_default().to_string();
+            return Ok(RefreshOutcome::Rotated {
+                username,
+                session_id: session_id.to_hex(),
+                refresh_token: format_token(&session_id, &new_secret),
+            });
+        }
+
+        let reuse_filter = doc! { "_id": session_id, "previous_token_hashes": &token_hash };
+        let reused = time_db_operation(
+            "find_reused_refresh_token",
+            self.collection.count_documents(reuse_filter),
+        )
+        .await?;
+        if reused > 0 {
+            self.revoke_by_id(session_id, "refresh_token_reuse").await?;
+            return Ok(RefreshOutcome::Reused);
+        }
+
+        Ok(RefreshOutcome::Invalid)
+    }
+}
@@ -0,0 +1,423 @@
+use crate::constants::COLL_NAME;
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::test_result::TestResultRecord;
+use crate::models::user::{DifficultyScores, HighScores, LanguageScores, Score};
+use crate::repositories::leaderboard_repository::{
+    create_position_filter, create_sort_stage, Ordering, SortKeys,
+};
+use crate::services::metrics_service::time_db_operation;
+use crate::structs::claims::TestSessionClaims;
+use crate::structs::leaderboard::{
+    LeaderboardCursor, LeaderboardEntry, LeaderboardPage, LeaderboardPaging,
+};
+use crate::structs::test_result::GetTestResultsQueries;
+use crate::utils::helpers::{is_duplicate_key_error, to_bson_datetime};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, to_document, Bson, DateTime as BsonDateTime, Document};
+use mongodb::Collection;
+use std::collections::HashMap;
+
+// Fixed width so that formatted dates compare correctly as strings
+const WINDOW_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S.%LZ";
+
+// Submitted typing tests: the one-time claims on their sessions, the stored
+// results, and the daily, weekly and monthly boards built from those results
+#[async_trait]
+pub trait TestResultRepository: Send + Sync {
+    // Marks the session as submitted; returns false if it was already used
+    async fn claim_session(&self, claims: &TestSessionClaims) -> Result<bool, AppError>;
+
+    // Undoes a claim whose result could not be saved
+    async fn release_session(&self, claims: &TestSessionClaims) -> Result<(), AppError>;
+
+    async fn insert(&self, result: &TestResultRecord) -> Result<(), AppError>;
+
+    // A user's results matching the filters, most recent first, along with
+    // how many match in total
+    async fn fetch_page(
+        &self,
+        username: &str,
+        queries: &GetTestResultsQueries,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<TestResultRecord>, u64), AppError>;
+
+    // Number of users with a result for the mode since `window_start`
+    async fn count_in_window(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+    ) -> Result<i64, AppError>;
+
+    // Each user's best result since `window_start`, in leaderboard order
+    async fn fetch_window_page(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError>;
+}
+
+pub(crate) fn create_single_mode_high_scores(mode: &GameMode, score: Score) -> HighScores {
+    let mut scores = HashMap::new();
+    scores.insert(mode.duration().to_string(), score);
+
+    let mut difficulties = HashMap::new();
+    difficulties.insert(mode.difficulty().to_string(), DifficultyScores { scores });
+
+    let mut languages = HashMap::new();
+    languages.insert(mode.language().to_string(), LanguageScores { difficulties });
+
+    HighScores { languages }
+}
+
+pub struct MongoTestResultRepository {
+    results: Collection<Document>,
+    test_sessions: Collection<Document>,
+}
+
+impl MongoTestResultRepository {
+    pub fn new(results: Collection<Document>, test_sessions: Collection<Document>) -> Self {
+        MongoTestResultRepository {
+            results,
+            test_sessions,
+        }
+    }
+}
+
+fn create_results_filter(username: &str, queries: &GetTestResultsQueries) -> Document {
+    let mut filter = doc! { "username": username };
+
+    if let Some(language) = &queries.language {
+        filter.insert("language", language);
+    }
+    if let Some(difficulty) = &queries.difficulty {
+        filter.insert("difficulty", difficulty);
+    }
+    if let Some(duration) = &queries.duration {
+        filter.insert("duration", duration);
+    }
+
+    let mut timestamp_range = Document::new();
+    if let Some(from) = queries.from {
+        timestamp_range.insert("$gte", to_bson_datetime(from));
+    }
+    if let Some(to) = queries.to {
+        timestamp_range.insert("$lte", to_bson_datetime(to));
+    }
+    if !timestamp_range.is_empty() {
+        filter.insert("timestamp", timestamp_range);
+    }
+
+    filter
+}
+
+fn windowed_sort_keys() -> SortKeys {
+    SortKeys {
+        wpm: "wpm".to_string(),
+        accuracy: "accuracy".to_string(),
+        date: "date".to_string(),
+    }
+}
+
+fn create_window_match_stage(mode: &GameMode, window_start: DateTime<Utc>) -> Document {
+    doc! { "$match": {
+        "language": mode.language(),
+        "difficulty": mode.difficulty(),
+        "duration": mode.duration(),
+        "timestamp": { "$gte": BsonDateTime::from_millis(window_start.timestamp_millis()) },
+    }}
+}
+
+// Reduces the results in the window to each user's best one, using the same
+// tie-breaking as the all-time board
+fn create_best_per_user_stages() -> Vec<Document> {
+    vec![
+        doc! { "$sort": { "wpm": -1, "accuracy": -1, "timestamp": 1, "_id": 1 } },
+        doc! { "$group": {
+            "_id": "$username",
+            "wpm": { "$first": "$wpm" },
+            "raw_wpm": { "$first": "$raw_wpm" },
+            "accuracy": { "$first": "$accuracy" },
+            "timestamp": { "$first": "$timestamp" },
+            "completed_tests": { "$sum": 1 },
+        }},
+        doc! { "$addFields": {
+            "date": { "$dateToString": { "date": "$timestamp", "format": WINDOW_DATE_FORMAT } },
+        }},
+    ]
+}
+
+// Like the all-time board, banned users are left off. Runs after grouping,
+// so there is one lookup per user rather than per result.
+fn create_exclude_banned_stages() -> Vec<Document> {
+    vec![
+        doc! { "$lookup": {
+            "from": COLL_NAME,
+            "let": { "username": "$_id" },
+            "pipeline": [
+                doc! { "$match": {
+                    "$expr": { "$eq": ["$username", "$$username"] },
+                    "banned": true,
+                }},
+                doc! { "$project": { "_id": 1 } },
+            ],
+            "as": "banned_user",
+        }},
+        doc! { "$match": { "banned_user": { "$size": 0 } } },
+        doc! { "$project": { "banned_user": 0 } },
+    ]
+}
+
+fn create_windowed_pipeline(
+    mode: &GameMode,
+    window_start: DateTime<Utc>,
+    paging: &LeaderboardPaging,
+    limit: u64,
+) -> Vec<Document> {
+    let keys = windowed_sort_keys();
+
+    let mut pipeline = vec![create_window_match_stage(mode, window_start)];
+    pipeline.extend(create_best_per_user_stages());
+    pipeline.extend(create_exclude_banned_stages());
+
+    if let Some(cursor) = &paging.after {
+        pipeline.push(doc! { "$match": create_position_filter(&keys, cursor, Ordering::After) });
+    }
+
+    pipeline.extend(vec![
+        create_sort_stage(&keys, false),
+        doc! { "$skip": paging.skip as i64 },
+        doc! { "$limit": limit as i64 },
+        // Only the users on this page are looked up to fill in their ids
+        doc! { "$lookup": {
+            "from": COLL_NAME,
+            "localField": "_id",
+            "foreignField": "username",
+            "as": "user",
+        }},
+        doc! { "$addFields": { "user_id": { "$arrayElemAt": ["$user._id", 0] } } },
+        doc! { "$project": { "user": 0 } },
+    ]);
+
+    pipeline
+}
+
+async fn get_windowed_document_count(
+    collection: &Collection<Document>,
+    mode: &GameMode,
+    window_start: DateTime<Utc>,
+) -> Result<i64, mongodb::error::Error> {
+    let mut pipeline = vec![
+        create_window_match_stage(mode, window_start),
+        doc! { "$group": { "_id": "$username" } },
+    ];
+    pipeline.extend(create_exclude_banned_stages());
+    pipeline.push(doc! { "$count": "total_count" });
+
+    time_db_operation("count_windowed_leaderboard", async {
+        let mut cursor = collection.aggregate(pipeline).await?;
+        let count = cursor
+            .try_next()
+            .await?
+            .and_then(|doc| get_number(&doc, "total_count"))
+            .unwrap_or(0);
+
+        Ok(count)
+    })
+    .await
+}
+
+fn get_number(doc: &Document, key: &str) -> Option<i64> {
+    match doc.get(key)? {
+        Bson::Int32(n) => Some(*n as i64),
+        Bson::Int64(n) => Some(*n),
+        Bson::Double(n) => Some(*n as i64),
+        _ => None,
+    }
+}
+
+fn extract_windowed_entry(
+    doc: &Document,
+    mode: &GameMode,
+) -> Option<(LeaderboardEntry, LeaderboardCursor)> {
+    let username = doc.get_str("_id").ok()?.to_string();
+    let wpm = get_number(doc, "wpm")?;
+    let accuracy = match doc.get("accuracy")? {
+        Bson::Double(accuracy) => *accuracy,
+        _ => get_number(doc, "accuracy")? as f64,
+    };
+    let timestamp = doc.get_datetime("timestamp").ok()?;
+    let date = Utc
+        .timestamp_millis_opt(timestamp.timestamp_millis())
+        .single()?;
+
+    let score = Score {
+        wpm: wpm as u32,
+        raw_wpm: get_number(doc, "raw_wpm").unwrap_or_default() as u32,
+        accuracy: accuracy as f32,
+        date,
+    };
+
+    let entry = LeaderboardEntry {
+        _id: doc
+            .get_object_id("user_id")
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        username: username.clone(),
+        completed_tests: get_number(doc, "completed_tests").unwrap_or_default() as u32,
+        high_scores: create_single_mode_high_scores(mode, score),
+    };
+
+    let position = LeaderboardCursor {
+        wpm,
+        accuracy,
+        date: doc.get_str("date").ok()?.to_string(),
+        id: Bson::String(username),
+    };
+
+    Some((entry, position))
+}
+
+async fn fetch_windowed_users(
+    collection: &Collection<Document>,
+    mode: &GameMode,
+    window_start: DateTime<Utc>,
+    paging: &LeaderboardPaging,
+) -> Result<LeaderboardPage, mongodb::error::Error> {
+    // Fetch one extra entry to find out whether another page exists
+    let pipeline = create_windowed_pipeline(mode, window_start, paging, paging.limit + 1);
+
+    let mut users = time_db_operation("aggregate_windowed_leaderboard", async {
+        let mut cursor = collection.aggregate(pipeline).await?;
+        let mut users = Vec::new();
+
+        while let Some(doc) = cursor.try_next().await? {
+            match extract_windowed_entry(&doc, mode) {
+                Some(entry) => users.push(entry),
+                None => tracing::warn!(id = ?doc.get("_id"), "Error processing document"),
+            }
+        }
+        Ok::<_, mongodb::error::Error>(users)
+    })
+    .await?;
+
+    let has_more = users.len() as u64 > paging.limit;
+    users.truncate(paging.limit as usize);
+
+    let next_cursor = if has_more {
+        users.last().map(|(_, position)| position.encode())
+    } else {
+        None
+    };
+
+    Ok(LeaderboardPage {
+        entries: users.into_iter().map(|(entry, _)| entry).collect(),
+        next_cursor,
+    })
+}
+
+#[async_trait]
+impl TestResultRepository for MongoTestResultRepository {
+    async fn claim_session(&self, claims: &TestSessionClaims) -> Result<bool, AppError> {
+        let filter = doc! { "test_id": &claims.test_id };
+        let update = doc! {
+            "$setOnInsert": {
+                "test_id": &claims.test_id,
+                "username": &claims.sub,
+                "finished_at": BsonDateTime::now(),
+            }
+        };
+
+        let claimed = time_db_operation(
+            "claim_test_session",
+            self.test_sessions.update_one(filter, update).upsert(true),
+        )
+        .await;
+        match claimed {
+            Ok(result) => Ok(result.upserted_id.is_some()),
+            // A concurrent submission of the same session inserted it first
+            Err(err) if is_duplicate_key_error(&err) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // The user can then submit the same session again instead of losing the test
+    async fn release_session(&self, claims: &TestSessionClaims) -> Result<(), AppError> {
+        time_db_operation(
+            "release_test_session",
+            self.test_sessions
+                .delete_one(doc! { "test_id": &claims.test_id }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn insert(&self, result: &TestResultRecord) -> Result<(), AppError> {
+        time_db_operation(
+            "insert_test_result",
+            self.results.insert_one(to_document(result)?),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn fetch_page(
+        &self,
+        username: &str,
+        queries: &GetTestResultsQueries,
+        offset: u64,
+        limit: u64,
+    ) -> Result<(Vec<TestResultRecord>, u64), AppError> {
+        let filter = create_results_filter(username, queries);
+
+        let total_count = time_db_operation(
+            "count_test_results",
+            self.results.count_documents(filter.clone()),
+        )
+        .await?;
+
+        let results = time_db_operation("find_test_results", async {
+            // Most recent results first, _id keeps the order stable for equal timestamps
+            let mut cursor = self
+                .results
+                .find(filter)
+                .sort(doc! { "timestamp": -1, "_id": -1 })
+                .skip(offset)
+                .limit(limit as i64)
+                .await?;
+
+            let mut results = Vec::new();
+            while let Some(doc) = cursor.try_next().await? {
+                match from_document::<TestResultRecord>(doc) {
+                    Ok(record) => results.push(record),
+                    Err(e) => tracing::warn!(error = ?e, "Error processing document"),
+                }
+            }
+            Ok::<_, mongodb::error::Error>(results)
+        })
+        .await?;
+
+        Ok((results, total_count))
+    }
+
+    async fn count_in_window(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+    ) -> Result<i64, AppError> {
+        Ok(get_windowed_document_count(&self.results, mode, window_start).await?)
+    }
+
+    async fn fetch_window_page(
+        &self,
+        mode: &GameMode,
+        window_start: DateTime<Utc>,
+        paging: &LeaderboardPaging,
+    ) -> Result<LeaderboardPage, AppError> {
+        Ok(fetch_windowed_users(&self.results, mode, window_start, paging).await?)
+    }
+}
@@ -0,0 +1,252 @@
+use crate::constants::EMAIL_INDEX_NAME;
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::user::{Score, User};
+use crate::services::metrics_service::time_db_operation;
+use crate::utils::helpers::is_duplicate_key_error;
+use async_trait::async_trait;
+use mongodb::bson::{
+    doc, from_bson, to_bson, to_document, Bson, DateTime as BsonDateTime, Document,
+};
+use mongodb::options::ReturnDocument;
+use mongodb::Collection;
+
+// Storage for user accounts and their personal bests
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>, AppError>;
+
+    // The stored user document without the password hash
+    async fn find_profile(&self, username: &str) -> Result<Option<Document>, AppError>;
+
+    async fn exists(&self, username: &str) -> Result<bool, AppError>;
+
+    async fn insert(&self, user: User) -> Result<(), AppError>;
+
+    async fn update_password(&self, username: &str, password_hash: &str) -> Result<(), AppError>;
+
+    // Swaps the stored hash for a stronger one of the same password, unless
+    // the password changed in the meantime. Returns whether it was replaced.
+    async fn upgrade_password_hash(
+        &self,
+        username: &str,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<bool, AppError>;
+
+    // Replaces or removes the address and marks it unverified. Returns false
+    // if the user does not exist.
+    async fn update_email(&self, username: &str, email: Option<&str>) -> Result<bool, AppError>;
+
+    // Starts the resend interval for verification emails
+    async fn mark_verification_email_sent(&self, username: &str) -> Result<(), AppError>;
+
+    // Returns false unless the user's current address is `email`; an
+    // address another user already verified is rejected with EmailTaken
+    async fn mark_email_verified(&self, username: &str, email: &str) -> Result<bool, AppError>;
+
+    // Counts the test and keeps the score if it beats the stored one. Returns
+    // None if the user does not exist, otherwise whether it was a personal best.
+    async fn record_score(
+        &self,
+        username: &str,
+        mode: &GameMode,
+        score: &Score,
+    ) -> Result<Option<bool>, AppError>;
+}
+
+pub struct MongoUserRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoUserRepository {
+    pub fn new(collection: Collection<Document>) -> Self {
+        MongoUserRepository { collection }
+    }
+}
+
+fn is_duplicate_email_error(err: &mongodb::error::Error) -> bool {
+    is_duplicate_key_error(err) && err.to_string().contains(EMAIL_INDEX_NAME)
+}
+
+fn get_existing_wpm(user_doc: &Document, mode: &GameMode) -> Option<i64> {
+    let score = user_doc
+        .get_document("high_scores")
+        .and_then(|doc| doc.get_document("languages"))
+        .and_then(|doc| doc.get_document(mode.language()))
+        .and_then(|doc| doc.get_document("difficulties"))
+        .and_then(|doc| doc.get_document(mode.difficulty()))
+        .and_then(|doc| doc.get_document("scores"))
+        .and_then(|doc| doc.get_document(mode.duration()))
+        .ok()?;
+
+    match score.get("wpm") {
+        Some(Bson::Int32(wpm)) => Some(*wpm as i64),
+        Some(Bson::Int64(wpm)) => Some(*wpm),
+        _ => None,
+    }
+}
+
+#[async_trait]
+impl UserRepository for MongoUserRepository {
+    async fn find_by_username(&self, username: &str) -> Result<Option<User>, AppError> {
+        let filter = doc! { "username": username };
+        let user_doc = time_db_operation("find_user", self.collection.find_one(filter)).await?;
+
+        match user_doc {
+            Some(doc) => Ok(Some(from_bson(Bson::Document(doc))?)),
+            None => Ok(None),
+        }
+    }
+
+    // The private email stays hidden; whether it is verified stays visible
+    async fn find_profile(&self, username: &str) -> Result<Option<Document>, AppError> {
+        let filter = doc! { "username": username };
+
+        let profile = time_db_operation(
+            "find_user_profile",
+            self.collection
+                .find_one(filter)
+                .projection(doc! { "password": 0, "email": 0, "email_verification_sent_at": 0 }),
+        )
+        .await?;
+
+        // Users from before email verification have no flag stored
+        Ok(profile.map(|mut profile| {
+            if !profile.contains_key("email_verified") {
+                profile.insert("email_verified", false);
+            }
+            profile
+        }))
+    }
+
+    async fn exists(&self, username: &str) -> Result<bool, AppError> {
+        let filter = doc! { "username": username };
+        Ok(
+            time_db_operation("find_user", self.collection.find_one(filter))
+                .await?
+                .is_some(),
+        )
+    }
+
+    async fn insert(&self, user: User) -> Result<(), AppError> {
+        match time_db_operation(
+            "insert_user",
+            self.collection.insert_one(to_document(&user)?),
+        )
+        .await
+        {
+            Ok(_) => Ok(()),
+            // Lost a race with a concurrent sign-up for the same name
+            Err(err) if is_duplicate_key_error(&err) => Err(AppError::UsernameTaken),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn update_password(&self, username: &str, password_hash: &str) -> Result<(), AppError> {
+        let filter = doc! { "username": username };
+        let update = doc! { "$set": { "password": password_hash } };
+        time_db_operation(
+            "update_password",
+            self.collection.update_one(filter, update),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upgrade_password_hash(
+        &self,
+        username: &str,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<bool, AppError> {
+        let filter = doc! { "username": username, "password": old_hash };
+        let update = doc! { "$set": { "password": new_hash } };
+        let result = time_db_operation(
+            "upgrade_password_hash",
+            self.collection.update_one(filter, update),
+        )
+        .await?;
+
+        Ok(result.modified_count > 0)
+    }
+
+    async fn update_email(&self, username: &str, email: Option<&str>) -> Result<bool, AppError> {
+        let filter = doc! { "username": username };
+        let update = match email {
+            Some(email) => doc! { "$set": { "email": email, "email_verified": false } },
+            None => doc! { "$unset": { "email": "" }, "$set": { "email_verified": false } },
+        };
+
+        let result =
+            time_db_operation("update_email", self.collection.update_one(filter, update)).await?;
+        Ok(result.matched_count > 0)
+    }
+
+    async fn mark_verification_email_sent(&self, username: &str) -> Result<(), AppError> {
+        let filter = doc! { "username": username };
+        let update = doc! { "$set": { "email_verification_sent_at": BsonDateTime::now() } };
+        time_db_operation(
+            "mark_verification_email_sent",
+            self.collection.update_one(filter, update),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    // A link for an address the user has since replaced matches nothing
+    async fn mark_email_verified(&self, username: &str, email: &str) -> Result<bool, AppError> {
+        let filter = doc! { "username": username, "email": email };
+        let update = doc! { "$set": { "email_verified": true } };
+
+        match time_db_operation("verify_email", self.collection.update_one(filter, update)).await {
+            Ok(result) => Ok(result.matched_count > 0),
+            Err(err) if is_duplicate_email_error(&err) => Err(AppError::EmailTaken),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // A single atomic update: completed_tests is always incremented and the
+    // score for this mode is only replaced when the new wpm beats the stored one
+    async fn record_score(
+        &self,
+        username: &str,
+        mode: &GameMode,
+        score: &Score,
+    ) -> Result<Option<bool>, AppError> {
+        let new_wpm = score.wpm as i64;
+        let score_path = mode.score_path();
+        let current_score = format!("${}", score_path);
+
+        let filter = doc! { "username": username };
+        let update = vec![doc! {
+            "$set": {
+                "completed_tests": { "$add": [{ "$ifNull": ["$completed_tests", 0] }, 1] },
+                &score_path: {
+                    "$cond": [
+                        { "$gt": [new_wpm, { "$ifNull": [format!("{}.wpm", current_score), -1] }] },
+                        { "$literal": to_bson(score)? },
+                        &current_score,
+                    ]
+                },
+            }
+        }];
+
+        // The document as it was before the update tells us whether this was a best
+        let previous = time_db_operation(
+            "record_score",
+            self.collection
+                .find_one_and_update(filter, update)
+                .projection(doc! { "_id": 0, &score_path: 1 })
+                .return_document(ReturnDocument::Before),
+        )
+        .await?;
+
+        Ok(previous.map(|user_doc| {
+            let existing_wpm = get_existing_wpm(&user_doc, mode).unwrap_or(-1);
+            new_wpm > existing_wpm
+        }))
+    }
+}
@@ -0,0 +1,17 @@
+use crate::controllers::email_controller::{
+    confirm_verification, delete_email, resend_verification, update_email,
+};
+use actix_web::web;
+
+pub fn configure_email_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/me/email")
+            .route(web::put().to(update_email))
+            .route(web::delete().to(delete_email)),
+    )
+    .route("/me/email/resend", web::post().to(resend_verification))
+    .route(
+        "/email_verification/confirm",
+        web::post().to(confirm_verification),
+    );
+}
@@ -0,0 +1,8 @@
+use crate::controllers::health_controller::{healthz, readyz, version};
+use actix_web::web;
+
+pub fn configure_health_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/healthz", web::get().to(healthz))
+        .route("/readyz", web::get().to(readyz))
+        .route("/version", web::get().to(version));
+}
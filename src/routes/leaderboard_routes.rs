@@ -1,9 +1,18 @@
-use crate::controllers::leaderboard_controller::get_leaderboard_stats;
+use crate::controllers::leaderboard_controller::{
+    get_leaderboard_around_user, get_leaderboard_stats, get_user_rank,
+};
+use crate::controllers::leaderboard_socket_controller::leaderboard_socket;
 use actix_web::web;
 
 pub fn configure_leaderboard_routes(cfg: &mut web::ServiceConfig) {
     cfg.route(
         "/get_leaderboard_stats",
         web::get().to(get_leaderboard_stats),
-    );
+    )
+    .route("/leaderboard/rank/{username}", web::get().to(get_user_rank))
+    .route(
+        "/leaderboard/around/{username}",
+        web::get().to(get_leaderboard_around_user),
+    )
+    .route("/ws/leaderboard", web::get().to(leaderboard_socket));
 }
@@ -0,0 +1,6 @@
+use crate::controllers::metrics_controller::get_metrics;
+use actix_web::web;
+
+pub fn configure_metrics_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(get_metrics));
+}
@@ -1,2 +1,10 @@
+pub mod email_routes;
+pub mod health_routes;
 pub mod leaderboard_routes;
+pub mod metrics_routes;
+pub mod password_routes;
+pub mod race_routes;
+pub mod session_routes;
+pub mod test_result_routes;
+pub mod typing_test_routes;
 pub mod user_routes;
@@ -0,0 +1,8 @@
+use crate::controllers::password_controller::{change_password, confirm_reset, request_reset};
+use actix_web::web;
+
+pub fn configure_password_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/me/password", web::put().to(change_password))
+        .route("/password_reset/request", web::post().to(request_reset))
+        .route("/password_reset/confirm", web::post().to(confirm_reset));
+}
@@ -0,0 +1,8 @@
+use crate::controllers::race_result_controller::get_user_races;
+use crate::controllers::race_socket_controller::race_socket;
+use actix_web::web;
+
+pub fn configure_race_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/ws/race", web::get().to(race_socket))
+        .route("/users/{username}/races", web::get().to(get_user_races));
+}
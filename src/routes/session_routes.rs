@@ -0,0 +1,13 @@
+use crate::controllers::session_controller::{
+    list_sessions, revoke_other_user_sessions, revoke_session,
+};
+use actix_web::web;
+
+pub fn configure_session_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/me/sessions")
+            .route(web::get().to(list_sessions))
+            .route(web::delete().to(revoke_other_user_sessions)),
+    )
+    .service(web::resource("/me/sessions/{id}").route(web::delete().to(revoke_session)));
+}
@@ -0,0 +1,6 @@
+use crate::controllers::test_result_controller::get_user_results;
+use actix_web::web;
+
+pub fn configure_test_result_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/users/{username}/results", web::get().to(get_user_results));
+}
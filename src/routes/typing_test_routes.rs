@@ -0,0 +1,7 @@
+use crate::controllers::typing_test_controller::{finish_test, start_test};
+use actix_web::web;
+
+pub fn configure_typing_test_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/tests/start", web::post().to(start_test))
+        .route("/tests/finish", web::post().to(finish_test));
+}
@@ -0,0 +1,370 @@
+use crate::config::modes::ModeRegistry;
+use crate::constants::{
+    COLL_NAME, EMAIL_VERIFICATIONS_COLL_NAME, PASSWORD_RESETS_COLL_NAME, SESSIONS_COLL_NAME,
+    TEST_RESULTS_COLL_NAME, TEST_SESSIONS_COLL_NAME,
+};
+use crate::errors::app_error::AppError;
+use crate::models::test_result::TestResultRecord;
+use crate::models::user::{default_user, HighScores, Score, User};
+use crate::repositories::session_repository::{MongoSessionRepository, SessionRepository};
+use crate::services::metrics_service::time_db_operation;
+use crate::services::password_service::hash_password;
+use crate::services::user_service::normalize_email;
+use crate::utils::helpers::{
+    get_duplicate_key_indexes, is_duplicate_key_error, to_chrono_datetime,
+};
+use chrono::{Duration, Utc};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, to_bson, to_document, Document};
+use mongodb::{Collection, Database};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::collections::HashSet;
+
+const SEED_USERNAME_PREFIX: &str = "seed_";
+const SEED_BATCH_SIZE: usize = 500;
+const SEED_MAX_AGE_DAYS: i64 = 90;
+
+// Maintenance operations behind the eletypes-admin binary. They talk to
+// MongoDB directly and skip the captcha and rate checks of the HTTP API.
+
+fn users(db: &Database) -> Collection<Document> {
+    db.collection(COLL_NAME)
+}
+
+fn sessions(db: &Database) -> MongoSessionRepository {
+    MongoSessionRepository::new(db.collection(SESSIONS_COLL_NAME))
+}
+
+fn user_not_found(username: &str) -> AppError {
+    AppError::NotFound(format!("User '{}' not found in the database", username))
+}
+
+fn hash(password: &str) -> Result<String, AppError> {
+    hash_password(password)
+        .map_err(|err| AppError::Internal(format!("Error hashing password: {}", err)))
+}
+
+pub async fn set_user_banned(db: &Database, username: &str, banned: bool) -> Result<(), AppError> {
+    let result = time_db_operation(
+        "admin_set_user_banned",
+        users(db).update_one(
+            doc! { "username": username },
+            doc! { "$set": { "banned": banned } },
+        ),
+    )
+    .await?;
+    if result.matched_count == 0 {
+        return Err(user_not_found(username));
+    }
+
+    // Existing tokens would otherwise keep working until they expire
+    if banned {
+        sessions(db).revoke_all(username, "banned").await?;
+    }
+    Ok(())
+}
+
+// Sets the address password reset links are sent to. An operator vouches
+// for it, so it counts as verified without a link.
+pub async fn set_user_email(db: &Database, username: &str, email: &str) -> Result<(), AppError> {
+    let email = normalize_email(email)?;
+    let updated = time_db_operation(
+        "admin_set_user_email",
+        users(db).update_one(
+            doc! { "username": username },
+            doc! { "$set": { "email": email, "email_verified": true } },
+        ),
+    )
+    .await;
+    let result = match updated {
+        Ok(result) => result,
+        Err(err) if is_duplicate_key_error(&err) => return Err(AppError::EmailTaken),
+        Err(err) => return Err(err.into()),
+    };
+    if result.matched_count == 0 {
+        return Err(user_not_found(username));
+    }
+    Ok(())
+}
+
+// Removes the account along with its sessions and personal history. Race
+// results are kept since they belong to the other players as well.
+pub async fn delete_user(db: &Database, username: &str) -> Result<(), AppError> {
+    let result = time_db_operation(
+        "admin_delete_user",
+        users(db).delete_one(doc! { "username": username }),
+    )
+    .await?;
+    if result.deleted_count == 0 {
+        return Err(user_not_found(username));
+    }
+
+    let filter = doc! { "username": username };
+    for name in [
+        EMAIL_VERIFICATIONS_COLL_NAME,
+        PASSWORD_RESETS_COLL_NAME,
+        SESSIONS_COLL_NAME,
+        TEST_RESULTS_COLL_NAME,
+        TEST_SESSIONS_COLL_NAME,
+    ] {
+        time_db_operation(
+            "admin_delete_user_data",
+            db.collection::<Document>(name).delete_many(filter.clone()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+pub async fn reset_password(db: &Database, username: &str, password: &str) -> Result<(), AppError> {
+    if password.is_empty() {
+        return Err(AppError::Validation(
+            "Password cannot be empty.".to_string(),
+        ));
+    }
+
+    let result = time_db_operation(
+        "admin_reset_password",
+        users(db).update_one(
+            doc! { "username": username },
+            doc! { "$set": { "password": hash(password)? } },
+        ),
+    )
+    .await?;
+    if result.matched_count == 0 {
+        return Err(user_not_found(username));
+    }
+
+    sessions(db).revoke_all(username, "password_reset").await?;
+    Ok(())
+}
+
+// Sets completed_tests to the number of stored results. Tests finished before
+// the results history existed are not counted, so this can lower the value.
+pub async fn recompute_completed_tests(db: &Database, username: &str) -> Result<u64, AppError> {
+    let completed_tests = time_db_operation(
+        "admin_count_test_results",
+        db.collection::<Document>(TEST_RESULTS_COLL_NAME)
+            .count_documents(doc! { "username": username }),
+    )
+    .await?;
+
+    let result = time_db_operation(
+        "admin_set_completed_tests",
+        users(db).update_one(
+            doc! { "username": username },
+            doc! { "$set": { "completed_tests": completed_tests as i64 } },
+        ),
+    )
+    .await?;
+    if result.matched_count == 0 {
+        return Err(user_not_found(username));
+    }
+    Ok(completed_tests)
+}
+
+async fn replace_high_scores(
+    db: &Database,
+    username: &str,
+    high_scores: &HighScores,
+) -> Result<(), AppError> {
+    let result = time_db_operation(
+        "admin_replace_high_scores",
+        users(db).update_one(
+            doc! { "username": username },
+            doc! { "$set": { "high_scores": to_bson(high_scores)? } },
+        ),
+    )
+    .await?;
+    if result.matched_count == 0 {
+        return Err(user_not_found(username));
+    }
+    Ok(())
+}
+
+fn empty_high_scores(modes: &ModeRegistry) -> HighScores {
+    default_user(modes).high_scores.unwrap_or(HighScores {
+        languages: Default::default(),
+    })
+}
+
+// Resets every personal best to the zeroed defaults new accounts start with
+pub async fn wipe_high_scores(
+    db: &Database,
+    username: &str,
+    modes: &ModeRegistry,
+) -> Result<(), AppError> {
+    replace_high_scores(db, username, &empty_high_scores(modes)).await
+}
+
+// Rebuilds the personal bests from the results history, using the same
+// ordering as the leaderboards. Returns the number of modes with a result.
+pub async fn recalculate_high_scores(
+    db: &Database,
+    username: &str,
+    modes: &ModeRegistry,
+) -> Result<usize, AppError> {
+    let pipeline = vec![
+        doc! { "$match": {
+            "username": username,
+            "language": { "$in": &modes.languages },
+            "difficulty": { "$in": &modes.difficulties },
+            "duration": { "$in": &modes.durations },
+        }},
+        doc! { "$sort": { "wpm": -1, "accuracy": -1, "timestamp": 1, "_id": 1 } },
+        doc! { "$group": {
+            "_id": { "language": "$language", "difficulty": "$difficulty", "duration": "$duration" },
+            "best": { "$first": "$$ROOT" },
+        }},
+    ];
+
+    let mut high_scores = empty_high_scores(modes);
+    let mut restored = 0;
+    let groups = time_db_operation("admin_aggregate_best_results", async {
+        db.collection::<Document>(TEST_RESULTS_COLL_NAME)
+            .aggregate(pipeline)
+            .await?
+            .try_collect::<Vec<Document>>()
+            .await
+    })
+    .await?;
+
+    for group in groups {
+        let best = group
+            .get_document("best")
+            .map_err(|e| AppError::Internal(format!("Malformed result aggregation: {}", e)))?;
+        let record: TestResultRecord = from_document(best.clone())?;
+
+        let score = Score {
+            wpm: record.wpm,
+            raw_wpm: record.raw_wpm,
+            accuracy: record.accuracy,
+            date: to_chrono_datetime(record.timestamp),
+        };
+
+        // The $match above guarantees these keys exist in the zeroed defaults
+        if let Some(scores) = high_scores
+            .languages
+            .get_mut(&record.language)
+            .and_then(|language| language.difficulties.get_mut(&record.difficulty))
+        {
+            scores.scores.insert(record.duration, score);
+            restored += 1;
+        }
+    }
+
+    replace_high_scores(db, username, &high_scores).await?;
+    Ok(restored)
+}
+
+// Box-Muller transform; rand 0.8 ships no normal distribution without rand_distr
+fn sample_normal(rng: &mut impl Rng, mean: f64, std_dev: f64) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+    mean + z * std_dev
+}
+
+fn create_seed_score(rng: &mut impl Rng, skill: f64, difficulty: &str, duration: &str) -> Score {
+    // Harder word lists and longer tests both pull the typing speed down a bit
+    let difficulty_penalty = if difficulty == "hard" { 0.08 } else { 0.0 };
+    let duration_secs: f64 = duration.parse().unwrap_or(60.0);
+    let penalty = 1.0 - difficulty_penalty - 0.02 * (duration_secs / 30.0);
+    let wpm = sample_normal(rng, skill * penalty, 4.0).clamp(10.0, 220.0);
+
+    // Faster typists tend to be more accurate too
+    let accuracy = sample_normal(rng, 88.0 + wpm / 20.0, 3.0).clamp(70.0, 100.0);
+    let raw_wpm = (wpm * 100.0 / accuracy).max(wpm);
+    let age = Duration::minutes(rng.gen_range(0..SEED_MAX_AGE_DAYS * 24 * 60));
+
+    Score {
+        wpm: wpm.round() as u32,
+        raw_wpm: raw_wpm.round() as u32,
+        accuracy: ((accuracy * 100.0).round() / 100.0) as f32,
+        date: Utc::now() - age,
+    }
+}
+
+fn create_seed_user(rng: &mut impl Rng, password_hash: &str, modes: &ModeRegistry) -> User {
+    let suffix: String = rng
+        .sample_iter(&Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect();
+
+    let mut user = default_user(modes);
+    user.username = format!("{}{}", SEED_USERNAME_PREFIX, suffix.to_lowercase());
+    user.password = password_hash.to_string();
+    user.completed_tests = Some(rng.gen_range(1..=500));
+    user.created_at = Some(Utc::now() - Duration::days(rng.gen_range(0..SEED_MAX_AGE_DAYS)));
+
+    // Typing speed across the player base is roughly normal around 60 wpm
+    let skill = sample_normal(rng, 60.0, 20.0).clamp(15.0, 180.0);
+    if let Some(high_scores) = user.high_scores.as_mut() {
+        for language in high_scores.languages.values_mut() {
+            for (difficulty, scores) in language.difficulties.iter_mut() {
+                for (duration, score) in scores.scores.iter_mut() {
+                    *score = create_seed_score(rng, skill, difficulty, duration);
+                }
+            }
+        }
+    }
+
+    user
+}
+
+// Inserts `count` synthetic users sharing one password and returns their
+// usernames. Seeded names start with "seed_" so they are easy to find later.
+pub async fn seed_users(
+    db: &Database,
+    count: usize,
+    password: &str,
+    modes: &ModeRegistry,
+) -> Result<Vec<String>, AppError> {
+    // One hash for the whole batch; Argon2 is too slow to run per seeded user
+    let password_hash = hash(password)?;
+    let mut usernames = Vec::with_capacity(count);
+    let mut remaining = count;
+
+    while remaining > 0 {
+        let batch_size = remaining.min(SEED_BATCH_SIZE);
+        let batch: Vec<User> = {
+            let mut rng = rand::thread_rng();
+            (0..batch_size)
+                .map(|_| create_seed_user(&mut rng, &password_hash, modes))
+                .collect()
+        };
+
+        let documents = batch
+            .iter()
+            .map(to_document)
+            .collect::<Result<Vec<Document>, _>>()?;
+
+        // Random names can collide with existing users; those are skipped
+        // and made up for by the next batch
+        let inserted = time_db_operation(
+            "admin_seed_users",
+            users(db).insert_many(documents).ordered(false),
+        )
+        .await;
+        let rejected = match inserted {
+            Ok(_) => HashSet::new(),
+            Err(err) => match get_duplicate_key_indexes(&err) {
+                Some(indexes) => indexes.into_iter().collect(),
+                None => return Err(err.into()),
+            },
+        };
+
+        let inserted: Vec<String> = batch
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !rejected.contains(index))
+            .map(|(_, user)| user.username)
+            .collect();
+        remaining -= inserted.len();
+        usernames.extend(inserted);
+    }
+
+    Ok(usernames)
+}
@@ -0,0 +1,245 @@
+use crate::errors::app_error::AppError;
+use crate::structs::captcha_response::CaptchaResponse;
+use async_trait::async_trait;
+
+const RECAPTCHA_VERIFY_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+const HCAPTCHA_VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+const TURNSTILE_VERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+// Checks a captcha token sent by the client. `action` names the form being
+// protected ("login", "sign_up") for providers that bind tokens to actions.
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    async fn verify(
+        &self,
+        token: &str,
+        action: &str,
+        remote_ip: Option<&str>,
+    ) -> Result<(), AppError>;
+}
+
+fn captcha_failed(error_codes: &[String]) -> AppError {
+    if error_codes.is_empty() {
+        AppError::CaptchaFailed(
+            "Captcha verification failed. It seems you are not a human.".to_string(),
+        )
+    } else {
+        AppError::CaptchaFailed(format!(
+            "Captcha verification failed: {}",
+            error_codes.join(", ")
+        ))
+    }
+}
+
+// All supported providers expose the same form-encoded siteverify API
+async fn post_siteverify(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    token: &str,
+    remote_ip: Option<&str>,
+) -> Result<CaptchaResponse, AppError> {
+    let mut form = vec![("secret", secret), ("response", token)];
+    if let Some(remote_ip) = remote_ip {
+        form.push(("remoteip", remote_ip));
+    }
+
+    let response = client.post(url).form(&form).send().await?;
+    Ok(response.json::<CaptchaResponse>().await?)
+}
+
+fn check_action(response: &CaptchaResponse, action: &str) -> Result<(), AppError> {
+    match &response.action {
+        Some(token_action) if token_action != action => Err(AppError::CaptchaFailed(format!(
+            "Captcha was solved for '{}' instead of '{}'.",
+            token_action, action
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub struct RecaptchaV2Verifier {
+    client: reqwest::Client,
+    secret: String,
+}
+
+impl RecaptchaV2Verifier {
+    pub fn new(client: reqwest::Client, secret: String) -> Self {
+        RecaptchaV2Verifier { client, secret }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for RecaptchaV2Verifier {
+    async fn verify(
+        &self,
+        token: &str,
+        _action: &str,
+        remote_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        let response = post_siteverify(
+            &self.client,
+            RECAPTCHA_VERIFY_URL,
+            &self.secret,
+            token,
+            remote_ip,
+        )
+        .await?;
+
+        if !response.success {
+            return Err(captcha_failed(&response.error_codes));
+        }
+        Ok(())
+    }
+}
+
+// v3 never shows a challenge; it scores the interaction between 0.0 (bot)
+// and 1.0 (human) and tags the token with the action it was created for
+pub struct RecaptchaV3Verifier {
+    client: reqwest::Client,
+    secret: String,
+    min_score: f32,
+}
+
+impl RecaptchaV3Verifier {
+    pub fn new(client: reqwest::Client, secret: String, min_score: f32) -> Self {
+        RecaptchaV3Verifier {
+            client,
+            secret,
+            min_score,
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for RecaptchaV3Verifier {
+    async fn verify(
+        &self,
+        token: &str,
+        action: &str,
+        remote_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        let response = post_siteverify(
+            &self.client,
+            RECAPTCHA_VERIFY_URL,
+            &self.secret,
+            token,
+            remote_ip,
+        )
+        .await?;
+
+        if !response.success {
+            return Err(captcha_failed(&response.error_codes));
+        }
+        if response.action.is_none() {
+            return Err(AppError::CaptchaFailed(
+                "Captcha token is missing its action.".to_string(),
+            ));
+        }
+        check_action(&response, action)?;
+
+        match response.score {
+            Some(score) if score >= self.min_score => Ok(()),
+            _ => Err(captcha_failed(&[])),
+        }
+    }
+}
+
+pub struct HCaptchaVerifier {
+    client: reqwest::Client,
+    secret: String,
+}
+
+impl HCaptchaVerifier {
+    pub fn new(client: reqwest::Client, secret: String) -> Self {
+        HCaptchaVerifier { client, secret }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for HCaptchaVerifier {
+    async fn verify(
+        &self,
+        token: &str,
+        _action: &str,
+        remote_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        let response = post_siteverify(
+            &self.client,
+            HCAPTCHA_VERIFY_URL,
+            &self.secret,
+            token,
+            remote_ip,
+        )
+        .await?;
+
+        if !response.success {
+            return Err(captcha_failed(&response.error_codes));
+        }
+        Ok(())
+    }
+}
+
+pub struct TurnstileVerifier {
+    client: reqwest::Client,
+    secret: String,
+}
+
+impl TurnstileVerifier {
+    pub fn new(client: reqwest::Client, secret: String) -> Self {
+        TurnstileVerifier { client, secret }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for TurnstileVerifier {
+    async fn verify(
+        &self,
+        token: &str,
+        action: &str,
+        remote_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        let response = post_siteverify(
+            &self.client,
+            TURNSTILE_VERIFY_URL,
+            &self.secret,
+            token,
+            remote_ip,
+        )
+        .await?;
+
+        if !response.success {
+            return Err(captcha_failed(&response.error_codes));
+        }
+        // The widget's action is optional, so only a mismatch is rejected
+        check_action(&response, action)
+    }
+}
+
+// Accepts (or rejects) every token without any network call. Meant for
+// tests and offline development only.
+pub struct FixedCaptchaVerifier {
+    pass: bool,
+}
+
+impl FixedCaptchaVerifier {
+    pub fn new(pass: bool) -> Self {
+        FixedCaptchaVerifier { pass }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for FixedCaptchaVerifier {
+    async fn verify(
+        &self,
+        _token: &str,
+        _action: &str,
+        _remote_ip: Option<&str>,
+    ) -> Result<(), AppError> {
+        if self.pass {
+            Ok(())
+        } else {
+            Err(captcha_failed(&[]))
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::config::auth::AuthConfig;
+use crate::config::mail::MailConfig;
+use crate::errors::app_error::AppError;
+use crate::models::user::User;
+use crate::repositories::email_verification_repository::EmailVerificationRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::mailer_service::{MailMessage, Mailer};
+use crate::services::user_service::{fetch_user_and_handle_response, normalize_email};
+use mongodb::bson::DateTime as BsonDateTime;
+
+// Rejects a new verification email while the last one is more recent than
+// the resend interval, so the endpoints cannot be used to flood an inbox
+fn check_resend_allowed(user: &User, resend_secs: i64) -> Result<(), AppError> {
+    let last_sent_at = match user.email_verification_sent_at {
+        Some(sent_at) => sent_at,
+        None => return Ok(()),
+    };
+
+    let elapsed_secs =
+        (BsonDateTime::now().timestamp_millis() - last_sent_at.timestamp_millis()) / 1000;
+    if elapsed_secs < resend_secs {
+        return Err(AppError::RateLimited((resend_secs - elapsed_secs) as u64));
+    }
+    Ok(())
+}
+
+fn create_verification_message(to: &str, link: &str, ttl_hours: i64) -> MailMessage {
+    MailMessage {
+        to: to.to_string(),
+        subject: "Verify your Eletypes email address".to_string(),
+        body: format!(
+            "This address was added to an Eletypes account.\n\n\
+             Open this link to confirm it is yours:\n{}\n\n\
+             The link expires in {} hours and works once. If you did not add \
+             this address, you can ignore this email.",
+            link, ttl_hours
+        ),
+    }
+}
+
+// Emails a fresh verification link for the address
+pub async fn send_verification_email(
+    users: &dyn UserRepository,
+    verifications: &dyn EmailVerificationRepository,
+    mailer: &dyn Mailer,
+    mail_config: &MailConfig,
+    auth: &AuthConfig,
+    username: &str,
+    email: &str,
+) -> Result<(), AppError> {
+    let token = verifications
+        .create(username, email, auth.email_verification_ttl_hours)
+        .await?;
+    let link = mail_config.create_link("/verify-email", &token);
+    users.mark_verification_email_sent(username).await?;
+    mailer
+        .send(&create_verification_message(
+            email,
+            &link,
+            auth.email_verification_ttl_hours,
+        ))
+        .await
+}
+
+// Sets a new address, unverified until the emailed link is followed. Asking
+// again for the current unverified address just resends the link.
+pub async fn change_email(
+    users: &dyn UserRepository,
+    verifications: &dyn EmailVerificationRepository,
+    mailer: &dyn Mailer,
+    mail_config: &MailConfig,
+    auth: &AuthConfig,
+    username: &str,
+    email: &str,
+) -> Result<(), AppError> {
+    let email = normalize_email(email)?;
+    let user = fetch_user_and_handle_response(users, username).await?;
+    if user.email.as_deref() == Some(email.as_str()) && user.email_verified {
+        return Ok(());
+    }
+
+    check_resend_allowed(&user, auth.email_verification_resend_secs)?;
+    if user.email.as_deref() != Some(email.as_str()) {
+        users.update_email(username, Some(&email)).await?;
+    }
+
+    send_verification_email(
+        users,
+        verifications,
+        mailer,
+        mail_config,
+        auth,
+        username,
+        &email,
+    )
+    .await
+}
+
+// Removes the address; pending links stop working with it. The resend
+// interval still runs, so removing and re-adding cannot skip it.
+pub async fn remove_email(
+    users: &dyn UserRepository,
+    verifications: &dyn EmailVerificationRepository,
+    username: &str,
+) -> Result<(), AppError> {
+    if !users.update_email(username, None).await? {
+        return Err(AppError::NotFound("User not found.".to_string()));
+    }
+    verifications.delete_pending(username).await
+}
+
+pub async fn resend_verification_email(
+    users: &dyn UserRepository,
+    verifications: &dyn EmailVerificationRepository,
+    mailer: &dyn Mailer,
+    mail_config: &MailConfig,
+    auth: &AuthConfig,
+    username: &str,
+) -> Result<(), AppError> {
+    let user = fetch_user_and_handle_response(users, username).await?;
+    let email = match user.email.clone() {
+        Some(email) if !user.email_verified => email,
+        Some(_) => {
+            return Err(AppError::Conflict(
+                "Email address is already verified.".to_string(),
+            ))
+        }
+        None => {
+            return Err(AppError::Validation(
+                "No email address to verify.".to_string(),
+            ))
+        }
+    };
+
+    check_resend_allowed(&user, auth.email_verification_resend_secs)?;
+    send_verification_email(
+        users,
+        verifications,
+        mailer,
+        mail_config,
+        auth,
+        username,
+        &email,
+    )
+    .await
+}
+
+// Redeems a verification token. Returns the username whose address it
+// verified.
+pub async fn verify_email(
+    users: &dyn UserRepository,
+    verifications: &dyn EmailVerificationRepository,
+    token: &str,
+) -> Result<String, AppError> {
+    let verification = match verifications.consume(token).await? {
+        Some(verification) => verification,
+        None => return Err(AppError::InvalidVerificationToken),
+    };
+
+    if !users
+        .mark_email_verified(&verification.username, &verification.email)
+        .await?
+    {
+        return Err(AppError::InvalidVerificationToken);
+    }
+    Ok(verification.username)
+}
@@ -0,0 +1,72 @@
+use crate::config::app_config::AppConfig;
+use crate::structs::health::{BuildInfo, HealthCheck, ReadinessReport};
+use actix_web::rt;
+use mongodb::bson::doc;
+use mongodb::Client;
+use std::time::{Duration, Instant};
+
+fn create_health_check(
+    name: &'static str,
+    started: Instant,
+    result: Result<(), String>,
+) -> HealthCheck {
+    if let Err(error) = &result {
+        tracing::warn!(
+            check = name,
+            %error,
+            duration_ms = started.elapsed().as_millis() as u64,
+            "Readiness check failed"
+        );
+    }
+
+    HealthCheck {
+        name,
+        healthy: result.is_ok(),
+    }
+}
+
+// The client connects lazily, so a ping is the only way to know the
+// database is actually reachable
+pub async fn check_database(client: &Client, timeout: Duration) -> HealthCheck {
+    let started = Instant::now();
+    let admin = client.database("admin");
+    let ping = admin.run_command(doc! { "ping": 1 });
+
+    let result = match rt::time::timeout(timeout, ping).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Ping failed: {}", e)),
+        Err(_) => Err(format!("No answer within {} ms", timeout.as_millis())),
+    };
+    create_health_check("mongodb", started, result)
+}
+
+pub fn check_config(config: &AppConfig) -> HealthCheck {
+    let started = Instant::now();
+    let problems = config.problems();
+
+    let result = if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    };
+    create_health_check("config", started, result)
+}
+
+pub async fn check_readiness(client: &Client, config: &AppConfig) -> ReadinessReport {
+    let timeout = Duration::from_millis(config.database.ping_timeout_ms);
+    let checks = vec![check_config(config), check_database(client, timeout).await];
+
+    ReadinessReport {
+        ready: checks.iter().all(|check| check.healthy),
+        checks,
+    }
+}
+
+// Set at compile time by build.rs
+pub fn get_build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_hash: env!("GIT_HASH"),
+        build_time: env!("BUILD_TIME"),
+    }
+}
@@ -0,0 +1,78 @@
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::user::Score;
+use crate::repositories::leaderboard_repository::LeaderboardRepository;
+use crate::structs::leaderboard_socket::LeaderboardUpdate;
+use tokio::sync::broadcast;
+
+// Updates are small and short-lived; slow subscribers simply skip ahead
+const HUB_CAPACITY: usize = 256;
+
+// In-process fan-out of leaderboard updates. A single hub is created before
+// the HTTP server starts and shared by every worker, so a personal best saved
+// on one worker reaches the sockets held by all of them.
+#[derive(Clone)]
+pub struct LeaderboardHub {
+    sender: broadcast::Sender<LeaderboardUpdate>,
+    top_n: u64,
+}
+
+impl LeaderboardHub {
+    pub fn new(top_n: u64) -> Self {
+        let (sender, _) = broadcast::channel(HUB_CAPACITY);
+        LeaderboardHub { sender, top_n }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LeaderboardUpdate> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, update: LeaderboardUpdate) {
+        // Sending only fails when nobody is listening, which is fine
+        let _ = self.sender.send(update);
+    }
+
+    pub fn top_n(&self) -> u64 {
+        self.top_n
+    }
+}
+
+async fn publish_if_in_top_n(
+    hub: &LeaderboardHub,
+    leaderboard: &dyn LeaderboardRepository,
+    mode: &GameMode,
+    username: &str,
+    score: &Score,
+) -> Result<(), AppError> {
+    let position = match leaderboard.fetch_user_position(mode, username).await? {
+        Some((_, position)) => position,
+        None => return Ok(()),
+    };
+
+    let rank = leaderboard.get_rank(mode, &position).await?;
+    if rank > hub.top_n() {
+        return Ok(());
+    }
+
+    hub.publish(LeaderboardUpdate {
+        mode: mode.clone(),
+        username: username.to_string(),
+        rank,
+        score: score.clone(),
+    });
+
+    Ok(())
+}
+
+// Called after a personal best was saved; failures only affect live updates
+pub async fn notify_personal_best(
+    hub: &LeaderboardHub,
+    leaderboard: &dyn LeaderboardRepository,
+    mode: &GameMode,
+    username: &str,
+    score: &Score,
+) {
+    if let Err(err) = publish_if_in_top_n(hub, leaderboard, mode, username, score).await {
+        tracing::error!(error = ?err, "Error publishing leaderboard update");
+    }
+}
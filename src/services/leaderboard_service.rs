@@ -1,124 +1,88 @@
-use crate::models::user::HighScores;
+use crate::errors::app_error::AppError;
 pub use crate::structs::leaderboard::{
-    GetLeaderboardStatsRequest, LeaderboardEntry, LeaderboardResponse,
+    GetLeaderboardStatsRequest, LeaderboardCursor, LeaderboardEntry, LeaderboardPage,
+    LeaderboardPaging, LeaderboardResponse, LeaderboardTimeWindow,
 };
-use actix_web::HttpResponse;
-use futures_util::TryStreamExt;
-use mongodb::bson::{doc, from_bson, Bson, Document};
-use mongodb::Collection;
-use std::collections::HashMap;
+use crate::utils::helpers::get_page_offset;
 
-pub async fn get_total_document_count(
-    collection: &Collection<Document>,
-) -> Result<i64, HttpResponse> {
-    match collection.count_documents(doc! {}).await {
-        Ok(count) => Ok(count as i64),
-        Err(_) => Err(
-            HttpResponse::InternalServerError().json(LeaderboardResponse {
-                status: "error".to_string(),
-                message: "Failed to fetch the total document count.".to_string(),
-                leaderboard: vec![],
-                total_count: 0,
-            }),
-        ),
-    }
-}
-
-pub fn extract_leaderboard_entry(
-    doc: &Document,
-) -> Result<LeaderboardEntry, mongodb::error::Error> {
-    let _id = doc
-        .get_object_id("_id")
-        .map(|id| id.to_string())
-        .unwrap_or_else(|_| "".to_string());
-    let username = doc
-        .get_str("username")
-        .map(|s| s.to_string())
-        .unwrap_or_else(|_| "".to_string());
-    let completed_tests = doc
-        .get_i32("completed_tests")
-        .map(|n| n as u32)
-        .unwrap_or_default();
+const DEFAULT_PAGE_LIMIT: u64 = 10;
+const MAX_PAGE_LIMIT: u64 = 100;
 
-    let high_scores = extract_high_scores(doc)?;
-
-    Ok(LeaderboardEntry {
-        _id,
-        username,
-        completed_tests,
-        high_scores,
-    })
-}
-
-fn extract_high_scores(doc: &Document) -> Result<HighScores, mongodb::error::Error> {
-    match doc.get("high_scores") {
-        Some(Bson::Document(doc)) => {
-            let high_scores: HighScores = from_bson(Bson::Document(doc.clone()))?;
-            Ok(high_scores)
-        }
-        _ => Ok(HighScores {
-            languages: HashMap::new(),
-        }),
+pub fn parse_leaderboard_window(window: Option<&str>) -> Result<LeaderboardTimeWindow, AppError> {
+    match window {
+        None | Some("all_time") => Ok(LeaderboardTimeWindow::AllTime),
+        Some("daily") => Ok(LeaderboardTimeWindow::Daily),
+        Some("weekly") => Ok(LeaderboardTimeWindow::Weekly),
+        Some("monthly") => Ok(LeaderboardTimeWindow::Monthly),
+        Some(_) => Err(AppError::Validation(
+            "window must be one of daily, weekly, monthly or all_time.".to_string(),
+        )),
     }
 }
 
-pub async fn fetch_filtered_users(
-    collection: &mongodb::Collection<Document>,
-    timer_duration: &str,
-    page: &str,
-    limit: &str,
-    language: &str,
-    difficulty: &str,
-) -> Result<Vec<LeaderboardEntry>, mongodb::error::Error> {
-    let pipeline = create_aggregation_pipeline(timer_duration, page, limit, language, difficulty);
+pub fn parse_leaderboard_paging(
+    page: Option<&str>,
+    limit: Option<&str>,
+    after: Option<&str>,
+    window: LeaderboardTimeWindow,
+) -> Result<LeaderboardPaging, AppError> {
+    let page_number = match page {
+        Some(page) => match page.parse::<u64>() {
+            Ok(page) if page >= 1 => page,
+            _ => {
+                return Err(AppError::Validation(
+                    "page must be a positive integer.".to_string(),
+                ))
+            }
+        },
+        None => 1,
+    };
 
-    let mut cursor = collection.aggregate(pipeline).await?;
-    let mut users = Vec::new();
+    let limit_number = match limit {
+        Some(limit) => match limit.parse::<u64>() {
+            Ok(limit) if (1..=MAX_PAGE_LIMIT).contains(&limit) => limit,
+            _ => {
+                return Err(AppError::Validation(format!(
+                    "limit must be an integer between 1 and {}.",
+                    MAX_PAGE_LIMIT
+                )))
+            }
+        },
+        None => DEFAULT_PAGE_LIMIT,
+    };
 
-    while let Some(doc) = cursor.try_next().await? {
-        match extract_leaderboard_entry(&doc) {
-            Ok(entry) => users.push(entry),
-            Err(e) => eprintln!("Error processing document: {:?}", e),
-        }
-    }
+    let after = match after {
+        Some(token) => match LeaderboardCursor::decode(token, window) {
+            Some(cursor) => Some(cursor),
+            None => {
+                return Err(AppError::Validation(
+                    "Invalid leaderboard cursor.".to_string(),
+                ))
+            }
+        },
+        None => None,
+    };
 
-    // log_leaderboard_stats(&users);
+    // A cursor already marks the position, so page-based skipping is ignored
+    let skip = if after.is_some() {
+        0
+    } else {
+        get_page_offset(page_number, limit_number)?
+    };
 
-    Ok(users)
+    Ok(LeaderboardPaging {
+        skip,
+        limit: limit_number,
+        after,
+    })
 }
 
-pub fn create_aggregation_pipeline(
-    timer_duration: &str,
-    page: &str,
-    limit: &str,
-    language: &str,
-    difficulty: &str,
-) -> Vec<Document> {
-    let page_number: usize = page.parse().unwrap_or(1);
-    let limit_number: usize = limit.parse().unwrap_or(10);
-    let skip_number = (page_number - 1) * limit_number;
+pub fn calculate_percentile(rank: u64, total_count: u64) -> f64 {
+    if total_count == 0 {
+        return 0.0;
+    }
 
-    vec![
-        doc! { "$match": {
-            format!("high_scores.languages.{}.difficulties.{}.scores.{}", language, difficulty, timer_duration): { "$exists": true }
-        }},
-        doc! { "$project": {
-            "_id": 1,
-            "username": 1,
-            "completed_tests": 1,
-            format!("high_scores.languages.{}.difficulties.{}.scores.{}", language, difficulty, timer_duration): 1
-        }},
-        doc! { "$sort": {
-            format!("high_scores.languages.{}.difficulties.{}.scores.{}.wpm", language, difficulty, timer_duration): -1
-        }},
-        doc! { "$skip": skip_number as i64 },
-        doc! { "$limit": limit_number as i64 },
-    ]
+    // Share of ranked players this user is ahead of
+    let percentile = (total_count - rank.min(total_count)) as f64 / total_count as f64 * 100.0;
+    (percentile * 100.0).round() / 100.0
 }
-
-// fn log_leaderboard_stats(users: &[LeaderboardEntry]) {
-//     println!(
-//         "Leaderboard Stats: {}",
-//         serde_json::to_string_pretty(&json!(users)).unwrap()
-//     );
-// }
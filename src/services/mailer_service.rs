@@ -0,0 +1,136 @@
+use crate::config::mail::MailConfig;
+use crate::errors::app_error::AppError;
+use actix_web::web;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::PathBuf;
+
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError>;
+}
+
+fn mail_error(err: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("Error sending mail: {}", err))
+}
+
+pub struct SmtpMailer {
+    from: String,
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+}
+
+impl SmtpMailer {
+    // STARTTLS on the configured port; credentials are optional for relays
+    pub fn new(config: &MailConfig) -> Self {
+        let transport =
+            match AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host) {
+                Ok(builder) => {
+                    let mut builder = builder.port(config.smtp_port);
+                    if !config.smtp_username.is_empty() {
+                        builder = builder.credentials(Credentials::new(
+                            config.smtp_username.clone(),
+                            config.smtp_password.clone(),
+                        ));
+                    }
+                    Some(builder.build())
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Invalid SMTP relay configuration");
+                    None
+                }
+            };
+
+        SmtpMailer {
+            from: config.from.clone(),
+            transport,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        let transport = self
+            .transport
+            .as_ref()
+            .ok_or_else(|| mail_error("SMTP is not configured"))?;
+
+        let email = Message::builder()
+            .from(self.from.parse::<Mailbox>().map_err(mail_error)?)
+            .to(message.to.parse::<Mailbox>().map_err(mail_error)?)
+            .subject(message.subject.clone())
+            .body(message.body.clone())
+            .map_err(mail_error)?;
+
+        transport.send(email).await.map_err(mail_error)?;
+        Ok(())
+    }
+}
+
+// Drops every message into a directory, one file each, for local development
+// and end-to-end tests
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(from: String, dir: String) -> Self {
+        FileMailer {
+            from,
+            dir: PathBuf::from(dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        let contents = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.from, message.to, message.subject, message.body
+        );
+        let path = self
+            .dir
+            .join(format!("{}.eml", Utc::now().format("%Y%m%dT%H%M%S%.6fZ")));
+
+        let dir = self.dir.clone();
+        let written = path.clone();
+        web::block(move || {
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(&written, contents)
+        })
+        .await
+        .map_err(mail_error)?
+        .map_err(mail_error)?;
+
+        tracing::info!(path = %path.display(), "Mail written to file");
+        Ok(())
+    }
+}
+
+// Prints messages, links included, to the log. Never use it in production:
+// anyone with log access could take over accounts.
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        tracing::info!(
+            to = %message.to,
+            subject = %message.subject,
+            body = %message.body,
+            "Mail not sent, logged instead"
+        );
+        Ok(())
+    }
+}
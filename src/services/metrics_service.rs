@@ -0,0 +1,170 @@
+use crate::errors::app_error::AppError;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::fmt;
+use std::future::IntoFuture;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tracing::Instrument;
+
+// Process-wide, like the Argon2 parameters, so services can record without
+// having the registry threaded through every call
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_operation_duration: HistogramVec,
+    pub sign_ups: IntCounter,
+    pub logins: IntCounterVec,
+    pub captcha_failures: IntCounterVec,
+    pub scores_submitted: IntCounter,
+    pub personal_bests: IntCounter,
+}
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(registry: &Registry, metric: T) -> T {
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric names are unique");
+    metric
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        Metrics {
+            http_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("http_requests_total", "HTTP requests by route and status"),
+                    &["method", "route", "status"],
+                )
+                .unwrap(),
+            ),
+            http_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "http_request_duration_seconds",
+                        "HTTP request latency by route",
+                    ),
+                    &["method", "route"],
+                )
+                .unwrap(),
+            ),
+            db_operation_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "db_operation_duration_seconds",
+                        "MongoDB operation latency",
+                    ),
+                    &["operation", "outcome"],
+                )
+                .unwrap(),
+            ),
+            sign_ups: register(
+                &registry,
+                IntCounter::new("sign_ups_total", "Accounts registered").unwrap(),
+            ),
+            logins: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new("logins_total", "Login attempts by outcome"),
+                    &["outcome"],
+                )
+                .unwrap(),
+            ),
+            captcha_failures: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "captcha_failures_total",
+                        "Rejected captcha tokens by action",
+                    ),
+                    &["action"],
+                )
+                .unwrap(),
+            ),
+            scores_submitted: register(
+                &registry,
+                IntCounter::new("scores_submitted_total", "Scores submitted").unwrap(),
+            ),
+            personal_bests: register(
+                &registry,
+                IntCounter::new(
+                    "personal_bests_total",
+                    "Submissions that set a personal best",
+                )
+                .unwrap(),
+            ),
+            registry,
+        }
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> Result<String, AppError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| AppError::Internal(format!("Error encoding metrics: {}", e)))?;
+
+        String::from_utf8(buffer)
+            .map_err(|e| AppError::Internal(format!("Error encoding metrics: {}", e)))
+    }
+}
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+// Awaits a MongoDB operation inside its own span and records how long it took
+pub async fn time_db_operation<F, T, E>(operation: &str, future: F) -> Result<T, E>
+where
+    F: IntoFuture<Output = Result<T, E>>,
+    E: fmt::Display,
+{
+    let started = Instant::now();
+    let result = future
+        .into_future()
+        .instrument(tracing::debug_span!("db", operation))
+        .await;
+
+    let outcome = match &result {
+        Ok(_) => "ok",
+        Err(e) => {
+            tracing::warn!(operation, error = %e, "Database operation failed");
+            "error"
+        }
+    };
+    metrics()
+        .db_operation_duration
+        .with_label_values(&[operation, outcome])
+        .observe(started.elapsed().as_secs_f64());
+
+    result
+}
+
+// Only an actual rejection counts; provider outages surface as other errors
+pub fn record_captcha_result(action: &str, result: &Result<(), AppError>) {
+    if let Err(AppError::CaptchaFailed(_)) = result {
+        metrics()
+            .captcha_failures
+            .with_label_values(&[action])
+            .inc();
+    }
+}
+
+pub fn record_login_result(result: &Result<bool, AppError>) {
+    let outcome = match result {
+        Ok(true) => "succeeded",
+        Ok(false) => "failed",
+        Err(AppError::Forbidden(_)) => "banned",
+        // Not a verdict on the credentials
+        Err(_) => return,
+    };
+    metrics().logins.with_label_values(&[outcome]).inc();
+}
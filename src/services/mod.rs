@@ -1,2 +1,19 @@
+pub mod admin_service;
+pub mod captcha_service;
+pub mod email_verification_service;
+pub mod health_service;
+pub mod leaderboard_hub;
 pub mod leaderboard_service;
+pub mod mailer_service;
+pub mod metrics_service;
+pub mod mode_service;
+pub mod password_reset_service;
+pub mod password_service;
+pub mod race_result_service;
+pub mod race_rooms;
+pub mod rate_limit_service;
+pub mod session_service;
+pub mod test_result_service;
+pub mod typing_test_service;
 pub mod user_service;
+pub mod windowed_leaderboard_service;
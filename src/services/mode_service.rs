@@ -0,0 +1,12 @@
+use crate::config::modes::ModeRegistry;
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+
+pub fn resolve_mode(
+    modes: &ModeRegistry,
+    language: &str,
+    difficulty: &str,
+    duration: &str,
+) -> Result<GameMode, AppError> {
+    Ok(modes.resolve(language, difficulty, duration)?)
+}
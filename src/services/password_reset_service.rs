@@ -0,0 +1,102 @@
+use crate::config::auth::AuthConfig;
+use crate::config::mail::MailConfig;
+use crate::errors::app_error::AppError;
+use crate::repositories::password_reset_repository::PasswordResetRepository;
+use crate::repositories::session_repository::SessionRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::mailer_service::{MailMessage, Mailer};
+use crate::services::user_service::set_user_password;
+use mongodb::bson::DateTime as BsonDateTime;
+
+// A reset sent within the resend interval is enough; another one would only
+// flood the inbox
+async fn was_reset_sent_recently(
+    resets: &dyn PasswordResetRepository,
+    username: &str,
+    resend_secs: i64,
+) -> Result<bool, AppError> {
+    let last_sent_at = match resets.last_created_at(username).await? {
+        Some(created_at) => created_at,
+        None => return Ok(false),
+    };
+    let elapsed_secs =
+        (BsonDateTime::now().timestamp_millis() - last_sent_at.timestamp_millis()) / 1000;
+    Ok(elapsed_secs < resend_secs)
+}
+
+fn create_reset_message(to: String, link: &str, ttl_minutes: i64) -> MailMessage {
+    MailMessage {
+        to,
+        subject: "Reset your Eletypes password".to_string(),
+        body: format!(
+            "Someone asked to reset the password of your Eletypes account.\n\n\
+             Open this link to choose a new password:\n{}\n\n\
+             The link expires in {} minutes and works once. If you did not ask \
+             for a reset, you can ignore this email.",
+            link, ttl_minutes
+        ),
+    }
+}
+
+// Emails a reset link when the account exists and has a verified address.
+// Callers answer the same way either way so accounts cannot be enumerated.
+pub async fn request_password_reset(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    mailer: &dyn Mailer,
+    mail_config: &MailConfig,
+    auth: &AuthConfig,
+    username: &str,
+) -> Result<(), AppError> {
+    let user = match users.find_by_username(username).await? {
+        Some(user) if !user.banned => user,
+        _ => return Ok(()),
+    };
+    let email = match user.email {
+        Some(email) if user.email_verified => email,
+        _ => {
+            tracing::info!(
+                username,
+                "Password reset requested without a verified email on file"
+            );
+            return Ok(());
+        }
+    };
+
+    if was_reset_sent_recently(resets, username, auth.password_reset_resend_secs).await? {
+        tracing::info!(username, "Password reset requested again too soon");
+        return Ok(());
+    }
+
+    let ttl_minutes = auth.password_reset_ttl_minutes;
+    let token = resets.create(username, ttl_minutes).await?;
+    let link = mail_config.create_link("/reset-password", &token);
+    mailer
+        .send(&create_reset_message(email, &link, ttl_minutes))
+        .await
+}
+
+// Redeems a reset token, sets the new password and signs the user out
+// everywhere, since whoever held the old password may still be logged in.
+// The token is only used up once the new password is stored, so a failed
+// write leaves the link working.
+pub async fn reset_password_with_token(
+    users: &dyn UserRepository,
+    resets: &dyn PasswordResetRepository,
+    sessions: &dyn SessionRepository,
+    token: &str,
+    password: &str,
+) -> Result<(), AppError> {
+    let username = match resets.find_valid(token).await? {
+        Some(username) => username,
+        None => return Err(AppError::InvalidResetToken),
+    };
+
+    set_user_password(users, &username, password).await?;
+    if !resets.consume(token).await? {
+        // A concurrent confirmation with the same link got there first
+        tracing::warn!(username, "Password reset token was used concurrently");
+    }
+    sessions.revoke_all(&username, "password_reset").await?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use crate::config::password::{get_argon2, get_argon2_params};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{Error as PasswordHashError, SaltString};
+use argon2::{Algorithm, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordCheck {
+    // The password does not match the stored value
+    Invalid,
+    // The password matches and the stored hash is up to date
+    Valid,
+    // The password matches but the stored value is plaintext or a weaker hash
+    ValidNeedsRehash,
+}
+
+pub fn hash_password(password: &str) -> Result<String, PasswordHashError> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    get_argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+}
+
+pub fn verify_password(password: &str, stored: &str) -> PasswordCheck {
+    match PasswordHash::new(stored) {
+        Ok(parsed_hash) => verify_password_hash(password, &parsed_hash),
+        // Anything that is not a PHC string is a legacy plaintext record
+        Err(_) => verify_plaintext(password, stored),
+    }
+}
+
+fn verify_password_hash(password: &str, parsed_hash: &PasswordHash) -> PasswordCheck {
+    // Only the Argon2 family is supported; the hash itself carries its parameters
+    let algorithm = match Algorithm::try_from(parsed_hash.algorithm) {
+        Ok(algorithm) => algorithm,
+        Err(_) => return PasswordCheck::Invalid,
+    };
+
+    if get_argon2()
+        .verify_password(password.as_bytes(), parsed_hash)
+        .is_err()
+    {
+        return PasswordCheck::Invalid;
+    }
+
+    if is_hash_outdated(algorithm, parsed_hash) {
+        PasswordCheck::ValidNeedsRehash
+    } else {
+        PasswordCheck::Valid
+    }
+}
+
+fn is_hash_outdated(algorithm: Algorithm, parsed_hash: &PasswordHash) -> bool {
+    if algorithm != Algorithm::Argon2id {
+        return true;
+    }
+
+    if parsed_hash.version != Some(Version::V0x13.into()) {
+        return true;
+    }
+
+    let current = get_argon2_params();
+    match Params::try_from(parsed_hash) {
+        Ok(params) => {
+            params.m_cost() < current.m_cost()
+                || params.t_cost() < current.t_cost()
+                || params.p_cost() < current.p_cost()
+        }
+        Err(_) => true,
+    }
+}
+
+fn verify_plaintext(password: &str, stored: &str) -> PasswordCheck {
+    if constant_time_eq(password.as_bytes(), stored.as_bytes()) {
+        PasswordCheck::ValidNeedsRehash
+    } else {
+        PasswordCheck::Invalid
+    }
+}
+
+// Compare without short-circuiting so timing does not leak the matching prefix
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
@@ -0,0 +1,82 @@
+use crate::errors::app_error::AppError;
+use crate::models::race_result::RaceResultRecord;
+use crate::services::metrics_service::time_db_operation;
+use crate::structs::race::{GetRaceResultsQueries, RaceResultEntry, RaceResultsPage};
+use crate::utils::helpers::{get_page_offset, to_chrono_datetime};
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, from_document, to_document, Document};
+use mongodb::Collection;
+
+const DEFAULT_RACES_LIMIT: u64 = 20;
+const MAX_RACES_LIMIT: u64 = 100;
+
+pub async fn insert_race_result(
+    collection: &Collection<Document>,
+    result: &RaceResultRecord,
+) -> Result<(), AppError> {
+    time_db_operation(
+        "insert_race_result",
+        collection.insert_one(to_document(result)?),
+    )
+    .await?;
+    Ok(())
+}
+
+fn to_race_result_entry(record: RaceResultRecord) -> RaceResultEntry {
+    RaceResultEntry {
+        _id: record.id.map(|id| id.to_hex()).unwrap_or_default(),
+        room_code: record.room_code,
+        language: record.language,
+        difficulty: record.difficulty,
+        text_length: record.text_length,
+        started_at: to_chrono_datetime(record.started_at),
+        finished_at: to_chrono_datetime(record.finished_at),
+        placements: record.placements,
+    }
+}
+
+pub async fn fetch_race_results(
+    collection: &Collection<Document>,
+    username: &str,
+    queries: &GetRaceResultsQueries,
+) -> Result<RaceResultsPage, AppError> {
+    let page = queries.page.unwrap_or(1).max(1);
+    let limit = queries
+        .limit
+        .unwrap_or(DEFAULT_RACES_LIMIT)
+        .clamp(1, MAX_RACES_LIMIT);
+    let offset = get_page_offset(page, limit)?;
+    let filter = doc! { "placements.username": username };
+
+    let total_count = time_db_operation(
+        "count_race_results",
+        collection.count_documents(filter.clone()),
+    )
+    .await?;
+
+    let races = time_db_operation("find_race_results", async {
+        let mut cursor = collection
+            .find(filter)
+            .sort(doc! { "started_at": -1, "_id": -1 })
+            .skip(offset)
+            .limit(limit as i64)
+            .await?;
+
+        let mut races = Vec::new();
+        while let Some(doc) = cursor.try_next().await? {
+            match from_document::<RaceResultRecord>(doc) {
+                Ok(record) => races.push(to_race_result_entry(record)),
+                Err(e) => tracing::warn!(error = ?e, "Error processing document"),
+            }
+        }
+        Ok::<_, mongodb::error::Error>(races)
+    })
+    .await?;
+
+    Ok(RaceResultsPage {
+        races,
+        total_count,
+        page,
+        limit,
+    })
+}
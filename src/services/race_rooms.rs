@@ -0,0 +1,462 @@
+use crate::models::race_result::{RacePlacement, RaceResultRecord};
+use crate::services::race_result_service::insert_race_result;
+use crate::structs::race::{RacePlayerState, RaceServerMessage};
+use crate::utils::helpers::to_bson_datetime;
+use crate::utils::word_generator::generate_words;
+use actix_web::rt;
+use chrono::{DateTime, Utc};
+use mongodb::bson::Document;
+use mongodb::Collection;
+use rand::Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+const ROOM_CODE_LENGTH: usize = 6;
+// No 0/O or 1/I so codes can be read out loud
+const ROOM_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const MAX_PLAYERS_PER_ROOM: usize = 8;
+const DEFAULT_RACE_WORD_COUNT: usize = 30;
+const MIN_RACE_WORD_COUNT: usize = 10;
+const MAX_RACE_WORD_COUNT: usize = 100;
+const COUNTDOWN_SECONDS: u64 = 3;
+// Players still typing when the limit is hit are placed by progress
+const RACE_TIME_LIMIT_SECONDS: u64 = 300;
+// Same ceiling as the single player verification
+const MAX_RACE_WPM: f64 = 300.0;
+
+pub type RaceSender = UnboundedSender<RaceServerMessage>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum RoomStatus {
+    Waiting,
+    Countdown,
+    Running { started_at: DateTime<Utc> },
+    Finished,
+}
+
+impl RoomStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RoomStatus::Waiting => "waiting",
+            RoomStatus::Countdown => "countdown",
+            RoomStatus::Running { .. } => "running",
+            RoomStatus::Finished => "finished",
+        }
+    }
+}
+
+struct RacePlayer {
+    sender: RaceSender,
+    connected: bool,
+    progress: usize,
+    wpm: u32,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+impl RacePlayer {
+    fn new(sender: RaceSender) -> Self {
+        RacePlayer {
+            sender,
+            connected: true,
+            progress: 0,
+            wpm: 0,
+            finished_at: None,
+        }
+    }
+}
+
+struct Room {
+    host: String,
+    language: String,
+    difficulty: String,
+    words: Vec<String>,
+    text_length: usize,
+    status: RoomStatus,
+    // Ordered by username so room snapshots are stable
+    players: BTreeMap<String, RacePlayer>,
+    finish_order: Vec<String>,
+}
+
+impl Room {
+    fn broadcast(&self, message: &RaceServerMessage) {
+        for player in self.players.values().filter(|player| player.connected) {
+            // The receiving session may already be gone; it cleans up on its own
+            let _ = player.sender.send(message.clone());
+        }
+    }
+
+    fn snapshot(&self, code: &str) -> RaceServerMessage {
+        RaceServerMessage::RoomState {
+            code: code.to_string(),
+            host: self.host.clone(),
+            state: self.status.as_str().to_string(),
+            language: self.language.clone(),
+            difficulty: self.difficulty.clone(),
+            players: self
+                .players
+                .iter()
+                .map(|(username, player)| RacePlayerState {
+                    username: username.clone(),
+                    progress: player.progress,
+                    wpm: player.wpm,
+                    finished: player.finished_at.is_some(),
+                })
+                .collect(),
+        }
+    }
+
+    fn all_connected_finished(&self) -> bool {
+        self.players
+            .values()
+            .filter(|player| player.connected)
+            .all(|player| player.finished_at.is_some())
+    }
+
+    // Finishers in the order they crossed the line, then everyone else by
+    // progress (ties by username for a deterministic table)
+    fn placements(&self) -> Vec<RacePlacement> {
+        let mut unfinished: Vec<(&String, &RacePlayer)> = self
+            .players
+            .iter()
+            .filter(|(_, player)| player.finished_at.is_none())
+            .collect();
+        unfinished.sort_by(|a, b| b.1.progress.cmp(&a.1.progress).then(a.0.cmp(b.0)));
+
+        let finished = self
+            .finish_order
+            .iter()
+            .filter_map(|username| self.players.get_key_value(username));
+
+        finished
+            .chain(unfinished)
+            .enumerate()
+            .map(|(index, (username, player))| RacePlacement {
+                place: index as u32 + 1,
+                username: username.clone(),
+                wpm: player.wpm,
+                progress: player.progress as u32,
+                finished: player.finished_at.is_some(),
+            })
+            .collect()
+    }
+}
+
+fn calculate_race_wpm(progress: usize, started_at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    // Floor the elapsed time at one second so the first keystrokes don't
+    // produce absurd speeds
+    let elapsed_ms = (now - started_at).num_milliseconds().max(1000) as f64;
+    (progress as f64 / 5.0) / (elapsed_ms / 60_000.0)
+}
+
+fn generate_room_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..ROOM_CODE_LENGTH)
+        .map(|_| ROOM_CODE_ALPHABET[rng.gen_range(0..ROOM_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+// Live race rooms. Like the leaderboard hub, a single instance is created
+// before the server starts so players connected to different workers can
+// share a room; each player is reached through their session's channel.
+pub struct RaceRooms {
+    rooms: Mutex<HashMap<String, Room>>,
+    results: Collection<Document>,
+}
+
+impl RaceRooms {
+    pub fn new(results: Collection<Document>) -> Self {
+        RaceRooms {
+            rooms: Mutex::new(HashMap::new()),
+            results,
+        }
+    }
+
+    fn lock_rooms(&self) -> MutexGuard<'_, HashMap<String, Room>> {
+        // A panic while holding the lock leaves the map itself usable
+        self.rooms.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn create_room(
+        &self,
+        username: &str,
+        sender: RaceSender,
+        language: &str,
+        difficulty: &str,
+        word_count: Option<usize>,
+    ) -> Result<String, String> {
+        let word_count = word_count
+            .unwrap_or(DEFAULT_RACE_WORD_COUNT)
+            .clamp(MIN_RACE_WORD_COUNT, MAX_RACE_WORD_COUNT);
+        let seed: u64 = rand::random();
+        let words = generate_words(language, difficulty, seed, word_count).ok_or_else(|| {
+            format!(
+                "No word list is available for {} ({}).",
+                language, difficulty
+            )
+        })?;
+        let text_length = words.join(" ").chars().count();
+
+        let mut rooms = self.lock_rooms();
+        let code = loop {
+            let code = generate_room_code();
+            if !rooms.contains_key(&code) {
+                break code;
+            }
+        };
+
+        let mut players = BTreeMap::new();
+        players.insert(username.to_string(), RacePlayer::new(sender));
+
+        let room = Room {
+            host: username.to_string(),
+            language: language.to_string(),
+            difficulty: difficulty.to_string(),
+            words,
+            text_length,
+            status: RoomStatus::Waiting,
+            players,
+            finish_order: Vec::new(),
+        };
+        room.broadcast(&room.snapshot(&code));
+        rooms.insert(code.clone(), room);
+
+        Ok(code)
+    }
+
+    pub fn join_room(&self, code: &str, username: &str, sender: RaceSender) -> Result<(), String> {
+        let mut rooms = self.lock_rooms();
+        let room = rooms
+            .get_mut(code)
+            .ok_or_else(|| "Room not found.".to_string())?;
+
+        if room.status != RoomStatus::Waiting {
+            return Err("The race has already started.".to_string());
+        }
+        if room.players.contains_key(username) {
+            return Err("You are already in this room.".to_string());
+        }
+        if room.players.len() >= MAX_PLAYERS_PER_ROOM {
+            return Err("Room is full.".to_string());
+        }
+
+        room.players
+            .insert(username.to_string(), RacePlayer::new(sender));
+        room.broadcast(&room.snapshot(code));
+
+        Ok(())
+    }
+
+    pub fn leave_room(&self, code: &str, username: &str) {
+        let mut rooms = self.lock_rooms();
+        let room = match rooms.get_mut(code) {
+            Some(room) => room,
+            None => return,
+        };
+
+        match room.status {
+            // Once the race is underway the player keeps their place in the
+            // results with whatever progress they made
+            RoomStatus::Countdown | RoomStatus::Running { .. } => {
+                if let Some(player) = room.players.get_mut(username) {
+                    player.connected = false;
+                }
+            }
+            RoomStatus::Waiting | RoomStatus::Finished => {
+                room.players.remove(username);
+            }
+        }
+
+        if room.players.values().all(|player| !player.connected) {
+            if let RoomStatus::Running { started_at } = room.status {
+                self.finish_race(code, room, started_at);
+            }
+            rooms.remove(code);
+            return;
+        }
+
+        if room.host == username {
+            if let Some((next_host, _)) = room.players.iter().find(|(_, player)| player.connected) {
+                room.host = next_host.clone();
+            }
+        }
+
+        if let RoomStatus::Running { started_at } = room.status {
+            if room.all_connected_finished() {
+                self.finish_race(code, room, started_at);
+            }
+        }
+        room.broadcast(&room.snapshot(code));
+    }
+
+    pub fn start_race(self: &Arc<Self>, code: &str, username: &str) -> Result<(), String> {
+        let mut rooms = self.lock_rooms();
+        let room = rooms
+            .get_mut(code)
+            .ok_or_else(|| "Room not found.".to_string())?;
+
+        if room.host != username {
+            return Err("Only the host can start the race.".to_string());
+        }
+        if room.status != RoomStatus::Waiting {
+            return Err("The race has already started.".to_string());
+        }
+
+        room.status = RoomStatus::Countdown;
+        room.broadcast(&room.snapshot(code));
+
+        rt::spawn(run_countdown(Arc::clone(self), code.to_string()));
+
+        Ok(())
+    }
+
+    pub fn update_progress(
+        &self,
+        code: &str,
+        username: &str,
+        position: usize,
+    ) -> Result<(), String> {
+        let mut rooms = self.lock_rooms();
+        let room = rooms
+            .get_mut(code)
+            .ok_or_else(|| "Room not found.".to_string())?;
+
+        let started_at = match room.status {
+            RoomStatus::Running { started_at } => started_at,
+            _ => return Err("The race is not running.".to_string()),
+        };
+        let text_length = room.text_length;
+        let player = room
+            .players
+            .get_mut(username)
+            .ok_or_else(|| "You are not in this room.".to_string())?;
+
+        if player.finished_at.is_some() {
+            return Err("You have already finished.".to_string());
+        }
+        if position > text_length {
+            return Err("Progress is past the end of the text.".to_string());
+        }
+        // Progress only moves forward; stale updates are dropped
+        if position <= player.progress {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let wpm = calculate_race_wpm(position, started_at, now);
+        if wpm > MAX_RACE_WPM {
+            return Err("Progress is faster than allowed.".to_string());
+        }
+
+        player.progress = position;
+        player.wpm = wpm.round() as u32;
+        let wpm = player.wpm;
+
+        room.broadcast(&RaceServerMessage::Progress {
+            username: username.to_string(),
+            progress: position,
+            wpm,
+        });
+
+        if position == text_length {
+            if let Some(player) = room.players.get_mut(username) {
+                player.finished_at = Some(now);
+            }
+            room.finish_order.push(username.to_string());
+            room.broadcast(&RaceServerMessage::PlayerFinished {
+                username: username.to_string(),
+                place: room.finish_order.len() as u32,
+                wpm,
+            });
+
+            if room.all_connected_finished() {
+                self.finish_race(code, room, started_at);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn begin_race(self: &Arc<Self>, code: &str) {
+        let mut rooms = self.lock_rooms();
+        let room = match rooms.get_mut(code) {
+            Some(room) if room.status == RoomStatus::Countdown => room,
+            _ => return,
+        };
+
+        let started_at = Utc::now();
+        room.status = RoomStatus::Running { started_at };
+        room.broadcast(&RaceServerMessage::RaceStarted {
+            words: room.words.clone(),
+            started_at,
+        });
+        room.broadcast(&room.snapshot(code));
+
+        rt::spawn(run_time_limit(
+            Arc::clone(self),
+            code.to_string(),
+            started_at,
+        ));
+    }
+
+    fn expire_race(&self, code: &str, started_at: DateTime<Utc>) {
+        let mut rooms = self.lock_rooms();
+        if let Some(room) = rooms.get_mut(code) {
+            // The room may have finished (or been recreated) in the meantime
+            if room.status == (RoomStatus::Running { started_at }) {
+                self.finish_race(code, room, started_at);
+            }
+        }
+    }
+
+    fn finish_race(&self, code: &str, room: &mut Room, started_at: DateTime<Utc>) {
+        room.status = RoomStatus::Finished;
+        let placements = room.placements();
+
+        room.broadcast(&RaceServerMessage::RaceFinished {
+            placements: placements.clone(),
+        });
+        room.broadcast(&room.snapshot(code));
+
+        let record = RaceResultRecord {
+            id: None,
+            room_code: code.to_string(),
+            language: room.language.clone(),
+            difficulty: room.difficulty.clone(),
+            text_length: room.text_length as u32,
+            started_at: to_bson_datetime(started_at),
+            finished_at: to_bson_datetime(Utc::now()),
+            placements,
+        };
+        let collection = self.results.clone();
+
+        rt::spawn(async move {
+            if let Err(err) = insert_race_result(&collection, &record).await {
+                tracing::error!(error = %err, "Error saving race result");
+            }
+        });
+    }
+}
+
+async fn run_countdown(rooms: Arc<RaceRooms>, code: String) {
+    for seconds in (1..=COUNTDOWN_SECONDS).rev() {
+        {
+            let rooms_guard = rooms.lock_rooms();
+            match rooms_guard.get(&code) {
+                Some(room) if room.status == RoomStatus::Countdown => {
+                    room.broadcast(&RaceServerMessage::Countdown { seconds })
+                }
+                _ => return,
+            }
+        }
+        rt::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    rooms.begin_race(&code);
+}
+
+async fn run_time_limit(rooms: Arc<RaceRooms>, code: String, started_at: DateTime<Utc>) {
+    rt::time::sleep(Duration::from_secs(RACE_TIME_LIMIT_SECONDS)).await;
+    rooms.expire_race(&code, started_at);
+}
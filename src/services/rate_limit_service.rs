@@ -0,0 +1,487 @@
+use crate::config::rate_limit::{LockoutConfig, RateLimitPolicy};
+use crate::errors::app_error::AppError;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+use std::net::IpAddr;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// Upper bound on the keys each limiter remembers
+const MAX_TRACKED_KEYS: usize = 10_000;
+// How many entries that are still needed may outlive their generation
+const MAX_KEPT_KEYS: usize = MAX_TRACKED_KEYS / 4;
+
+// Holds about `MAX_TRACKED_KEYS` entries in two generations. Every access
+// moves an entry into the current one; when that fills up, the previous
+// generation, i.e. whatever went untouched for a whole generation, is
+// dropped, except for up to `MAX_KEPT_KEYS` entries the caller still needs.
+// Each access costs amortized O(1) and recently used keys always survive.
+struct BoundedMap<K, V> {
+    current: HashMap<K, V>,
+    previous: HashMap<K, V>,
+}
+
+impl<K: Eq + Hash, V> BoundedMap<K, V> {
+    fn new() -> Self {
+        BoundedMap {
+            current: HashMap::new(),
+            previous: HashMap::new(),
+        }
+    }
+
+    fn get<Q: Eq + Hash + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.current.get(key).or_else(|| self.previous.get(key))
+    }
+
+    fn get_or_insert_with(
+        &mut self,
+        key: K,
+        default: impl FnOnce() -> V,
+        keep: impl Fn(&V) -> bool,
+    ) -> &mut V {
+        let value = self
+            .current
+            .remove(&key)
+            .or_else(|| self.previous.remove(&key))
+            .unwrap_or_else(default);
+        if self.current.len() >= MAX_TRACKED_KEYS / 2 {
+            let kept: HashMap<K, V> = mem::take(&mut self.previous)
+                .into_iter()
+                .filter(|(_, value)| keep(value))
+                .take(MAX_KEPT_KEYS)
+                .collect();
+            self.previous = mem::replace(&mut self.current, kept);
+        }
+        self.current.entry(key).or_insert(value)
+    }
+
+    fn remove<Q: Eq + Hash + ?Sized>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+    {
+        self.current.remove(key);
+        self.previous.remove(key);
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, policy: &RateLimitPolicy, now: Instant) {
+        let rate = policy.per_minute as f64 / 60.0;
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(policy.burst as f64);
+        self.updated_at = now;
+    }
+}
+
+fn seconds_until(deadline: Instant, now: Instant) -> u64 {
+    // Round up so that retrying after the advertised delay succeeds
+    (deadline.saturating_duration_since(now).as_secs_f64().ceil() as u64).max(1)
+}
+
+// Buckets live in memory, so with several instances every instance enforces
+// its own limits
+pub struct RateLimiter {
+    policies: Vec<RateLimitPolicy>,
+    buckets: Mutex<BoundedMap<(usize, String), TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(policies: Vec<RateLimitPolicy>) -> Self {
+        RateLimiter {
+            policies,
+            buckets: Mutex::new(BoundedMap::new()),
+        }
+    }
+
+    // Indexes of the policies that apply to a request
+    pub fn matching_policies<'a>(
+        &'a self,
+        method: &'a str,
+        route: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a RateLimitPolicy)> + 'a {
+        self.policies.iter().enumerate().filter(move |(_, policy)| {
+            policy.route == route
+                && policy
+                    .method
+                    .as_deref()
+                    .is_none_or(|allowed| allowed.eq_ignore_ascii_case(method))
+        })
+    }
+
+    // Takes a token from the bucket of `key` under the given policy
+    pub fn acquire(&self, policy_index: usize, key: &str) -> Result<(), AppError> {
+        self.acquire_at(policy_index, key, Instant::now())
+    }
+
+    // The clock is passed in so tests can move it
+    fn acquire_at(&self, policy_index: usize, key: &str, now: Instant) -> Result<(), AppError> {
+        let policy = &self.policies[policy_index];
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // A forgotten bucket only starts over full
+        let bucket = buckets.get_or_insert_with(
+            (policy_index, key.to_string()),
+            || TokenBucket {
+                tokens: policy.burst as f64,
+                updated_at: now,
+            },
+            |_| false,
+        );
+        bucket.refill(policy, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        let rate = policy.per_minute as f64 / 60.0;
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / rate);
+        Err(AppError::RateLimited(seconds_until(now + wait, now)))
+    }
+}
+
+struct FailedLogins {
+    failures: u32,
+    lockouts: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl FailedLogins {
+    fn new(now: Instant) -> Self {
+        FailedLogins {
+            failures: 0,
+            lockouts: 0,
+            last_failure: now,
+            locked_until: None,
+        }
+    }
+
+    fn is_locked(&self, now: Instant) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+// Failures are counted per username and client address, so guessing from
+// one address cannot lock the owner out from another. A second, higher
+// limit per username catches guessing spread over many addresses.
+type LockoutKey = (String, Option<IpAddr>);
+
+// Locks a username out after repeated failed logins. Each lockout doubles in
+// length up to the configured maximum; a successful login starts over.
+pub struct LoginLockout {
+    config: LockoutConfig,
+    entries: Mutex<BoundedMap<LockoutKey, FailedLogins>>,
+    accounts: Mutex<BoundedMap<String, FailedLogins>>,
+}
+
+impl LoginLockout {
+    pub fn new(config: LockoutConfig) -> Self {
+        LoginLockout {
+            config,
+            entries: Mutex::new(BoundedMap::new()),
+            accounts: Mutex::new(BoundedMap::new()),
+        }
+    }
+
+    // The window runs from the end of the last lockout, so a lockout longer
+    // than the window still doubles the next one
+    fn is_expired(&self, entry: &FailedLogins, now: Instant) -> bool {
+        let window = Duration::from_secs(self.config.failure_window_secs);
+        let since = entry
+            .locked_until
+            .map_or(entry.last_failure, |until| until.max(entry.last_failure));
+        now.saturating_duration_since(since) > window
+    }
+
+    // Counts a failure and returns the lockout length once `max_failures`
+    // is reached
+    fn count_failure(
+        &self,
+        entry: &mut FailedLogins,
+        max_failures: u32,
+        now: Instant,
+    ) -> Option<u64> {
+        if self.is_expired(entry, now) {
+            entry.failures = 0;
+            entry.lockouts = 0;
+        }
+
+        entry.failures += 1;
+        entry.last_failure = now;
+        if entry.failures < max_failures {
+            return None;
+        }
+
+        let factor = 2u64.saturating_pow(entry.lockouts);
+        let seconds = self
+            .config
+            .base_lockout_secs
+            .saturating_mul(factor)
+            .min(self.config.max_lockout_secs);
+
+        entry.failures = 0;
+        entry.lockouts += 1;
+        entry.locked_until = Some(now + Duration::from_secs(seconds));
+        Some(seconds)
+    }
+
+    pub fn check(&self, username: &str, client_ip: Option<IpAddr>) -> Result<(), AppError> {
+        self.check_at(username, client_ip, Instant::now())
+    }
+
+    pub fn record_failure(&self, username: &str, client_ip: Option<IpAddr>) {
+        self.record_failure_at(username, client_ip, Instant::now())
+    }
+
+    // The clock is passed in so tests can move it
+    fn check_at(
+        &self,
+        username: &str,
+        client_ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let address_until = self
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(username.to_string(), client_ip))
+            .and_then(|entry| entry.locked_until);
+        let account_until = self
+            .accounts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(username)
+            .and_then(|entry| entry.locked_until);
+
+        match address_until.max(account_until) {
+            Some(until) if until > now => Err(AppError::AccountLocked(seconds_until(until, now))),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_failure_at(&self, username: &str, client_ip: Option<IpAddr>, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+
+        // Live lockouts survive eviction; they are few, unlike failures
+        let keep = |entry: &FailedLogins| entry.is_locked(now);
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let entry = entries.get_or_insert_with(
+            (username.to_string(), client_ip),
+            || FailedLogins::new(now),
+            keep,
+        );
+        if let Some(seconds) = self.count_failure(entry, self.config.max_failures, now) {
+            tracing::warn!(
+                username,
+                client_ip = ?client_ip,
+                lockout_secs = seconds,
+                "Locking account after repeated failed logins"
+            );
+        }
+        drop(entries);
+
+        let mut accounts = self.accounts.lock().unwrap_or_else(PoisonError::into_inner);
+        let account =
+            accounts.get_or_insert_with(username.to_string(), || FailedLogins::new(now), keep);
+        if let Some(seconds) = self.count_failure(account, self.config.max_account_failures, now) {
+            tracing::warn!(
+                username,
+                lockout_secs = seconds,
+                "Locking account everywhere after repeated failed logins from several addresses"
+            );
+        }
+    }
+
+    pub fn record_success(&self, username: &str, client_ip: Option<IpAddr>) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&(username.to_string(), client_ip));
+        self.accounts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(username);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::rate_limit::RateLimitKey;
+    use std::net::Ipv4Addr;
+
+    const FIRST_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+    const SECOND_IP: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+
+    fn create_limiter(burst: u32, per_minute: u32) -> RateLimiter {
+        RateLimiter::new(vec![RateLimitPolicy {
+            route: "/login".to_string(),
+            method: None,
+            key: RateLimitKey::Ip,
+            burst,
+            per_minute,
+        }])
+    }
+
+    fn create_lockout() -> LoginLockout {
+        LoginLockout::new(LockoutConfig {
+            enabled: true,
+            max_failures: 3,
+            max_account_failures: 5,
+            base_lockout_secs: 60,
+            max_lockout_secs: 600,
+            failure_window_secs: 300,
+        })
+    }
+
+    fn fail(lockout: &LoginLockout, client_ip: Option<IpAddr>, times: u32, now: Instant) {
+        for _ in 0..times {
+            lockout.record_failure_at("alice", client_ip, now);
+        }
+    }
+
+    fn locked_for(result: Result<(), AppError>) -> Option<u64> {
+        match result {
+            Ok(()) => None,
+            Err(AppError::AccountLocked(seconds)) => Some(seconds),
+            Err(other) => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills_over_time() {
+        let limiter = create_limiter(2, 30);
+        let start = Instant::now();
+
+        assert!(limiter.acquire_at(0, "a", start).is_ok());
+        assert!(limiter.acquire_at(0, "a", start).is_ok());
+        // One token every two seconds
+        assert!(matches!(
+            limiter.acquire_at(0, "a", start),
+            Err(AppError::RateLimited(2))
+        ));
+        // Other keys have their own bucket
+        assert!(limiter.acquire_at(0, "b", start).is_ok());
+
+        let later = start + Duration::from_secs(2);
+        assert!(limiter.acquire_at(0, "a", later).is_ok());
+        assert!(limiter.acquire_at(0, "a", later).is_err());
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_the_burst() {
+        let limiter = create_limiter(2, 30);
+        let later = Instant::now() + Duration::from_secs(3600);
+
+        assert!(limiter.acquire_at(0, "a", later).is_ok());
+        assert!(limiter.acquire_at(0, "a", later).is_ok());
+        assert!(limiter.acquire_at(0, "a", later).is_err());
+    }
+
+    #[test]
+    fn lockout_starts_at_max_failures_and_expires() {
+        let lockout = create_lockout();
+        let start = Instant::now();
+
+        fail(&lockout, FIRST_IP, 2, start);
+        assert_eq!(locked_for(lockout.check_at("alice", FIRST_IP, start)), None);
+
+        fail(&lockout, FIRST_IP, 1, start);
+        assert_eq!(
+            locked_for(lockout.check_at("alice", FIRST_IP, start)),
+            Some(60)
+        );
+        // Only the address that guessed is locked out
+        assert_eq!(
+            locked_for(lockout.check_at("alice", SECOND_IP, start)),
+            None
+        );
+
+        let later = start + Duration::from_secs(60);
+        assert_eq!(locked_for(lockout.check_at("alice", FIRST_IP, later)), None);
+    }
+
+    #[test]
+    fn repeated_lockouts_double_up_to_the_maximum() {
+        let lockout = create_lockout();
+        let mut now = Instant::now();
+
+        for expected in [60, 120, 240, 480, 600] {
+            fail(&lockout, FIRST_IP, 3, now);
+            assert_eq!(
+                locked_for(lockout.check_at("alice", FIRST_IP, now)),
+                Some(expected)
+            );
+            now += Duration::from_secs(expected);
+        }
+    }
+
+    #[test]
+    fn failures_outside_the_window_are_forgotten() {
+        let lockout = create_lockout();
+        let start = Instant::now();
+
+        fail(&lockout, FIRST_IP, 2, start);
+        let later = start + Duration::from_secs(301);
+        fail(&lockout, FIRST_IP, 2, later);
+
+        assert_eq!(locked_for(lockout.check_at("alice", FIRST_IP, later)), None);
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let lockout = create_lockout();
+        let now = Instant::now();
+
+        fail(&lockout, FIRST_IP, 2, now);
+        lockout.record_success("alice", FIRST_IP);
+        fail(&lockout, FIRST_IP, 2, now);
+
+        assert_eq!(locked_for(lockout.check_at("alice", FIRST_IP, now)), None);
+    }
+
+    #[test]
+    fn failures_from_many_addresses_lock_the_account_everywhere() {
+        let lockout = create_lockout();
+        let now = Instant::now();
+
+        for last_octet in 1..=5 {
+            let client_ip = Some(IpAddr::V4(Ipv4Addr::new(10, 0, 1, last_octet)));
+            fail(&lockout, client_ip, 1, now);
+        }
+
+        assert_eq!(locked_for(lockout.check_at("alice", None, now)), Some(60));
+        assert_eq!(locked_for(lockout.check_at("bob", None, now)), None);
+    }
+
+    #[test]
+    fn disabled_lockout_never_locks() {
+        let lockout = LoginLockout::new(LockoutConfig {
+            enabled: false,
+            ..LockoutConfig::default()
+        });
+        let now = Instant::now();
+
+        fail(&lockout, FIRST_IP, 100, now);
+
+        assert_eq!(locked_for(lockout.check_at("alice", FIRST_IP, now)), None);
+    }
+}
@@ -0,0 +1,5 @@
+use crate::utils::token::parse_token;
+
+pub fn read_refresh_session_id(token: &str) -> Option<String> {
+    parse_token(token).map(|(session_id, _)| session_id.to_hex())
+}
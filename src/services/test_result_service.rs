@@ -0,0 +1,74 @@
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::test_result::{CharStats, TestResultRecord};
+use crate::models::user::Score;
+use crate::repositories::test_result_repository::TestResultRepository;
+use crate::structs::test_result::{GetTestResultsQueries, TestResultEntry, TestResultsPage};
+use crate::utils::helpers::{get_page_offset, to_chrono_datetime};
+use mongodb::bson::DateTime as BsonDateTime;
+
+const DEFAULT_RESULTS_LIMIT: u64 = 20;
+const MAX_RESULTS_LIMIT: u64 = 100;
+
+// Stamped with the server's clock; windowed leaderboards filter on the
+// timestamp, so a client-supplied date must never end up here
+pub fn create_test_result(
+    username: &str,
+    mode: &GameMode,
+    score: &Score,
+    char_stats: Option<CharStats>,
+) -> TestResultRecord {
+    TestResultRecord {
+        id: None,
+        username: username.to_string(),
+        language: mode.language().to_string(),
+        difficulty: mode.difficulty().to_string(),
+        duration: mode.duration().to_string(),
+        wpm: score.wpm,
+        raw_wpm: score.raw_wpm,
+        accuracy: score.accuracy,
+        timestamp: BsonDateTime::now(),
+        char_stats,
+    }
+}
+
+fn to_test_result_entry(record: TestResultRecord) -> TestResultEntry {
+    TestResultEntry {
+        _id: record.id.map(|id| id.to_hex()).unwrap_or_default(),
+        language: record.language,
+        difficulty: record.difficulty,
+        duration: record.duration,
+        wpm: record.wpm,
+        raw_wpm: record.raw_wpm,
+        accuracy: record.accuracy,
+        timestamp: to_chrono_datetime(record.timestamp),
+        char_stats: record.char_stats,
+    }
+}
+
+// The requested page and page size, and how many results come before it
+fn get_results_paging(queries: &GetTestResultsQueries) -> Result<(u64, u64, u64), AppError> {
+    let page = queries.page.unwrap_or(1).max(1);
+    let limit = queries
+        .limit
+        .unwrap_or(DEFAULT_RESULTS_LIMIT)
+        .clamp(1, MAX_RESULTS_LIMIT);
+    let offset = get_page_offset(page, limit)?;
+    Ok((page, limit, offset))
+}
+
+pub async fn fetch_test_results(
+    results: &dyn TestResultRepository,
+    username: &str,
+    queries: &GetTestResultsQueries,
+) -> Result<TestResultsPage, AppError> {
+    let (page, limit, offset) = get_results_paging(queries)?;
+    let (records, total_count) = results.fetch_page(username, queries, offset, limit).await?;
+
+    Ok(TestResultsPage {
+        results: records.into_iter().map(to_test_result_entry).collect(),
+        total_count,
+        page,
+        limit,
+    })
+}
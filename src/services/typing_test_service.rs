@@ -0,0 +1,526 @@
+use crate::config::auth::AuthConfig;
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::test_result::CharStats;
+use crate::structs::claims::TestSessionClaims;
+use crate::structs::typing_test::{FinishTestRequest, Keystroke, TestResult, TestSession};
+use crate::utils::word_generator::generate_words;
+use chrono::{TimeZone, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use mongodb::bson::oid::ObjectId;
+
+const TEST_SESSION_AUDIENCE: &str = "typing_test";
+// How long a session stays valid after its duration has elapsed
+const TEST_SESSION_GRACE_SECS: i64 = 600;
+// Enough words for the fastest plausible typist to never run out
+const WORDS_PER_SECOND: usize = 5;
+const MIN_WORD_COUNT: usize = 50;
+
+const TIMING_TOLERANCE_MS: u64 = 1000;
+const MAX_KEYSTROKES_PER_SECOND: usize = 35;
+const MIN_KEYSTROKES_FOR_VARIANCE_CHECK: usize = 50;
+const MIN_KEYSTROKE_INTERVAL_STDDEV_MS: f64 = 2.0;
+const MAX_PLAUSIBLE_WPM: u32 = 300;
+
+fn rejected(message: &str) -> AppError {
+    AppError::InvalidTestResult(message.to_string())
+}
+
+pub fn create_test_session(
+    auth: &AuthConfig,
+    username: &str,
+    mode: &GameMode,
+) -> Result<TestSession, AppError> {
+    // Registry durations are validated as positive integers at startup
+    let duration: u32 = match mode.duration().parse() {
+        Ok(duration) => duration,
+        Err(_) => return Err(AppError::Validation("Invalid test duration.".to_string())),
+    };
+
+    let seed: u64 = rand::random();
+    let word_count = (duration as usize * WORDS_PER_SECOND).max(MIN_WORD_COUNT);
+    let words = match generate_words(mode.language(), mode.difficulty(), seed, word_count) {
+        Some(words) => words,
+        None => {
+            return Err(AppError::Validation(
+                "Unsupported language or difficulty.".to_string(),
+            ))
+        }
+    };
+
+    let started_at = Utc::now();
+    let claims = TestSessionClaims {
+        sub: username.to_owned(),
+        aud: TEST_SESSION_AUDIENCE.to_string(),
+        exp: (started_at.timestamp() + duration as i64 + TEST_SESSION_GRACE_SECS) as usize,
+        test_id: ObjectId::new().to_hex(),
+        seed,
+        word_count,
+        language: mode.language().to_string(),
+        difficulty: mode.difficulty().to_string(),
+        duration,
+        started_at: started_at.timestamp_millis(),
+    };
+
+    let encoding_key = EncodingKey::from_secret(auth.jwt_secret.as_bytes());
+    let session_token = encode(&Header::default(), &claims, &encoding_key)?;
+
+    Ok(TestSession {
+        test_id: claims.test_id,
+        session_token,
+        words,
+        language: claims.language,
+        difficulty: claims.difficulty,
+        duration,
+        started_at,
+    })
+}
+
+pub fn decode_test_session(auth: &AuthConfig, token: &str) -> Result<TestSessionClaims, AppError> {
+    let decoding_key = DecodingKey::from_secret(auth.jwt_secret.as_bytes());
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_audience(&[TEST_SESSION_AUDIENCE]);
+
+    decode::<TestSessionClaims>(token, &decoding_key, &validation)
+        .map(|data| data.claims)
+        .map_err(|_| AppError::Validation("Invalid or expired test session.".to_string()))
+}
+
+pub fn verify_test_result(
+    claims: &TestSessionClaims,
+    request: &FinishTestRequest,
+) -> Result<TestResult, AppError> {
+    let duration_ms = claims.duration as u64 * 1000;
+    let started_at = Utc
+        .timestamp_millis_opt(claims.started_at)
+        .single()
+        .unwrap_or_else(Utc::now);
+    let elapsed_ms = (Utc::now() - started_at).num_milliseconds().max(0) as u64;
+
+    if elapsed_ms + TIMING_TOLERANCE_MS < duration_ms {
+        return Err(rejected("Test finished before its duration elapsed."));
+    }
+
+    validate_keystroke_timings(&request.keystrokes, duration_ms).map_err(rejected)?;
+
+    let words = generate_words(
+        &claims.language,
+        &claims.difficulty,
+        claims.seed,
+        claims.word_count,
+    )
+    .ok_or_else(|| rejected("Unsupported language or difficulty."))?;
+    let target: Vec<char> = words.join(" ").chars().collect();
+
+    let replay = replay_keystrokes(&request.keystrokes, &target);
+    if replay.text != request.typed {
+        return Err(rejected(
+            "Typed input does not match the recorded keystrokes.",
+        ));
+    }
+    if replay.text.chars().count() > target.len() {
+        return Err(rejected("Typed input is longer than the test text."));
+    }
+
+    let minutes = claims.duration as f64 / 60.0;
+    let correct_chars = count_correct_word_chars(&request.typed, &words);
+    let char_stats = compute_char_stats(&request.typed, &words);
+    let typed_chars = request.typed.chars().count();
+
+    let wpm = (correct_chars as f64 / 5.0 / minutes).round() as u32;
+    let raw_wpm = (typed_chars as f64 / 5.0 / minutes).round() as u32;
+    let accuracy = if replay.total_chars == 0 {
+        0.0
+    } else {
+        let ratio = replay.correct_chars as f64 / replay.total_chars as f64;
+        ((ratio * 10000.0).round() / 100.0) as f32
+    };
+
+    if raw_wpm > MAX_PLAUSIBLE_WPM {
+        return Err(rejected("Typing speed is not plausible."));
+    }
+
+    Ok(TestResult {
+        test_id: claims.test_id.clone(),
+        wpm,
+        raw_wpm,
+        accuracy,
+        char_stats,
+        personal_best: false,
+    })
+}
+
+fn validate_keystroke_timings(keystrokes: &[Keystroke], duration_ms: u64) -> Result<(), &str> {
+    if keystrokes.is_empty() {
+        return Err("No keystrokes were recorded.");
+    }
+
+    if keystrokes
+        .windows(2)
+        .any(|pair| pair[1].timestamp < pair[0].timestamp)
+    {
+        return Err("Keystroke timestamps are out of order.");
+    }
+
+    let last_timestamp = keystrokes[keystrokes.len() - 1].timestamp;
+    if last_timestamp > duration_ms + TIMING_TOLERANCE_MS {
+        return Err("Keystrokes were recorded after the test ended.");
+    }
+
+    // Sliding one-second window over the keystroke timeline
+    let mut window_start = 0;
+    for (index, keystroke) in keystrokes.iter().enumerate() {
+        while keystroke.timestamp - keystrokes[window_start].timestamp >= 1000 {
+            window_start += 1;
+        }
+        if index - window_start + 1 > MAX_KEYSTROKES_PER_SECOND {
+            return Err("Keystrokes are too fast to be human.");
+        }
+    }
+
+    // Scripted input tends to have near-constant intervals between keys
+    if keystrokes.len() >= MIN_KEYSTROKES_FOR_VARIANCE_CHECK {
+        let intervals: Vec<f64> = keystrokes
+            .windows(2)
+            .map(|pair| (pair[1].timestamp - pair[0].timestamp) as f64)
+            .collect();
+        let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        let variance = intervals
+            .iter()
+            .map(|interval| (interval - mean).powi(2))
+            .sum::<f64>()
+            / intervals.len() as f64;
+
+        if variance.sqrt() < MIN_KEYSTROKE_INTERVAL_STDDEV_MS {
+            return Err("Keystroke timings are too uniform to be human.");
+        }
+    }
+
+    Ok(())
+}
+
+struct KeystrokeReplay {
+    text: String,
+    correct_chars: usize,
+    total_chars: usize,
+}
+
+fn replay_keystrokes(keystrokes: &[Keystroke], target: &[char]) -> KeystrokeReplay {
+    let mut typed: Vec<char> = Vec::new();
+    let mut correct_chars = 0;
+    let mut total_chars = 0;
+
+    for keystroke in keystrokes {
+        let key = keystroke.key.as_str();
+
+        if key == "Backspace" {
+            typed.pop();
+            continue;
+        }
+
+        // Named keys such as "Shift" or "Enter" do not produce text, while IME
+        // commits (e.g. Chinese input) can produce several characters at once
+        let is_named_key = key.chars().count() > 1 && key.is_ascii();
+        if is_named_key {
+            continue;
+        }
+
+        for ch in key.chars() {
+            if target.get(typed.len()) == Some(&ch) {
+                correct_chars += 1;
+            }
+            total_chars += 1;
+            typed.push(ch);
+        }
+    }
+
+    KeystrokeReplay {
+        text: typed.into_iter().collect(),
+        correct_chars,
+        total_chars,
+    }
+}
+
+fn count_correct_word_chars(typed: &str, words: &[String]) -> usize {
+    let typed_words: Vec<&str> = typed.split(' ').collect();
+    let last_index = typed_words.len() - 1;
+
+    typed_words
+        .iter()
+        .zip(words.iter())
+        .enumerate()
+        .filter(|(_, (typed_word, word))| *typed_word == word)
+        .map(|(index, (typed_word, _))| {
+            // Count the space typed after every completed correct word
+            let space = if index < last_index { 1 } else { 0 };
+            typed_word.chars().count() + space
+        })
+        .sum()
+}
+
+fn compute_char_stats(typed: &str, words: &[String]) -> CharStats {
+    let typed_words: Vec<&str> = typed.split(' ').collect();
+    let last_index = typed_words.len() - 1;
+    let mut stats = CharStats::default();
+
+    for (index, (typed_word, word)) in typed_words.iter().zip(words.iter()).enumerate() {
+        let typed_chars: Vec<char> = typed_word.chars().collect();
+        let word_chars: Vec<char> = word.chars().collect();
+
+        for (typed_char, word_char) in typed_chars.iter().zip(word_chars.iter()) {
+            if typed_char == word_char {
+                stats.correct += 1;
+            } else {
+                stats.incorrect += 1;
+            }
+        }
+
+        if typed_chars.len() > word_chars.len() {
+            stats.extra += (typed_chars.len() - word_chars.len()) as u32;
+        } else if index < last_index {
+            // The word still being typed when time ran out is not missed
+            stats.missed += (word_chars.len() - typed_chars.len()) as u32;
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 42;
+    const WORD_COUNT: usize = 200;
+
+    fn create_claims(duration: u32) -> TestSessionClaims {
+        let started_at = Utc::now() - chrono::Duration::seconds(duration as i64);
+        TestSessionClaims {
+            sub: "alice".to_string(),
+            aud: TEST_SESSION_AUDIENCE.to_string(),
+            exp: 0,
+            test_id: "test".to_string(),
+            seed: SEED,
+            word_count: WORD_COUNT,
+            language: "english".to_string(),
+            difficulty: "normal".to_string(),
+            duration,
+            started_at: started_at.timestamp_millis(),
+        }
+    }
+
+    fn create_words() -> Vec<String> {
+        generate_words("english", "normal", SEED, WORD_COUNT).unwrap()
+    }
+
+    // One keystroke per key, `base_ms` apart give or take some jitter
+    fn create_keystrokes(keys: &[&str], base_ms: u64, jitter_ms: u64) -> Vec<Keystroke> {
+        let mut timestamp = 0;
+        keys.iter()
+            .enumerate()
+            .map(|(index, key)| {
+                timestamp += base_ms + (index as u64 * 37) % (jitter_ms + 1);
+                Keystroke {
+                    key: key.to_string(),
+                    timestamp,
+                }
+            })
+            .collect()
+    }
+
+    fn create_request(typed: &str, keystrokes: Vec<Keystroke>) -> FinishTestRequest {
+        FinishTestRequest {
+            session_token: String::new(),
+            typed: typed.to_string(),
+            keystrokes,
+        }
+    }
+
+    fn split_keys(text: &str) -> Vec<String> {
+        text.chars().map(|ch| ch.to_string()).collect()
+    }
+
+    fn assert_rejected(result: Result<TestResult, AppError>, expected: &str) {
+        match result {
+            Err(AppError::InvalidTestResult(message)) => assert_eq!(message, expected),
+            Err(err) => panic!("unexpected error: {}", err),
+            Ok(result) => panic!("accepted with {} wpm", result.wpm),
+        }
+    }
+
+    #[test]
+    fn accepts_a_replayed_test() {
+        let typed = create_words()[..10].join(" ");
+        let keys = split_keys(&typed);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let request = create_request(&typed, create_keystrokes(&keys, 120, 90));
+
+        let result = verify_test_result(&create_claims(15), &request).unwrap();
+
+        let expected_wpm = (typed.chars().count() as f64 / 5.0 / 0.25).round() as u32;
+        assert_eq!(result.wpm, expected_wpm);
+        assert_eq!(result.raw_wpm, expected_wpm);
+        assert_eq!(result.accuracy, 100.0);
+        assert_eq!(result.char_stats.incorrect, 0);
+    }
+
+    #[test]
+    fn corrected_typos_lower_accuracy_but_not_wpm() {
+        let words = create_words();
+        let typed = words[..2].join(" ");
+        let mut keys = split_keys(&typed);
+        // A wrong key, erased and then typed correctly
+        keys.insert(1, "#".to_string());
+        keys.insert(2, "Backspace".to_string());
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let request = create_request(&typed, create_keystrokes(&keys, 150, 90));
+
+        let result = verify_test_result(&create_claims(15), &request).unwrap();
+
+        let total_chars = typed.chars().count() + 1;
+        let expected =
+            (typed.chars().count() as f64 / total_chars as f64 * 10000.0).round() / 100.0;
+        assert_eq!(result.accuracy, expected as f32);
+        assert_eq!(result.char_stats.incorrect, 0);
+    }
+
+    #[test]
+    fn rejects_typed_text_the_keystrokes_do_not_produce() {
+        let words = create_words();
+        let replayed = words[..3].join(" ");
+        // Claims a whole extra word on top of what was typed
+        let claimed = words[..4].join(" ");
+        let keys = split_keys(&replayed);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let request = create_request(&claimed, create_keystrokes(&keys, 120, 90));
+
+        assert_rejected(
+            verify_test_result(&create_claims(15), &request),
+            "Typed input does not match the recorded keystrokes.",
+        );
+    }
+
+    #[test]
+    fn rejects_a_wrong_word_claimed_as_correct() {
+        let words = create_words();
+        let typed = format!("{}x {}", words[0], words[1]);
+        let keys = split_keys(&typed);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let mut request = create_request(&typed, create_keystrokes(&keys, 120, 90));
+        request.typed = words[..2].join(" ");
+
+        assert_rejected(
+            verify_test_result(&create_claims(15), &request),
+            "Typed input does not match the recorded keystrokes.",
+        );
+    }
+
+    #[test]
+    fn rejects_an_implausible_speed() {
+        let text = create_words().join(" ");
+        // 400 characters in 15 seconds are 320 wpm
+        let typed: String = text.chars().take(400).collect();
+        let keys = split_keys(&typed);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let request = create_request(&typed, create_keystrokes(&keys, 30, 10));
+
+        assert_rejected(
+            verify_test_result(&create_claims(15), &request),
+            "Typing speed is not plausible.",
+        );
+    }
+
+    #[test]
+    fn rejects_a_test_finished_early() {
+        let typed = create_words()[0].clone();
+        let keys = split_keys(&typed);
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let request = create_request(&typed, create_keystrokes(&keys, 120, 90));
+        let mut claims = create_claims(15);
+        claims.started_at = Utc::now().timestamp_millis() - 5_000;
+
+        assert_rejected(
+            verify_test_result(&claims, &request),
+            "Test finished before its duration elapsed.",
+        );
+    }
+
+    #[test]
+    fn rejects_impossible_keystroke_timings() {
+        let keys = ["a"; 60];
+
+        let too_fast = create_keystrokes(&keys, 10, 5);
+        assert_eq!(
+            validate_keystroke_timings(&too_fast, 15_000),
+            Err("Keystrokes are too fast to be human.")
+        );
+
+        let uniform = create_keystrokes(&keys, 100, 0);
+        assert_eq!(
+            validate_keystroke_timings(&uniform, 15_000),
+            Err("Keystroke timings are too uniform to be human.")
+        );
+
+        let mut out_of_order = create_keystrokes(&keys, 100, 50);
+        out_of_order.swap(10, 11);
+        assert_eq!(
+            validate_keystroke_timings(&out_of_order, 15_000),
+            Err("Keystroke timestamps are out of order.")
+        );
+
+        let too_late = create_keystrokes(&keys, 300, 50);
+        assert_eq!(
+            validate_keystroke_timings(&too_late, 15_000),
+            Err("Keystrokes were recorded after the test ended.")
+        );
+
+        assert_eq!(
+            validate_keystroke_timings(&[], 15_000),
+            Err("No keystrokes were recorded.")
+        );
+    }
+
+    #[test]
+    fn replay_applies_backspace_and_skips_named_keys() {
+        let target: Vec<char> = "ab cd".chars().collect();
+        let keystrokes = create_keystrokes(
+            &["a", "x", "Backspace", "b", "Shift", " ", "c", "d"],
+            100,
+            50,
+        );
+
+        let replay = replay_keystrokes(&keystrokes, &target);
+
+        assert_eq!(replay.text, "ab cd");
+        assert_eq!(replay.correct_chars, 5);
+        assert_eq!(replay.total_chars, 6);
+    }
+
+    #[test]
+    fn replay_accepts_multi_character_ime_commits() {
+        let target: Vec<char> = "你好 世界".chars().collect();
+        let keystrokes = create_keystrokes(&["你好", " ", "世界"], 200, 50);
+
+        let replay = replay_keystrokes(&keystrokes, &target);
+
+        assert_eq!(replay.text, "你好 世界");
+        assert_eq!(replay.correct_chars, 5);
+        assert_eq!(replay.total_chars, 5);
+    }
+
+    #[test]
+    fn counts_correct_words_with_their_spaces() {
+        let words: Vec<String> = ["the", "quick", "brown", "fox"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+
+        // Both words and the space after the first
+        assert_eq!(count_correct_word_chars("the quick", &words), 9);
+        // A mistyped word counts for nothing, not even its space
+        assert_eq!(count_correct_word_chars("the quack brown", &words), 9);
+        // An unfinished last word is not correct yet
+        assert_eq!(count_correct_word_chars("the qui", &words), 4);
+        assert_eq!(count_correct_word_chars("", &words), 0);
+    }
+}
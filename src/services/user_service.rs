@@ -1,72 +1,135 @@
-use crate::models::user::{
-    default_user, DifficultyScores, HighScores, LanguageScores, Score, User,
-};
-use crate::structs::api_response::ApiResponse;
-use crate::structs::api_response::{error_response, success_response};
+use crate::config::app_config::AppConfig;
+use crate::config::auth::AuthConfig;
+use crate::config::modes::ModeRegistry;
+use crate::constants::{ACCESS_TOKEN_MINUTES, REFRESH_TOKEN_DAYS};
+use crate::errors::app_error::AppError;
+use crate::models::mode::GameMode;
+use crate::models::session::DeviceInfo;
+use crate::models::user::{default_user, Score, User};
+use crate::repositories::session_repository::SessionRepository;
+use crate::repositories::user_repository::UserRepository;
+use crate::services::metrics_service::metrics;
+use crate::services::password_service::{hash_password, verify_password, PasswordCheck};
 use crate::structs::claims::Claims;
-use crate::structs::leaderboard::ScoreUpdateRequest;
-use crate::structs::recaptcha_response::RecaptchaResponse;
 use actix_web::cookie::time::Duration;
 use actix_web::{
     cookie::{Cookie, SameSite},
-    HttpResponse,
+    http::header::{USER_AGENT, X_FORWARDED_FOR},
+    web, HttpRequest,
 };
-use chrono::Utc;
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use jsonwebtoken::{encode, EncodingKey, Header};
-use mongodb::bson::{doc, from_bson, to_bson, to_document, Bson, Document};
-use mongodb::error::Error;
-use mongodb::Collection;
-use reqwest::Error as ReqwestError;
-use std::collections::HashMap;
-use std::env;
-
-pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    // Fetch the secret key from environment variables
-    let secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    let decoding_key = DecodingKey::from_secret(secret.as_ref());
+use std::net::IpAddr;
+
+fn decode_jwt(
+    auth: &AuthConfig,
+    token: &str,
+    validation: &Validation,
+) -> Result<Claims, jsonwebtoken::errors::Error> {
+    let decoding_key = DecodingKey::from_secret(auth.jwt_secret.as_ref());
 
     // Decode the JWT and extract claims
-    decode::<Claims>(token, &decoding_key, &Validation::new(Algorithm::HS256))
-        .map(|data| data.claims) // Extract claims from the decoded token
+    decode::<Claims>(token, &decoding_key, validation).map(|data| data.claims)
+}
+
+// Access tokens are only honoured while their session is still active, so a
+// revoked session locks the token out before it expires
+pub async fn verify_jwt(
+    auth: &AuthConfig,
+    sessions: &dyn SessionRepository,
+    token: &str,
+) -> Result<Claims, AppError> {
+    let claims = decode_jwt(auth, token, &Validation::new(Algorithm::HS256))?;
+
+    if sessions.is_active(&claims.sid).await? {
+        Ok(claims)
+    } else {
+        Err(AppError::SessionRevoked)
+    }
+}
+
+// Reads the session id from an access token even after it expired, which is
+// all logout needs
+pub fn read_session_id(auth: &AuthConfig, token: &str) -> Option<String> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_exp = false;
+    decode_jwt(auth, token, &validation)
+        .ok()
+        .map(|claims| claims.sid)
+}
+
+// Username of a valid access token, without checking its session
+pub fn read_username(auth: &AuthConfig, token: &str) -> Option<String> {
+    decode_jwt(auth, token, &Validation::new(Algorithm::HS256))
+        .ok()
+        .map(|claims| claims.sub)
+}
+
+pub fn get_device_info(req: &HttpRequest) -> DeviceInfo {
+    let user_agent = req
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    DeviceInfo {
+        user_agent,
+        ip_address: get_client_ip(req).map(|ip| ip.to_string()),
+    }
+}
+
+// The connecting address, unless that is one of our proxies. Then it is the
+// last X-Forwarded-For hop that is not a proxy: each proxy appends the
+// address it saw, while everything before it is up to the client.
+pub fn get_client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let trusted_proxies = match req.app_data::<web::Data<AppConfig>>() {
+        Some(config) => config.server.trusted_proxies.as_slice(),
+        None => &[],
+    };
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let hops: Vec<IpAddr> = req
+        .headers()
+        .get_all(X_FORWARDED_FOR)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    let client = hops
+        .iter()
+        .rev()
+        .find(|hop| !trusted_proxies.contains(hop))
+        .or(hops.first())
+        .copied();
+    Some(client.unwrap_or(peer))
 }
 
 pub async fn process_user_registration(
-    collection: &Collection<Document>,
+    users: &dyn UserRepository,
     username: &str,
     password: &str,
-) -> HttpResponse {
-    match is_user_exists(collection, username).await {
-        Ok(true) => HttpResponse::BadRequest().json(error_response("Username already taken.")),
-        Ok(false) => {
-            let user = create_user(username.to_string(), password.to_string());
-            match insert_user(collection, user).await {
-                Ok(_) => HttpResponse::Ok().json(success_response("User successfully registered.")),
-                Err(err) => HttpResponse::InternalServerError().json(error_response(&err)),
-            }
-        }
-        Err(_) => HttpResponse::InternalServerError()
-            .json(error_response("Error checking username availability.")),
+    email: Option<String>,
+    modes: &ModeRegistry,
+) -> Result<(), AppError> {
+    if users.exists(username).await? {
+        return Err(AppError::UsernameTaken);
     }
-}
 
-pub async fn verify_recaptcha_and_check(token: &str) -> Result<(), HttpResponse> {
-    match verify_recaptcha(token).await {
-        Ok(response) if response.success => Ok(()),
-        Ok(_) => Err(HttpResponse::BadRequest().json(error_response(
-            "reCAPTCHA verification failed. It seems you are not a human.",
-        ))),
-        Err(err) => {
-            eprintln!("Error verifying reCAPTCHA: {:?}", err);
-            Err(HttpResponse::InternalServerError().json(error_response(
-                "An error occurred while verifying reCAPTCHA. Please try again later.",
-            )))
-        }
-    }
+    let password_hash = hash_password_blocking(password).await?;
+    let mut user = create_user(username.to_string(), password_hash, modes);
+    user.email = email;
+    users.insert(user).await?;
+
+    metrics().sign_ups.inc();
+    Ok(())
 }
 
 pub fn create_http_only_cookie(token: String) -> Cookie<'static> {
-    let max_age = Duration::days(7);
+    // The cookie lives as long as the access token inside it
+    let max_age = Duration::minutes(ACCESS_TOKEN_MINUTES);
 
     Cookie::build("user_jwt_token", token)
         .http_only(true)
@@ -77,288 +140,219 @@ pub fn create_http_only_cookie(token: String) -> Cookie<'static> {
         .finish()
 }
 
+pub fn create_refresh_cookie(token: String) -> Cookie<'static> {
+    let max_age = Duration::days(REFRESH_TOKEN_DAYS);
+
+    Cookie::build("user_refresh_token", token)
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/")
+        .max_age(max_age)
+        .finish()
+}
+
+pub fn create_expired_cookie(name: &str) -> Cookie<'static> {
+    Cookie::build(name.to_string(), "")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::None)
+        .path("/")
+        .max_age(Duration::new(0, 0))
+        .finish()
+}
+
 pub async fn authenticate_user(
-    collection: &Collection<Document>,
+    users: &dyn UserRepository,
     username: &str,
     password: &str,
-) -> Result<bool, Error> {
-    if let Some(user) = fetch_user_by_username(collection, username).await? {
-        Ok(user.password == password)
-    } else {
-        Ok(false)
+) -> Result<bool, AppError> {
+    let user = match users.find_by_username(username).await? {
+        Some(user) => user,
+        None => return Ok(false),
+    };
+
+    let check = verify_password_blocking(password, &user.password).await;
+
+    // Only reveal the ban to someone who knows the password
+    if check != PasswordCheck::Invalid && user.banned {
+        return Err(AppError::Forbidden(
+            "This account has been banned.".to_string(),
+        ));
+    }
+
+    match check {
+        PasswordCheck::Valid => Ok(true),
+        PasswordCheck::ValidNeedsRehash => {
+            // Transparently migrate plaintext and weaker hashes on successful login
+            if let Err(err) = upgrade_password_hash(users, username, password, &user.password).await
+            {
+                tracing::error!(username, error = %err, "Error upgrading password hash");
+            }
+            Ok(true)
+        }
+        PasswordCheck::Invalid => Ok(false),
     }
 }
 
-// Helper function to fetch the JWT secret from the environment
-fn get_jwt_secret() -> Result<String, env::VarError> {
-    env::var("JWT_SECRET")
+// Argon2 is deliberately expensive, so keep it off the async workers
+async fn hash_password_blocking(password: &str) -> Result<String, AppError> {
+    let password = password.to_string();
+
+    web::block(move || hash_password(&password))
+        .await?
+        .map_err(|err| AppError::Internal(format!("Error hashing password: {}", err)))
 }
 
-// Helper function to get the expiration timestamp
-fn get_expiration_time(days: i64) -> usize {
-    (chrono::Utc::now() + chrono::Duration::days(days)).timestamp() as usize
+async fn verify_password_blocking(password: &str, stored: &str) -> PasswordCheck {
+    let password = password.to_string();
+    let stored = stored.to_string();
+
+    web::block(move || verify_password(&password, &stored))
+        .await
+        .unwrap_or(PasswordCheck::Invalid)
+}
+
+pub async fn set_user_password(
+    users: &dyn UserRepository,
+    username: &str,
+    password: &str,
+) -> Result<(), AppError> {
+    let password_hash = hash_password_blocking(password).await?;
+    users.update_password(username, &password_hash).await
+}
+
+// Only replaces the hash that was just verified, so a password changed
+// concurrently is not reverted to the one used to log in
+async fn upgrade_password_hash(
+    users: &dyn UserRepository,
+    username: &str,
+    password: &str,
+    old_hash: &str,
+) -> Result<(), AppError> {
+    let password_hash = hash_password_blocking(password).await?;
+    if !users
+        .upgrade_password_hash(username, old_hash, &password_hash)
+        .await?
+    {
+        tracing::debug!(username, "Password changed before its hash was upgraded");
+    }
+    Ok(())
 }
 
-pub fn generate_jwt(username: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    // Fetch the secret key
-    let secret_key = get_jwt_secret().expect("JWT_SECRET must be set");
+// Helper function to get the expiration timestamp
+fn get_expiration_time(minutes: i64) -> usize {
+    (chrono::Utc::now() + chrono::Duration::minutes(minutes)).timestamp() as usize
+}
 
+pub fn generate_jwt(
+    auth: &AuthConfig,
+    username: &str,
+    session_id: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
     // Create claims with expiration
     let claims = Claims {
         sub: username.to_owned(),
-        exp: get_expiration_time(7),
+        exp: get_expiration_time(ACCESS_TOKEN_MINUTES),
+        sid: session_id.to_owned(),
     };
 
     // Create the encoding key
-    let encoding_key = EncodingKey::from_secret(secret_key.as_bytes());
+    let encoding_key = EncodingKey::from_secret(auth.jwt_secret.as_bytes());
 
     // Encode the token
     encode(&Header::default(), &claims, &encoding_key)
 }
 
+fn invalid(message: &str) -> Result<(), AppError> {
+    Err(AppError::Validation(message.to_string()))
+}
+
 pub fn validate_credentials(
     username: &str,
     token: &str,
     password: &str,
     confirmation_password: Option<&str>,
-) -> Option<HttpResponse> {
+) -> Result<(), AppError> {
     if username.is_empty() {
-        return Some(HttpResponse::BadRequest().json(error_response("Username cannot be empty.")));
+        return invalid("Username cannot be empty.");
     }
     if token.is_empty() {
-        return Some(HttpResponse::BadRequest().json(error_response("Token cannot be empty.")));
+        return invalid("Token cannot be empty.");
     }
     if password.is_empty() {
-        return Some(HttpResponse::BadRequest().json(error_response("Password cannot be empty.")));
+        return invalid("Password cannot be empty.");
     }
     if let Some(confirmation_password) = confirmation_password {
         if confirmation_password.is_empty() {
-            return Some(
-                HttpResponse::BadRequest()
-                    .json(error_response("Confirmation Password cannot be empty.")),
-            );
+            return invalid("Confirmation Password cannot be empty.");
         }
         if confirmation_password != password {
-            return Some(
-                HttpResponse::BadRequest()
-                    .json(error_response("Confirmation Password is incorrect.")),
-            );
+            return invalid("Confirmation Password is incorrect.");
         }
     }
-    None
+    Ok(())
 }
 
-pub fn create_user(username: String, password: String) -> User {
-    let mut user = default_user();
-    user.username = username;
-    user.password = password;
-    user
-}
-
-pub async fn insert_user(collection: &Collection<Document>, user: User) -> Result<(), String> {
-    let user_doc = match to_document(&user) {
-        Ok(doc) => doc,
-        Err(err) => return Err(format!("Error converting user to BSON: {}", err)),
-    };
-
-    match collection.insert_one(user_doc).await {
-        Ok(_) => Ok(()),
-        Err(err) => Err(format!("Error inserting user: {}", err)),
+pub fn validate_new_password(password: &str, confirmation_password: &str) -> Result<(), AppError> {
+    if password.is_empty() {
+        return invalid("Password cannot be empty.");
     }
-}
-
-pub async fn verify_recaptcha(token: &str) -> Result<RecaptchaResponse, ReqwestError> {
-    let secret_key = std::env::var("SECRET_KEY").expect("SECRET_KEY must be set");
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post("https://www.google.com/recaptcha/api/siteverify")
-        .form(&[("secret", secret_key), ("response", token.to_string())])
-        .send()
-        .await?;
-
-    let recaptcha_response = response.json::<RecaptchaResponse>().await?;
-
-    Ok(recaptcha_response)
-}
-
-pub async fn is_user_exists(
-    collection: &Collection<Document>,
-    username: &str,
-) -> Result<bool, Error> {
-    let filter = doc! { "username": username };
-    match collection.find_one(filter).await {
-        Ok(Some(_)) => Ok(true),
-        Ok(None) => Ok(false),
-        Err(e) => Err(e),
+    if confirmation_password != password {
+        return invalid("Confirmation Password is incorrect.");
     }
+    Ok(())
 }
 
-pub async fn fetch_user_and_handle_response(
-    collection: &Collection<Document>,
-    username: &str,
-) -> Result<User, HttpResponse> {
-    match fetch_user_by_username(collection, username).await {
-        Ok(Some(user)) => Ok(user),
-        Ok(None) => Err(create_not_found_response(username)),
-        Err(_) => Err(create_internal_server_error_response(username)),
+// Addresses are compared case-insensitively, so they are stored lowercased
+pub fn normalize_email(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    match email.parse::<lettre::Address>() {
+        Ok(_) => Ok(email),
+        Err(_) => Err(AppError::Validation(
+            "Email address is not valid.".to_string(),
+        )),
     }
 }
 
-fn create_not_found_response(username: &str) -> HttpResponse {
-    HttpResponse::NotFound().json(ApiResponse {
-        status: "error".to_string(),
-        message: format!("User '{}' not found in the database", username),
-    })
-}
-
-fn create_internal_server_error_response(username: &str) -> HttpResponse {
-    HttpResponse::InternalServerError().json(ApiResponse {
-        status: "error".to_string(),
-        message: format!(
-            "Error fetching user '{}'. Please try again later.",
-            username
-        ),
-    })
+pub fn create_user(username: String, password_hash: String, modes: &ModeRegistry) -> User {
+    let mut user = default_user(modes);
+    user.username = username;
+    user.password = password_hash;
+    user
 }
 
-pub async fn save_user_scores(
-    collection: &Collection<Document>,
+pub async fn fetch_user_and_handle_response(
+    users: &dyn UserRepository,
     username: &str,
-    user: &User,
-) -> HttpResponse {
-    match update_user_in_db(collection, username, user).await {
-        Ok(result) if result.matched_count > 0 => create_success_response(username),
-        Ok(_) => create_not_found_update_response(username),
-        Err(_) => create_internal_server_error_update_response(username),
-    }
-}
-
-fn create_success_response(username: &str) -> HttpResponse {
-    HttpResponse::Ok().json(ApiResponse {
-        status: "success".to_string(),
-        message: format!("Scores updated successfully for user '{}'.", username),
-    })
-}
-
-fn create_not_found_update_response(username: &str) -> HttpResponse {
-    HttpResponse::NotFound().json(ApiResponse {
-        status: "error".to_string(),
-        message: format!("User '{}' not found when attempting to update", username),
-    })
-}
-
-fn create_internal_server_error_update_response(username: &str) -> HttpResponse {
-    HttpResponse::InternalServerError().json(ApiResponse {
-        status: "error".to_string(),
-        message: format!(
-            "Failed to update scores for user '{}'. Please try again later.",
+) -> Result<User, AppError> {
+    match users.find_by_username(username).await? {
+        Some(user) => Ok(user),
+        None => Err(AppError::NotFound(format!(
+            "User '{}' not found in the database",
             username
-        ),
-    })
-}
-
-fn create_score_entry(score_update: &ScoreUpdateRequest) -> Score {
-    Score {
-        wpm: score_update.score.wpm,
-        raw_wpm: score_update.score.raw_wpm,
-        accuracy: score_update.score.accuracy,
-        date: score_update.score.date,
-    }
-}
-
-pub fn update_user_high_scores(user: &mut User, score_update: ScoreUpdateRequest) {
-    let new_entry = create_score_entry(&score_update);
-
-    // Extract relevant details from score_update
-    let timer_duration_str = score_update.duration;
-    let language = score_update.language;
-    let difficulty = score_update.difficulty;
-
-    user.high_scores = Some(update_high_scores(
-        user.high_scores.take(),
-        language,
-        difficulty,
-        timer_duration_str,
-        new_entry,
-    ));
-    increment_completed_tests(&mut user.completed_tests);
-}
-
-fn update_high_scores(
-    existing_high_scores: Option<HighScores>,
-    language: String,
-    difficulty: String,
-    timer_duration_str: String,
-    new_entry: Score,
-) -> HighScores {
-    let mut high_scores = existing_high_scores.unwrap_or_else(|| HighScores {
-        languages: HashMap::new(),
-    });
-
-    let language_scores = high_scores
-        .languages
-        .entry(language.clone())
-        .or_insert_with(|| LanguageScores {
-            difficulties: HashMap::new(),
-        });
-
-    let difficulty_scores = language_scores
-        .difficulties
-        .entry(difficulty.clone())
-        .or_insert_with(|| DifficultyScores {
-            scores: HashMap::new(),
-        });
-
-    let existing_score = difficulty_scores
-        .scores
-        .entry(timer_duration_str.clone())
-        .or_insert_with(|| Score {
-            wpm: 0,
-            raw_wpm: 0,
-            accuracy: 0.0,
-            date: Utc::now(),
-        });
-
-    // Update score if new entry is better
-    if new_entry.wpm > existing_score.wpm {
-        *existing_score = new_entry;
-    }
-
-    high_scores
-}
-
-fn increment_completed_tests(completed_tests: &mut Option<u32>) {
-    if let Some(tests) = completed_tests {
-        *tests += 1;
+        ))),
     }
 }
 
-pub async fn update_user_in_db(
-    collection: &Collection<Document>,
+pub async fn save_user_scores(
+    users: &dyn UserRepository,
     username: &str,
-    user: &User,
-) -> Result<mongodb::results::UpdateResult, mongodb::error::Error> {
-    let filter = doc! { "username": username };
-    let update = doc! {
-        "$set": {
-            "high_scores": to_bson(&user.high_scores).unwrap(),
-            "completed_tests": user.completed_tests,
+    mode: &GameMode,
+    score: &Score,
+) -> Result<bool, AppError> {
+    match users.record_score(username, mode, score).await? {
+        Some(personal_best) => {
+            let metrics = metrics();
+            metrics.scores_submitted.inc();
+            if personal_best {
+                metrics.personal_bests.inc();
+            }
+            Ok(personal_best)
         }
-    };
-    collection.update_one(filter, update).await
-}
-
-pub async fn fetch_user_by_username(
-    collection: &Collection<Document>,
-    username: &str,
-) -> Result<Option<User>, mongodb::error::Error> {
-    let filter = doc! { "username": username };
-    let user_doc = collection.find_one(filter).await?;
-
-    if let Some(doc) = user_doc {
-        Ok(Some(from_bson(Bson::Document(doc))?))
-    } else {
-        Ok(None)
+        None => Err(AppError::NotFound(format!(
+            "User '{}' not found when attempting to update",
+            username
+        ))),
     }
 }
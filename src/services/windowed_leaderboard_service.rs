@@ -0,0 +1,79 @@
+use crate::structs::leaderboard::LeaderboardTimeWindow;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+
+// Start of the current window in UTC; None for the all-time board. Weeks
+// start on Monday.
+pub fn get_window_start(
+    window: LeaderboardTimeWindow,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let today = now.date_naive();
+
+    let start_date = match window {
+        LeaderboardTimeWindow::Daily => today,
+        LeaderboardTimeWindow::Weekly => {
+            today - Duration::days(today.weekday().num_days_from_monday() as i64)
+        }
+        LeaderboardTimeWindow::Monthly => NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?,
+        LeaderboardTimeWindow::AllTime => return None,
+    };
+
+    Some(Utc.from_utc_datetime(&start_date.and_hms_opt(0, 0, 0)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 30, 0).unwrap()
+    }
+
+    fn midnight(year: i32, month: u32, day: u32) -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn daily_window_starts_at_midnight_utc() {
+        let start = get_window_start(LeaderboardTimeWindow::Daily, at(2024, 3, 1, 23));
+
+        assert_eq!(start, midnight(2024, 3, 1));
+    }
+
+    #[test]
+    fn weekly_window_starts_on_monday() {
+        // 2024-03-03 is a Sunday, so its week began in February
+        let sunday = get_window_start(LeaderboardTimeWindow::Weekly, at(2024, 3, 3, 12));
+        let monday = get_window_start(LeaderboardTimeWindow::Weekly, at(2024, 3, 4, 0));
+
+        assert_eq!(sunday, midnight(2024, 2, 26));
+        assert_eq!(monday, midnight(2024, 3, 4));
+    }
+
+    #[test]
+    fn weekly_window_crosses_the_year() {
+        // 2021-01-01 is a Friday in ISO week 53 of 2020
+        let start = get_window_start(LeaderboardTimeWindow::Weekly, at(2021, 1, 1, 8));
+
+        assert_eq!(start, midnight(2020, 12, 28));
+    }
+
+    #[test]
+    fn monthly_window_rolls_over_at_the_first() {
+        let last_day = get_window_start(LeaderboardTimeWindow::Monthly, at(2024, 2, 29, 23));
+        let first_day = get_window_start(LeaderboardTimeWindow::Monthly, at(2024, 3, 1, 0));
+        let new_year = get_window_start(LeaderboardTimeWindow::Monthly, at(2025, 1, 1, 0));
+
+        assert_eq!(last_day, midnight(2024, 2, 1));
+        assert_eq!(first_day, midnight(2024, 3, 1));
+        assert_eq!(new_year, midnight(2025, 1, 1));
+    }
+
+    #[test]
+    fn all_time_has_no_window() {
+        assert_eq!(
+            get_window_start(LeaderboardTimeWindow::AllTime, at(2024, 3, 1, 12)),
+            None
+        );
+    }
+}
@@ -6,6 +6,17 @@ pub struct ApiResponse {
     pub message: String,
 }
 
+// Body of every error response: the usual status/message pair plus a stable,
+// machine-readable code
+#[derive(Serialize)]
+pub struct ApiErrorResponse {
+    #[serde(flatten)]
+    pub response: ApiResponse,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
 #[derive(Serialize)]
 pub struct ApiResponseWithData<T> {
     pub status: String,
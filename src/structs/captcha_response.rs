@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+// Response of the siteverify endpoints; reCAPTCHA, hCaptcha and Turnstile
+// all share this shape, with score and action only set by some of them
+#[derive(Deserialize, Serialize)]
+pub struct CaptchaResponse {
+    pub success: bool,
+    #[serde(rename = "error-codes", default)]
+    pub error_codes: Vec<String>,
+    #[serde(default)]
+    pub score: Option<f32>,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+}
@@ -4,4 +4,20 @@ use serde::{Deserialize, Serialize};
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    // Id of the server-side session the access token belongs to
+    pub sid: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestSessionClaims {
+    pub sub: String,
+    pub aud: String,
+    pub exp: usize,
+    pub test_id: String,
+    pub seed: u64,
+    pub word_count: usize,
+    pub language: String,
+    pub difficulty: String,
+    pub duration: u32,
+    pub started_at: i64,
 }
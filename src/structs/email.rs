@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct EmailVerificationConfirmation {
+    pub token: String,
+}
+
+#[derive(Serialize)]
+pub struct EmailStatus {
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
@@ -1,4 +1,5 @@
-use crate::models::user::{HighScores, Score};
+use crate::models::user::HighScores;
+use mongodb::bson::{oid::ObjectId, Bson};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -16,6 +17,63 @@ pub struct LeaderboardResponse {
     pub message: String,
     pub leaderboard: Vec<LeaderboardEntry>,
     pub total_count: i64,
+    pub next_cursor: Option<String>,
+}
+
+pub struct LeaderboardPage {
+    pub entries: Vec<LeaderboardEntry>,
+    pub next_cursor: Option<String>,
+}
+
+pub struct LeaderboardPaging {
+    pub skip: u64,
+    pub limit: u64,
+    pub after: Option<LeaderboardCursor>,
+}
+
+// Position of an entry in the leaderboard ordering (wpm, then accuracy, then
+// earliest date, then _id). Used both as a keyset pagination cursor and to
+// locate a user when computing ranks. The _id is the user's ObjectId on the
+// all-time board and the username on windowed boards.
+pub struct LeaderboardCursor {
+    pub wpm: i64,
+    pub accuracy: f64,
+    pub date: String,
+    pub id: Bson,
+}
+
+impl LeaderboardCursor {
+    pub fn encode(&self) -> String {
+        let id = match &self.id {
+            Bson::ObjectId(id) => id.to_hex(),
+            Bson::String(id) => id.clone(),
+            other => other.to_string(),
+        };
+
+        format!("{}_{}_{}_{}", self.wpm, self.accuracy, self.date, id)
+    }
+
+    pub fn decode(token: &str, window: LeaderboardTimeWindow) -> Option<Self> {
+        // The id comes last so that usernames containing '_' still decode
+        let mut parts = token.splitn(4, '_');
+        let wpm = parts.next()?.parse().ok()?;
+        let accuracy = parts.next()?.parse().ok()?;
+        let date = parts.next()?.to_string();
+        let id = parts.next()?;
+
+        let id = match window {
+            LeaderboardTimeWindow::AllTime => Bson::ObjectId(ObjectId::parse_str(id).ok()?),
+            _ if id.is_empty() => return None,
+            _ => Bson::String(id.to_string()),
+        };
+
+        Some(LeaderboardCursor {
+            wpm,
+            accuracy,
+            date,
+            id,
+        })
+    }
 }
 
 #[derive(Serialize)]
@@ -23,19 +81,117 @@ pub struct GetLeaderboardStatsRequest {
     pub timer_duration: u32,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct ScoreUpdateRequest {
-    pub duration: String,
-    pub language: String,
-    pub difficulty: String,
-    pub score: Score,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeaderboardTimeWindow {
+    Daily,
+    Weekly,
+    Monthly,
+    AllTime,
 }
 
 #[derive(Deserialize)]
 pub struct GetLeaderboardStatsQueries {
     pub timer_duration: String,
-    pub page: String,
-    pub limit: String,
+    pub page: Option<String>,
+    pub limit: Option<String>,
+    pub after: Option<String>,
+    pub window: Option<String>,
+    pub difficulty: String,
+    pub language: String,
+}
+
+#[derive(Deserialize)]
+pub struct GetUserRankQueries {
+    pub timer_duration: String,
     pub difficulty: String,
     pub language: String,
+    // Number of entries above and below the user, only used by the "around" query
+    pub range: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct UserRank {
+    pub username: String,
+    pub rank: u64,
+    pub total_count: u64,
+    pub percentile: f64,
+}
+
+#[derive(Serialize)]
+pub struct RankedLeaderboardEntry {
+    pub rank: u64,
+    #[serde(flatten)]
+    pub entry: LeaderboardEntry,
+}
+
+#[derive(Serialize)]
+pub struct AroundUserLeaderboard {
+    #[serde(flatten)]
+    pub user_rank: UserRank,
+    pub entries: Vec<RankedLeaderboardEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_cursor(id: Bson) -> LeaderboardCursor {
+        LeaderboardCursor {
+            wpm: 123,
+            accuracy: 98.5,
+            date: "2024-03-01T10:20:30.000Z".to_string(),
+            id,
+        }
+    }
+
+    #[test]
+    fn all_time_cursor_round_trips() {
+        let id = ObjectId::new();
+        let token = create_cursor(Bson::ObjectId(id)).encode();
+
+        let cursor = LeaderboardCursor::decode(&token, LeaderboardTimeWindow::AllTime).unwrap();
+
+        assert_eq!(cursor.wpm, 123);
+        assert_eq!(cursor.accuracy, 98.5);
+        assert_eq!(cursor.date, "2024-03-01T10:20:30.000Z");
+        assert_eq!(cursor.id, Bson::ObjectId(id));
+    }
+
+    #[test]
+    fn windowed_cursor_keeps_underscores_in_the_username() {
+        let token = create_cursor(Bson::String("fast_typer_1".to_string())).encode();
+
+        let cursor = LeaderboardCursor::decode(&token, LeaderboardTimeWindow::Weekly).unwrap();
+
+        assert_eq!(cursor.id, Bson::String("fast_typer_1".to_string()));
+        assert_eq!(cursor.encode(), token);
+    }
+
+    #[test]
+    fn tampered_cursors_are_rejected() {
+        let id = ObjectId::new().to_hex();
+        let tampered = [
+            format!("fast_98.5_2024-03-01_{}", id),
+            format!("123_high_2024-03-01_{}", id),
+            "123_98.5_2024-03-01_not-an-object-id".to_string(),
+            "123_98.5_2024-03-01".to_string(),
+            String::new(),
+        ];
+
+        for token in &tampered {
+            assert!(
+                LeaderboardCursor::decode(token, LeaderboardTimeWindow::AllTime).is_none(),
+                "{}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn windowed_cursor_needs_a_username() {
+        assert!(
+            LeaderboardCursor::decode("123_98.5_2024-03-01_", LeaderboardTimeWindow::Daily)
+                .is_none()
+        );
+    }
 }
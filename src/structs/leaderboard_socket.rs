@@ -0,0 +1,35 @@
+use crate::models::mode::GameMode;
+use crate::models::user::Score;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LeaderboardUpdate {
+    pub mode: GameMode,
+    pub username: String,
+    pub rank: u64,
+    pub score: Score,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LeaderboardClientMessage {
+    Subscribe {
+        language: String,
+        difficulty: String,
+        timer_duration: String,
+    },
+    Unsubscribe {
+        language: String,
+        difficulty: String,
+        timer_duration: String,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LeaderboardServerMessage {
+    Subscribed { mode: GameMode },
+    Unsubscribed { mode: GameMode },
+    LeaderboardUpdate(LeaderboardUpdate),
+    Error { message: String },
+}
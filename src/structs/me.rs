@@ -0,0 +1,13 @@
+use crate::models::user::UserSettings;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct CurrentUser {
+    pub username: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_tests: u32,
+    pub settings: UserSettings,
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
@@ -1,6 +1,15 @@
 pub mod api_response;
+pub mod captcha_response;
 pub mod claims;
+pub mod email;
+pub mod health;
 pub mod leaderboard;
+pub mod leaderboard_socket;
 pub mod login;
-pub mod recaptcha_response;
+pub mod me;
+pub mod password;
+pub mod race;
+pub mod session;
 pub mod sign_up;
+pub mod test_result;
+pub mod typing_test;
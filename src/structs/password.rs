@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    pub confirmation_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+pub struct PasswordResetConfirmation {
+    pub token: String,
+    pub password: String,
+    pub confirmation_password: String,
+}
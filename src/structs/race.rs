@@ -0,0 +1,94 @@
+use crate::models::race_result::RacePlacement;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RaceClientMessage {
+    Create {
+        language: String,
+        difficulty: String,
+        word_count: Option<usize>,
+    },
+    Join {
+        code: String,
+    },
+    Start,
+    // Number of characters of the race text typed correctly so far
+    Progress {
+        position: usize,
+    },
+    Leave,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RacePlayerState {
+    pub username: String,
+    pub progress: usize,
+    pub wpm: u32,
+    pub finished: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RaceServerMessage {
+    RoomState {
+        code: String,
+        host: String,
+        state: String,
+        language: String,
+        difficulty: String,
+        players: Vec<RacePlayerState>,
+    },
+    Countdown {
+        seconds: u64,
+    },
+    RaceStarted {
+        words: Vec<String>,
+        started_at: DateTime<Utc>,
+    },
+    Progress {
+        username: String,
+        progress: usize,
+        wpm: u32,
+    },
+    PlayerFinished {
+        username: String,
+        place: u32,
+        wpm: u32,
+    },
+    RaceFinished {
+        placements: Vec<RacePlacement>,
+    },
+    Left,
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct RaceResultEntry {
+    #[serde(rename = "_id")]
+    pub _id: String,
+    pub room_code: String,
+    pub language: String,
+    pub difficulty: String,
+    pub text_length: u32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub placements: Vec<RacePlacement>,
+}
+
+#[derive(Serialize)]
+pub struct RaceResultsPage {
+    pub races: Vec<RaceResultEntry>,
+    pub total_count: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Deserialize)]
+pub struct GetRaceResultsQueries {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
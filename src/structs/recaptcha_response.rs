@@ -1,7 +0,0 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Deserialize, Serialize)]
-pub struct RecaptchaResponse {
-    pub success: bool,
-    pub error_codes: Option<Vec<String>>,
-}
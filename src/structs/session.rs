@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct SessionEntry {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    // The session the request was made with
+    pub current: bool,
+}
+
+#[derive(Serialize)]
+pub struct RevokedSessions {
+    pub revoked_count: u64,
+}
+
+pub enum RefreshOutcome {
+    Rotated {
+        username: String,
+        session_id: String,
+        refresh_token: String,
+    },
+    // A rotated-out token was presented again; the session is now revoked
+    Reused,
+    Invalid,
+}
@@ -6,4 +6,7 @@ pub struct SignUpRequest {
     pub password: String,
     pub confirmation_password: String,
     pub token: String,
+    // Optional; a verification link is sent to it after sign-up
+    #[serde(default)]
+    pub email: Option<String>,
 }
@@ -0,0 +1,36 @@
+use crate::models::test_result::CharStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+pub struct TestResultEntry {
+    #[serde(rename = "_id")]
+    pub _id: String,
+    pub language: String,
+    pub difficulty: String,
+    pub duration: String,
+    pub wpm: u32,
+    pub raw_wpm: u32,
+    pub accuracy: f32,
+    pub timestamp: DateTime<Utc>,
+    pub char_stats: Option<CharStats>,
+}
+
+#[derive(Serialize)]
+pub struct TestResultsPage {
+    pub results: Vec<TestResultEntry>,
+    pub total_count: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Deserialize)]
+pub struct GetTestResultsQueries {
+    pub language: Option<String>,
+    pub difficulty: Option<String>,
+    pub duration: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+}
@@ -0,0 +1,46 @@
+use crate::models::test_result::CharStats;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct StartTestRequest {
+    pub language: String,
+    pub difficulty: String,
+    pub duration: String,
+}
+
+#[derive(Serialize)]
+pub struct TestSession {
+    pub test_id: String,
+    pub session_token: String,
+    pub words: Vec<String>,
+    pub language: String,
+    pub difficulty: String,
+    pub duration: u32,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Keystroke {
+    // The key as reported by the browser, e.g. "a", " " or "Backspace"
+    pub key: String,
+    // Milliseconds since the first keystroke of the test
+    pub timestamp: u64,
+}
+
+#[derive(Deserialize)]
+pub struct FinishTestRequest {
+    pub session_token: String,
+    pub typed: String,
+    pub keystrokes: Vec<Keystroke>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TestResult {
+    pub test_id: String,
+    pub wpm: u32,
+    pub raw_wpm: u32,
+    pub accuracy: f32,
+    pub char_stats: CharStats,
+    pub personal_best: bool,
+}
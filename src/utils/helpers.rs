@@ -1,7 +1,75 @@
 use crate::constants::{COLL_NAME, DB_NAME};
-use mongodb::bson::Document;
+use crate::errors::app_error::AppError;
+use chrono::{DateTime, TimeZone, Utc};
+use mongodb::bson::{DateTime as BsonDateTime, Document};
+use mongodb::error::{ErrorKind, InsertManyError, WriteFailure};
 use mongodb::{Client, Collection};
 
 pub fn get_collection(client: &Client) -> Collection<Document> {
     client.database(DB_NAME).collection(COLL_NAME)
 }
+
+pub fn get_collection_by_name(client: &Client, name: &str) -> Collection<Document> {
+    client.database(DB_NAME).collection(name)
+}
+
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+
+// True when a write was rejected by a unique index
+pub fn is_duplicate_key_error(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY_ERROR_CODE
+        }
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY_ERROR_CODE,
+        ErrorKind::InsertMany(InsertManyError {
+            write_errors: Some(write_errors),
+            ..
+        }) => write_errors
+            .iter()
+            .any(|write_error| write_error.code == DUPLICATE_KEY_ERROR_CODE),
+        _ => false,
+    }
+}
+
+// For an unordered insert_many that only failed on unique indexes, the
+// positions of the documents that were rejected; everything else went in
+pub fn get_duplicate_key_indexes(err: &mongodb::error::Error) -> Option<Vec<usize>> {
+    match err.kind.as_ref() {
+        ErrorKind::InsertMany(InsertManyError {
+            write_errors: Some(write_errors),
+            write_concern_error: None,
+            ..
+        }) if write_errors
+            .iter()
+            .all(|write_error| write_error.code == DUPLICATE_KEY_ERROR_CODE) =>
+        {
+            Some(
+                write_errors
+                    .iter()
+                    .map(|write_error| write_error.index)
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+// Number of documents before a 1-based page. Pages too far out for MongoDB
+// to skip to are rejected rather than left to overflow.
+pub fn get_page_offset(page: u64, limit: u64) -> Result<u64, AppError> {
+    page.saturating_sub(1)
+        .checked_mul(limit)
+        .filter(|offset| *offset <= i64::MAX as u64)
+        .ok_or_else(|| AppError::Validation("page is too large.".to_string()))
+}
+
+pub fn to_bson_datetime(date: DateTime<Utc>) -> BsonDateTime {
+    BsonDateTime::from_millis(date.timestamp_millis())
+}
+
+pub fn to_chrono_datetime(date: BsonDateTime) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(date.timestamp_millis())
+        .single()
+        .unwrap_or_else(Utc::now)
+}
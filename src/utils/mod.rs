@@ -1 +1,4 @@
 pub mod helpers;
+pub mod redact;
+pub mod token;
+pub mod word_generator;
@@ -0,0 +1,11 @@
+use std::fmt;
+
+// Stands in for passwords, password hashes and tokens in hand-written Debug
+// impls so that they never reach the logs
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
@@ -0,0 +1,30 @@
+use mongodb::bson::oid::ObjectId;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+const TOKEN_SECRET_BYTES: usize = 32;
+
+pub fn generate_token_secret() -> String {
+    let mut bytes = [0u8; TOKEN_SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn hash_token_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+// Refresh, reset and verification tokens are "<record id>.<secret>" so the
+// record can be found without scanning by hash
+pub fn format_token(id: &ObjectId, secret: &str) -> String {
+    format!("{}.{}", id.to_hex(), secret)
+}
+
+pub fn parse_token(token: &str) -> Option<(ObjectId, &str)> {
+    let (id, secret) = token.split_once('.')?;
+    let id = ObjectId::parse_str(id).ok()?;
+    if secret.is_empty() {
+        return None;
+    }
+    Some((id, secret))
+}
@@ -0,0 +1,50 @@
+use crate::constants::word_lists::{CHINESE_HARD, CHINESE_NORMAL, ENGLISH_HARD, ENGLISH_NORMAL};
+
+// Small deterministic PRNG (SplitMix64) so a seed always expands to the same
+// word list, independent of any external crate's algorithm choices.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+pub fn get_word_list(language: &str, difficulty: &str) -> Option<&'static [&'static str]> {
+    match (language, difficulty) {
+        ("english", "normal") => Some(ENGLISH_NORMAL),
+        ("english", "hard") => Some(ENGLISH_HARD),
+        ("chinese", "normal") => Some(CHINESE_NORMAL),
+        ("chinese", "hard") => Some(CHINESE_HARD),
+        _ => None,
+    }
+}
+
+pub fn generate_words(
+    language: &str,
+    difficulty: &str,
+    seed: u64,
+    count: usize,
+) -> Option<Vec<String>> {
+    let word_list = get_word_list(language, difficulty)?;
+    let mut rng = SplitMix64::new(seed);
+
+    let words = (0..count)
+        .map(|_| {
+            let index = (rng.next_u64() % word_list.len() as u64) as usize;
+            word_list[index].to_string()
+        })
+        .collect();
+
+    Some(words)
+}
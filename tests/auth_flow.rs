@@ -0,0 +1,226 @@
+mod common;
+
+use actix_web::{test, App};
+use common::{
+    configure_app_data, create_config, get_cookie, log_in_request, send, sign_up_request,
+    RecordingMailer, PASSWORD,
+};
+use eletypes_backend::repositories::memory_repository::InMemoryRepository;
+use eletypes_backend::routes::email_routes::configure_email_routes;
+use eletypes_backend::routes::password_routes::configure_password_routes;
+use eletypes_backend::routes::user_routes::configure_user_routes;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+#[actix_web::test]
+async fn sign_up_login_and_fetch_current_user() {
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                Arc::new(RecordingMailer::default()),
+            ))
+            .configure(configure_user_routes),
+    )
+    .await;
+
+    let (status, _) = send(&app, sign_up_request("alice", None).to_request()).await;
+    assert_eq!(status, 200);
+
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    assert!(response.status().is_success());
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie)
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, me).await;
+    assert_eq!(body["data"]["username"], "alice");
+    assert_eq!(body["data"]["completed_tests"], 0);
+}
+
+#[actix_web::test]
+async fn reused_refresh_token_revokes_the_session() {
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                Arc::new(RecordingMailer::default()),
+            ))
+            .configure(configure_user_routes),
+    )
+    .await;
+
+    send(&app, sign_up_request("alice", None).to_request()).await;
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+    let first_refresh = get_cookie(&response, "user_refresh_token");
+
+    let refresh = test::TestRequest::post()
+        .uri("/refresh_token")
+        .cookie(first_refresh.clone())
+        .to_request();
+    let response = test::call_service(&app, refresh).await;
+    assert!(response.status().is_success());
+    let second_refresh = get_cookie(&response, "user_refresh_token");
+    assert_ne!(first_refresh.value(), second_refresh.value());
+
+    // Presenting the rotated-out token again looks like a stolen token
+    let replay = test::TestRequest::post()
+        .uri("/refresh_token")
+        .cookie(first_refresh)
+        .to_request();
+    let (status, body) = send(&app, replay).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["code"], "refresh_token_reused");
+
+    // The whole session is gone, including the latest token
+    let refresh = test::TestRequest::post()
+        .uri("/refresh_token")
+        .cookie(second_refresh)
+        .to_request();
+    let (status, body) = send(&app, refresh).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["code"], "invalid_refresh_token");
+
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie)
+        .to_request();
+    let (status, _) = send(&app, me).await;
+    assert_eq!(status, 401);
+}
+
+#[actix_web::test]
+async fn verification_link_confirms_the_email_once() {
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                mailer.clone(),
+            ))
+            .configure(configure_user_routes)
+            .configure(configure_email_routes),
+    )
+    .await;
+
+    let sign_up = sign_up_request("alice", Some("Alice@Example.com")).to_request();
+    let (status, _) = send(&app, sign_up).await;
+    assert_eq!(status, 200);
+    assert_eq!(mailer.count(), 1);
+    let token = mailer.last_token();
+
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie.clone())
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, me).await;
+    assert_eq!(body["data"]["email"], "alice@example.com");
+    assert_eq!(body["data"]["email_verified"], false);
+
+    let confirm = test::TestRequest::post()
+        .uri("/email_verification/confirm")
+        .set_json(json!({ "token": token }))
+        .to_request();
+    let (status, _) = send(&app, confirm).await;
+    assert_eq!(status, 200);
+
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie)
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, me).await;
+    assert_eq!(body["data"]["email_verified"], true);
+
+    let confirm_again = test::TestRequest::post()
+        .uri("/email_verification/confirm")
+        .set_json(json!({ "token": token }))
+        .to_request();
+    let (status, body) = send(&app, confirm_again).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["code"], "invalid_verification_token");
+}
+
+#[actix_web::test]
+async fn password_reset_sets_the_password_and_signs_out_everywhere() {
+    let mailer = Arc::new(RecordingMailer::default());
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                mailer.clone(),
+            ))
+            .configure(configure_user_routes)
+            .configure(configure_email_routes)
+            .configure(configure_password_routes),
+    )
+    .await;
+
+    // Reset links only go to verified addresses
+    send(
+        &app,
+        sign_up_request("alice", Some("alice@example.com")).to_request(),
+    )
+    .await;
+    let confirm = test::TestRequest::post()
+        .uri("/email_verification/confirm")
+        .set_json(json!({ "token": mailer.last_token() }))
+        .to_request();
+    send(&app, confirm).await;
+
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+
+    let request_reset = test::TestRequest::post()
+        .uri("/password_reset/request")
+        .set_json(json!({ "username": "alice" }))
+        .to_request();
+    let (status, _) = send(&app, request_reset).await;
+    assert_eq!(status, 200);
+    mailer.wait_for(2).await;
+    let token = mailer.last_token();
+
+    let confirm_reset = test::TestRequest::post()
+        .uri("/password_reset/confirm")
+        .set_json(json!({
+            "token": token,
+            "password": "battery staple",
+            "confirmation_password": "battery staple",
+        }))
+        .to_request();
+    let (status, _) = send(&app, confirm_reset).await;
+    assert_eq!(status, 200);
+
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie)
+        .to_request();
+    let (status, _) = send(&app, me).await;
+    assert_eq!(status, 401);
+
+    let (status, _) = send(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    assert_eq!(status, 401);
+    let (status, _) = send(&app, log_in_request("alice", "battery staple").to_request()).await;
+    assert_eq!(status, 200);
+
+    // The link works once
+    let reuse = test::TestRequest::post()
+        .uri("/password_reset/confirm")
+        .set_json(json!({
+            "token": token,
+            "password": "another one",
+            "confirmation_password": "another one",
+        }))
+        .to_request();
+    let (status, body) = send(&app, reuse).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["code"], "invalid_reset_token");
+}
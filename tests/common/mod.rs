@@ -0,0 +1,154 @@
+// Each test binary compiles this module and uses only part of it
+#![allow(dead_code)]
+
+use actix_web::body::MessageBody;
+use actix_web::cookie::Cookie;
+use actix_web::dev::{Service, ServiceResponse};
+use actix_web::{rt, test, web, Error};
+use async_trait::async_trait;
+use eletypes_backend::config::app_config::AppConfig;
+use eletypes_backend::config::captcha::{load_captcha_verifier, CaptchaProvider};
+use eletypes_backend::errors::app_error::AppError;
+use eletypes_backend::repositories::email_verification_repository::EmailVerificationRepository;
+use eletypes_backend::repositories::leaderboard_repository::LeaderboardRepository;
+use eletypes_backend::repositories::memory_repository::InMemoryRepository;
+use eletypes_backend::repositories::password_reset_repository::PasswordResetRepository;
+use eletypes_backend::repositories::session_repository::SessionRepository;
+use eletypes_backend::repositories::test_result_repository::TestResultRepository;
+use eletypes_backend::repositories::user_repository::UserRepository;
+use eletypes_backend::services::leaderboard_hub::LeaderboardHub;
+use eletypes_backend::services::mailer_service::{MailMessage, Mailer};
+use eletypes_backend::services::rate_limit_service::LoginLockout;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const PASSWORD: &str = "correct horse";
+
+pub fn create_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.auth.jwt_secret = "test-secret".to_string();
+    config.captcha.provider = CaptchaProvider::Noop;
+    config
+}
+
+// Keeps sent mail so tests can follow the links in it
+#[derive(Default)]
+pub struct RecordingMailer {
+    messages: Mutex<Vec<MailMessage>>,
+}
+
+#[async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, message: &MailMessage) -> Result<(), AppError> {
+        self.messages.lock().unwrap().push(MailMessage {
+            to: message.to.clone(),
+            subject: message.subject.clone(),
+            body: message.body.clone(),
+        });
+        Ok(())
+    }
+}
+
+impl RecordingMailer {
+    pub fn count(&self) -> usize {
+        self.messages.lock().unwrap().len()
+    }
+
+    // Token of the link in the most recent message
+    pub fn last_token(&self) -> String {
+        let messages = self.messages.lock().unwrap();
+        let body = &messages.last().expect("a message was sent").body;
+        let start = body.find("token=").expect("the message has a link") + "token=".len();
+        body[start..]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    // Password reset mail goes out in the background
+    pub async fn wait_for(&self, count: usize) {
+        for _ in 0..100 {
+            if self.count() >= count {
+                return;
+            }
+            rt::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("expected {} messages, got {}", count, self.count());
+    }
+}
+
+// Registers the in-memory repository under every repository trait, along
+// with the rest of the shared state the routes expect
+pub fn configure_app_data(
+    config: AppConfig,
+    repository: Arc<InMemoryRepository>,
+    mailer: Arc<RecordingMailer>,
+) -> impl FnOnce(&mut web::ServiceConfig) {
+    move |cfg| {
+        let users: Arc<dyn UserRepository> = repository.clone();
+        let leaderboard: Arc<dyn LeaderboardRepository> = repository.clone();
+        let sessions: Arc<dyn SessionRepository> = repository.clone();
+        let results: Arc<dyn TestResultRepository> = repository.clone();
+        let verifications: Arc<dyn EmailVerificationRepository> = repository.clone();
+        let resets: Arc<dyn PasswordResetRepository> = repository;
+        let mailer: Arc<dyn Mailer> = mailer;
+
+        cfg.app_data(web::Data::from(load_captcha_verifier(&config.captcha)))
+            .app_data(web::Data::from(mailer))
+            .app_data(web::Data::new(LoginLockout::new(
+                config.rate_limit.lockout.clone(),
+            )))
+            .app_data(web::Data::new(LeaderboardHub::new(
+                config.realtime.leaderboard_push_top_n,
+            )))
+            .app_data(web::Data::new(config.modes.clone()))
+            .app_data(web::Data::from(users))
+            .app_data(web::Data::from(leaderboard))
+            .app_data(web::Data::from(sessions))
+            .app_data(web::Data::from(results))
+            .app_data(web::Data::from(verifications))
+            .app_data(web::Data::from(resets))
+            .app_data(web::Data::new(config));
+    }
+}
+
+pub fn get_cookie<B>(response: &ServiceResponse<B>, name: &str) -> Cookie<'static> {
+    response
+        .response()
+        .cookies()
+        .find(|cookie| cookie.name() == name)
+        .unwrap_or_else(|| panic!("response sets the {} cookie", name))
+        .into_owned()
+}
+
+pub fn sign_up_request(username: &str, email: Option<&str>) -> test::TestRequest {
+    test::TestRequest::post().uri("/sign_up").set_json(json!({
+        "username": username,
+        "password": PASSWORD,
+        "confirmation_password": PASSWORD,
+        "token": "captcha",
+        "email": email,
+    }))
+}
+
+pub fn log_in_request(username: &str, password: &str) -> test::TestRequest {
+    test::TestRequest::post().uri("/login").set_json(json!({
+        "username": username,
+        "password": password,
+        "token": "captcha",
+    }))
+}
+
+// Status and JSON body of a request
+pub async fn send<S, R, B>(app: &S, request: R) -> (u16, Value)
+where
+    S: Service<R, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let response = test::call_service(app, request).await;
+    let status = response.status().as_u16();
+    let body = test::read_body(response).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use actix_web::{test, App};
+use chrono::Utc;
+use common::{
+    configure_app_data, create_config, get_cookie, log_in_request, send, sign_up_request,
+    RecordingMailer, PASSWORD,
+};
+use eletypes_backend::repositories::memory_repository::InMemoryRepository;
+use eletypes_backend::routes::test_result_routes::configure_test_result_routes;
+use eletypes_backend::routes::typing_test_routes::configure_typing_test_routes;
+use eletypes_backend::routes::user_routes::configure_user_routes;
+use eletypes_backend::structs::claims::TestSessionClaims;
+use eletypes_backend::utils::word_generator::generate_words;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const DURATION_SECS: u32 = 15;
+
+// A session started a full duration ago, so the test can be finished at once
+fn create_elapsed_session(username: &str, seed: u64) -> String {
+    let started_at = Utc::now() - chrono::Duration::seconds(DURATION_SECS as i64 + 1);
+    let claims = TestSessionClaims {
+        sub: username.to_string(),
+        aud: "typing_test".to_string(),
+        exp: (Utc::now().timestamp() + 600) as usize,
+        test_id: format!("test-{}", seed),
+        seed,
+        word_count: 75,
+        language: "english".to_string(),
+        difficulty: "normal".to_string(),
+        duration: DURATION_SECS,
+        started_at: started_at.timestamp_millis(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(b"test-secret"),
+    )
+    .unwrap()
+}
+
+// The first words typed without mistakes, at an uneven human pace
+fn create_finish_request(session_token: &str, seed: u64) -> Value {
+    let words = generate_words("english", "normal", seed, 75).unwrap();
+    let typed = words[..8].join(" ");
+
+    let mut timestamp = 0;
+    let keystrokes: Vec<Value> = typed
+        .chars()
+        .enumerate()
+        .map(|(index, ch)| {
+            timestamp += 120 + (index as u64 * 37) % 90;
+            json!({ "key": ch.to_string(), "timestamp": timestamp })
+        })
+        .collect();
+
+    json!({
+        "session_token": session_token,
+        "typed": typed,
+        "keystrokes": keystrokes,
+    })
+}
+
+#[actix_web::test]
+async fn finished_test_is_scored_stored_and_only_accepted_once() {
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                Arc::new(RecordingMailer::default()),
+            ))
+            .configure(configure_user_routes)
+            .configure(configure_typing_test_routes)
+            .configure(configure_test_result_routes),
+    )
+    .await;
+
+    send(&app, sign_up_request("alice", None).to_request()).await;
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+
+    let finish_request = create_finish_request(&create_elapsed_session("alice", 42), 42);
+    let finish = test::TestRequest::post()
+        .uri("/tests/finish")
+        .cookie(access_cookie.clone())
+        .set_json(&finish_request)
+        .to_request();
+    let (status, body) = send(&app, finish).await;
+    assert_eq!(status, 200, "{}", body);
+    assert!(body["data"]["wpm"].as_u64().unwrap() > 0);
+    assert_eq!(body["data"]["accuracy"], 100.0);
+    assert_eq!(body["data"]["personal_best"], true);
+
+    let results = test::TestRequest::get()
+        .uri("/users/alice/results")
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, results).await;
+    assert_eq!(body["data"]["total_count"], 1);
+    assert_eq!(body["data"]["results"][0]["duration"], "15");
+
+    let me = test::TestRequest::get()
+        .uri("/me")
+        .cookie(access_cookie.clone())
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, me).await;
+    assert_eq!(body["data"]["completed_tests"], 1);
+
+    let resubmit = test::TestRequest::post()
+        .uri("/tests/finish")
+        .cookie(access_cookie)
+        .set_json(&finish_request)
+        .to_request();
+    let (status, body) = send(&app, resubmit).await;
+    assert_eq!(status, 409);
+    assert_eq!(body["code"], "conflict");
+}
+
+#[actix_web::test]
+async fn test_of_another_user_is_rejected() {
+    let app = test::init_service(
+        App::new()
+            .configure(configure_app_data(
+                create_config(),
+                Arc::new(InMemoryRepository::new()),
+                Arc::new(RecordingMailer::default()),
+            ))
+            .configure(configure_user_routes)
+            .configure(configure_typing_test_routes),
+    )
+    .await;
+
+    send(&app, sign_up_request("alice", None).to_request()).await;
+    let response = test::call_service(&app, log_in_request("alice", PASSWORD).to_request()).await;
+    let access_cookie = get_cookie(&response, "user_jwt_token");
+
+    let finish = test::TestRequest::post()
+        .uri("/tests/finish")
+        .cookie(access_cookie)
+        .set_json(create_finish_request(&create_elapsed_session("bob", 7), 7))
+        .to_request();
+    let (status, _) = send(&app, finish).await;
+    assert_eq!(status, 403);
+}